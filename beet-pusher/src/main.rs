@@ -5,14 +5,22 @@
 //! Proof of concept for pushing a simple beet query to VLC, with id tracking
 
 use crate::config_file::ConfigFile;
+use beets::BeetItem;
 use clap::Parser;
 use determined::Determined;
-use path_url::BaseUrl;
+use path_url::UrlMapping;
+use profile::Profile;
+use schedule::{PlaybackTiming, Schedule};
 use std::path::PathBuf;
-use todo_move_to_a_beet_lib::{query_beet, BeetItem};
-use tracing::{debug, info};
+use supervisor::{Supervisor, VlcError};
+use tracing::{debug, info, warn};
 use vlc_http::goal::TargetPlaylistItems;
 
+mod profile;
+mod schedule;
+mod supervisor;
+mod wake;
+
 #[derive(clap::Parser, Debug)]
 struct Args {
     #[clap(flatten)]
@@ -22,32 +30,16 @@ struct Args {
 }
 
 fn main() -> eyre::Result<()> {
-    const SLEEP_DURATION: std::time::Duration = std::time::Duration::from_millis(1000);
-
     tracing_subscriber::fmt::init();
 
-    // TODO delete unused diagnostic
-    if false {
-        let mut spigot = setup_spigot()?;
-        let view = spigot.view_table_default();
-        println!("{view}");
-        let rng = &mut rand::thread_rng();
-        for _ in 0..50 {
-            let peeked = spigot.peek(rng, 1)?;
-            println!("{:?}", peeked.items());
-            spigot.finalize_peeked(peeked.accept_into_inner());
-        }
-        return Ok(());
-    }
-
     let Args { auth, config_file } = Args::parse();
     let auth = vlc_http::Auth::new(auth.into())?;
 
-    let config_file = config_file.unwrap_or(PathBuf::from("beet-pusher.config.toml"));
-    let config_file = match ConfigFile::open(&config_file) {
+    let config_path = config_file.unwrap_or(PathBuf::from("beet-pusher.config.toml"));
+    let config_file = match ConfigFile::open(&config_path) {
         Ok(config_file) => config_file,
         Err(error) if error.is_missing_file() => {
-            let template_file = ConfigFile::write_template_for_file(config_file)?;
+            let template_file = ConfigFile::write_template_for_file(config_path)?;
             eyre::bail!(
                 "config file not found, wrote template to {}",
                 template_file.display()
@@ -55,26 +47,37 @@ fn main() -> eyre::Result<()> {
         }
         Err(error) => Err(error)?,
     };
-    let ConfigFile {
-        base_url,
-        publish_id_file,
-    } = config_file;
+    let mut config_file = config_file;
 
-    let now_playing_observer = |item: BeetItem| {
-        let beet_id = item.get_beet_id();
-        let path = item.get_path();
+    let make_now_playing_observer = |publish_id_file: Option<PathBuf>| {
+        move |item: BeetItem| {
+            let beet_id = item.get_beet_id();
+            let path = item.get_path();
 
-        println!("Now playing id={beet_id}: {path}");
+            println!("Now playing id={beet_id}: {path}");
 
-        publish_id_file
-            .as_ref()
-            .map(|dest| now_playing_observer::write_now_playing_file(dest, &item))
-            .transpose()?;
+            publish_id_file
+                .as_ref()
+                .map(|dest| now_playing_observer::write_now_playing_file(dest, &item))
+                .transpose()?;
 
-        Ok::<_, now_playing_observer::PublishError>(())
+            Ok::<_, now_playing_observer::PublishError>(())
+        }
     };
 
-    let spigot = setup_spigot()?;
+    let wake_rx = wake::Receiver::new();
+    let _config_watcher = wake::watch_config_file(&config_path, wake_rx.sender())?;
+    if let Some(control_address) = config_file.control_address {
+        let _control_thread = wake::spawn_control_socket(control_address, wake_rx.sender())?;
+    }
+
+    let beet = beets::Beet::new("beet");
+    let profile_name = config_file.active_profile.clone();
+    let profile = config_file
+        .get_profile(&profile_name)
+        .expect("active profile should be validated on open");
+    info!(profile = profile_name, "starting profile");
+    let spigot = profile.build_spigot(&beet)?;
 
     let rng = &mut rand::thread_rng();
     let state = vlc_http::ClientState::new();
@@ -86,10 +89,20 @@ fn main() -> eyre::Result<()> {
         client: Client { state },
         http_runner,
         determined: Determined::default(),
-        config: Config { base_url },
-        now_playing_observer: Some(now_playing_observer),
+        config: Config {
+            profile_name,
+            url_mapping: profile.url_mapping()?,
+            keep_history: profile.keep_history,
+        },
+        beet,
+        now_playing_observer: Some(make_now_playing_observer(
+            config_file.publish_id_file.clone(),
+        )),
     };
 
+    let mut schedule = Schedule::default();
+    let mut supervisor = Supervisor::default();
+
     // TODO add a "determined holder" concept, to make it easy to:
     // 1. Peek a bunch, update spigot
     // 2. Load into VLC, retrieve "after current" items
@@ -98,9 +111,44 @@ fn main() -> eyre::Result<()> {
     // ---> Prototype as a struct here, the move to bucket_spigot::order if it's generally useful
     loop {
         pusher.fill_determined()?;
-        pusher.push_playlist_update()?;
+        let delay = pusher.poll_vlc(&mut schedule, &mut supervisor)?;
 
-        std::thread::sleep(SLEEP_DURATION);
+        let Some(event) = wake_rx.wait_timeout(delay) else {
+            continue;
+        };
+        info!(?event, "woke before scheduled poll");
+        schedule.reset_backoff();
+
+        match event {
+            wake::Event::Control(wake::ControlCommand::Poll) => {}
+            wake::Event::Control(wake::ControlCommand::SwitchProfile(name)) => {
+                let Some(profile) = config_file.get_profile(&name) else {
+                    warn!(profile = name, "ignoring switch to unknown profile");
+                    continue;
+                };
+                if let Err(error) = pusher.switch_profile(&name, profile) {
+                    warn!(error = %format_args!("{error:#}"), "failed to switch profile");
+                }
+            }
+            wake::Event::Control(wake::ControlCommand::ReloadConfig)
+            | wake::Event::ConfigChanged => {
+                let new_config_file = match ConfigFile::open(&config_path) {
+                    Ok(config_file) => config_file,
+                    Err(error) => {
+                        warn!(%error, "ignoring invalid config file change");
+                        continue;
+                    }
+                };
+                if new_config_file.control_address != config_file.control_address {
+                    warn!("control_address change requires a restart");
+                }
+                pusher.apply_config(&config_file, &new_config_file);
+                pusher.now_playing_observer = Some(make_now_playing_observer(
+                    new_config_file.publish_id_file.clone(),
+                ));
+                config_file = new_config_file;
+            }
+        }
     }
 }
 
@@ -206,10 +254,6 @@ mod now_playing_observer {
     }
 }
 
-type UreqError = vlc_http::http_runner::ureq::Error;
-type ExhaustResult<'a, T> =
-    Result<<T as vlc_http::Plan>::Output<'a>, vlc_http::sync::Error<T, UreqError>>;
-
 struct BeetPusher<'a, R, F> {
     spigot: bucket_spigot::Network<BeetItem, String>,
     rng: &'a mut R,
@@ -217,18 +261,21 @@ struct BeetPusher<'a, R, F> {
     http_runner: vlc_http::http_runner::ureq::HttpRunner,
     determined: Determined<BeetItem>,
     config: Config,
+    beet: beets::Beet,
     now_playing_observer: Option<F>,
 }
 struct Client {
     state: vlc_http::ClientState,
 }
 struct Config {
-    base_url: BaseUrl,
+    profile_name: String,
+    url_mapping: UrlMapping,
+    keep_history: u16,
 }
 impl<R: rand::RngCore, F> BeetPusher<'_, R, F> {
-    fn complete_plan<T>(&mut self, query: T) -> ExhaustResult<'_, T>
+    fn complete_plan<T>(&mut self, query: T) -> Result<T::Output<'_>, VlcError>
     where
-        T: vlc_http::Plan,
+        T: vlc_http::Plan + Send + Sync + 'static,
     {
         const MAX_ENDPOINTS_PER_ACTION: usize = 100;
         let output = vlc_http::sync::complete_plan(
@@ -258,7 +305,7 @@ impl<R: rand::RngCore, F> BeetPusher<'_, R, F> {
             }
             let () = self
                 .determined
-                .modify_gen_urls(&mut self.config.base_url, |dest| {
+                .modify_gen_urls(&mut self.config.url_mapping, |dest| {
                     dest.extend(peeked.items().iter().map(|&item| item.clone()));
                 })?;
             self.spigot.finalize_peeked(peeked.accept_into_inner());
@@ -275,27 +322,134 @@ impl<R: rand::RngCore, F> BeetPusher<'_, R, F> {
         );
         Ok(())
     }
-    fn push_playlist_update<E>(&mut self) -> eyre::Result<()>
+    /// Replaces the URL mapping, regenerating the upcoming URLs
+    ///
+    /// On failure, the previous mapping remains active (and the upcoming items are discarded).
+    fn set_url_mapping(&mut self, url_mapping: UrlMapping) -> eyre::Result<()> {
+        let previous = std::mem::replace(&mut self.config.url_mapping, url_mapping);
+        // regenerate URLs for the new base
+        self.determined
+            .modify_gen_urls(&mut self.config.url_mapping, |_| ())
+            .inspect_err(|_| self.config.url_mapping = previous)?;
+        Ok(())
+    }
+    /// Replaces the spigot and settings with the specified profile
+    ///
+    /// Discards the upcoming (determined) items, but not the current playing item in VLC.
+    /// On failure, the current profile remains active.
+    fn switch_profile(&mut self, name: &str, profile: &Profile) -> eyre::Result<()> {
+        let url_mapping = profile.url_mapping()?;
+        let spigot = profile.build_spigot(&self.beet)?;
+
+        self.spigot = spigot;
+        name.clone_into(&mut self.config.profile_name);
+        self.config.keep_history = profile.keep_history;
+        self.config.url_mapping = url_mapping;
+        self.determined
+            .modify_gen_urls(&mut self.config.url_mapping, Vec::clear)?;
+
+        info!(profile = name, "switched profile");
+        Ok(())
+    }
+    /// Applies changes to the active profile from a reloaded config file
+    ///
+    /// Switches profile if the `active_profile` changed in the file, or rebuilds the spigot if the
+    /// current profile's script changed. On failure, the current settings remain active.
+    fn apply_config(&mut self, old: &ConfigFile, new: &ConfigFile) {
+        let name = if new.active_profile == old.active_profile {
+            // keep the profile selected at runtime (if any)
+            self.config.profile_name.clone()
+        } else {
+            new.active_profile.clone()
+        };
+        let Some(profile) = new.get_profile(&name) else {
+            warn!(
+                profile = name,
+                "profile missing from config file, keeping settings"
+            );
+            return;
+        };
+
+        let script_changed = old
+            .get_profile(&name)
+            .is_none_or(|old_profile| old_profile.spigot_script != profile.spigot_script);
+        if name != self.config.profile_name || script_changed {
+            if let Err(error) = self.switch_profile(&name, profile) {
+                warn!(error = %format_args!("{error:#}"), "failed to switch profile");
+            }
+        } else {
+            let result = profile
+                .url_mapping()
+                .map_err(eyre::Report::from)
+                .and_then(|url_mapping| self.set_url_mapping(url_mapping));
+            match result {
+                Ok(()) => self.config.keep_history = profile.keep_history,
+                Err(error) => {
+                    warn!(error = %format_args!("{error:#}"), "failed to apply profile, keeping settings");
+                }
+            }
+        }
+    }
+    /// Pushes the playlist update, returning the delay until the next poll
+    ///
+    /// Recoverable VLC errors reset the client state (to resync and re-push on reconnect) and
+    /// return the reconnect backoff delay.
+    fn poll_vlc<E>(
+        &mut self,
+        schedule: &mut Schedule,
+        supervisor: &mut Supervisor,
+    ) -> eyre::Result<std::time::Duration>
+    where
+        F: FnMut(BeetItem) -> Result<(), E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        match self.push_playlist_update() {
+            Ok(playback_timing) => {
+                supervisor.on_success();
+
+                let delay = schedule.next_delay(playback_timing);
+                debug!(?delay, ?playback_timing, "wait for next poll");
+                Ok(delay)
+            }
+            Err(report) => {
+                let Some(vlc_error) = report.downcast_ref::<VlcError>() else {
+                    return Err(report);
+                };
+                let Some(delay) = supervisor.on_failure(vlc_error.category()) else {
+                    return Err(report);
+                };
+                warn!(error = %format_args!("{report:#}"), ?delay, "retrying VLC");
+
+                self.client = Client {
+                    state: vlc_http::ClientState::new(),
+                };
+                Ok(delay)
+            }
+        }
+    }
+    fn push_playlist_update<E>(&mut self) -> eyre::Result<PlaybackTiming>
     where
         F: FnMut(BeetItem) -> Result<(), E>,
         E: std::error::Error + Send + Sync + 'static,
     {
         let target = TargetPlaylistItems::new()
             .set_urls(self.determined.urls().to_vec()) // FIXME cloning to vec feels so wrong...
-            .set_keep_history(5);
+            .set_keep_history(self.config.keep_history);
 
-        let action = self
-            .client
-            .state
-            .build_plan()
-            .set_playlist_and_query_matched(target);
+        let (action, query_playback) = {
+            let plan_builder = self.client.state.build_plan();
+            (
+                plan_builder.set_playlist_and_query_matched(target),
+                plan_builder.query_playback(),
+            )
+        };
 
         let output = self.complete_plan(action)?;
         let output_len = output.len();
         if output_len < self.determined.len() {
             let () = self
                 .determined
-                .modify_gen_urls(&mut self.config.base_url, |dest| {
+                .modify_gen_urls(&mut self.config.url_mapping, |dest| {
                     // FIXME this would be terrible (~N^2?) if expected len >> 2
                     while dest.len() > output_len {
                         let removed = dest.remove(0);
@@ -306,7 +460,10 @@ impl<R: rand::RngCore, F> BeetPusher<'_, R, F> {
                     Ok::<_, E>(())
                 })??;
         }
-        Ok(())
+
+        // NOTE: cached by the action above (no additional request)
+        let playback_status = self.complete_plan(query_playback)?;
+        Ok(PlaybackTiming::from(playback_status))
     }
 }
 
@@ -328,7 +485,13 @@ impl<R, F> std::fmt::Debug for BeetPusher<'_, R, F> {
             client: Client { state },
             http_runner: _,
             determined,
-            config: Config { base_url },
+            config:
+                Config {
+                    profile_name,
+                    url_mapping,
+                    keep_history,
+                },
+            beet: _,
             now_playing_observer: _,
         } = self;
         f.debug_struct("BeetPusher")
@@ -336,19 +499,54 @@ impl<R, F> std::fmt::Debug for BeetPusher<'_, R, F> {
             .field("client.state", state)
             .field("determined.items", &determined.items())
             .field("determined.urls", &determined.urls())
-            .field("config.base_url", base_url)
+            .field("config.profile_name", profile_name)
+            .field("config.url_mapping", url_mapping)
+            .field("config.keep_history", keep_history)
             .finish()
     }
 }
 
 mod config_file {
-    use crate::path_url::BaseUrl;
+    use crate::{path_url::BaseUrl, profile::Profile};
+    use std::collections::BTreeMap;
+    use tracing::warn;
+
+    /// Name of the profile for a legacy config file
+    const LEGACY_PROFILE: &str = "default";
 
     #[derive(serde::Serialize, serde::Deserialize)]
     pub(super) struct ConfigFile {
-        pub base_url: BaseUrl,
         // If specified, writes the "now playing" ID to a text file for other scripts to pickup
         pub publish_id_file: Option<std::path::PathBuf>,
+        // If specified, listens for control commands (e.g. "poll") on the TCP address
+        pub control_address: Option<std::net::SocketAddr>,
+        // Name of the profile to use on startup (or when changed in the file)
+        pub active_profile: String,
+        pub profiles: BTreeMap<String, Profile>,
+    }
+    /// Config file before named profiles, with a top-level `base_url`
+    #[derive(serde::Deserialize)]
+    struct LegacyConfigFile {
+        base_url: BaseUrl,
+        publish_id_file: Option<std::path::PathBuf>,
+        control_address: Option<std::net::SocketAddr>,
+    }
+    impl From<LegacyConfigFile> for ConfigFile {
+        fn from(legacy: LegacyConfigFile) -> Self {
+            let LegacyConfigFile {
+                base_url,
+                publish_id_file,
+                control_address,
+            } = legacy;
+            Self {
+                publish_id_file,
+                control_address,
+                active_profile: LEGACY_PROFILE.to_owned(),
+                profiles: [(LEGACY_PROFILE.to_owned(), Profile::legacy(base_url))]
+                    .into_iter()
+                    .collect(),
+            }
+        }
     }
     impl ConfigFile {
         pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, ErrorOpen> {
@@ -363,9 +561,46 @@ mod config_file {
                 .map_err(ErrorOpenKind::Read)
                 .map_err(make_error)?;
 
-            toml::from_str(&file_contents)
-                .map_err(ErrorOpenKind::Parse)
-                .map_err(make_error)
+            Self::parse(&file_contents).map_err(make_error)
+        }
+        fn parse(file_contents: &str) -> Result<Self, ErrorOpenKind> {
+            let table: toml::Table = toml::from_str(file_contents).map_err(ErrorOpenKind::Parse)?;
+
+            let config_file: Self = if table.contains_key("base_url")
+                && !table.contains_key("profiles")
+            {
+                warn!(
+                    profile = LEGACY_PROFILE,
+                    "legacy config file with top-level base_url, move it into [profiles.{LEGACY_PROFILE}] (see the template)"
+                );
+                table
+                    .try_into::<LegacyConfigFile>()
+                    .map_err(ErrorOpenKind::Parse)?
+                    .into()
+            } else {
+                table.try_into().map_err(ErrorOpenKind::Parse)?
+            };
+
+            if config_file
+                .get_profile(&config_file.active_profile)
+                .is_none()
+            {
+                return Err(ErrorOpenKind::UnknownActiveProfile(
+                    config_file.active_profile,
+                ));
+            }
+            for (name, profile) in &config_file.profiles {
+                if let Err(error) = profile.url_mapping() {
+                    return Err(ErrorOpenKind::InvalidProfile {
+                        name: name.clone(),
+                        error,
+                    });
+                }
+            }
+            Ok(config_file)
+        }
+        pub fn get_profile(&self, name: &str) -> Option<&Profile> {
+            self.profiles.get(name)
         }
     }
 
@@ -378,6 +613,11 @@ mod config_file {
     enum ErrorOpenKind {
         Read(std::io::Error),
         Parse(toml::de::Error),
+        UnknownActiveProfile(String),
+        InvalidProfile {
+            name: String,
+            error: crate::path_url::ErrorRewrite,
+        },
     }
     impl ErrorOpen {
         pub fn is_missing_file(&self) -> bool {
@@ -390,6 +630,8 @@ mod config_file {
             match &self.kind {
                 Kind::Read(error) => Some(error),
                 Kind::Parse(error) => Some(error),
+                Kind::UnknownActiveProfile(_) => None,
+                Kind::InvalidProfile { error, .. } => Some(error),
             }
         }
     }
@@ -400,6 +642,20 @@ mod config_file {
             let description = match kind {
                 Kind::Read(_) => "failed to read",
                 Kind::Parse(_) => "failed to parse",
+                Kind::UnknownActiveProfile(name) => {
+                    return write!(
+                        f,
+                        "active_profile {name:?} not found in profiles of config file: {path}",
+                        path = path.display()
+                    );
+                }
+                Kind::InvalidProfile { name, error: _ } => {
+                    return write!(
+                        f,
+                        "invalid profile {name:?} in config file: {path}",
+                        path = path.display()
+                    );
+                }
             };
 
             write!(
@@ -422,14 +678,7 @@ mod config_file {
                 kind,
             };
 
-            let default_config = Self {
-                base_url: BaseUrl(
-                    "file:///path/to/beets/folder/"
-                        .parse()
-                        .expect("default base_url should parse"),
-                ),
-                publish_id_file: Some(std::path::PathBuf::from("current_item_id.txt")),
-            };
+            let default_config = Self::template();
             let contents =
                 toml::to_string(&default_config).expect("default config should serialize");
 
@@ -439,6 +688,21 @@ mod config_file {
 
             Ok(template_file)
         }
+        fn template() -> Self {
+            let default_profile = "default".to_owned();
+            Self {
+                active_profile: default_profile.clone(),
+                profiles: [(default_profile, Profile::template())]
+                    .into_iter()
+                    .collect(),
+                publish_id_file: Some(std::path::PathBuf::from("current_item_id.txt")),
+                control_address: Some(
+                    "127.0.0.1:8089"
+                        .parse()
+                        .expect("default control_address should parse"),
+                ),
+            }
+        }
     }
 
     #[derive(Debug)]
@@ -470,6 +734,46 @@ mod config_file {
             )
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{ConfigFile, LEGACY_PROFILE};
+
+        #[test]
+        fn legacy_template() {
+            // template before named profiles
+            let contents = r#"
+base_url = "file:///path/to/beets/folder/"
+publish_id_file = "current_item_id.txt"
+control_address = "127.0.0.1:8089"
+"#;
+            let parsed = ConfigFile::parse(contents).expect("legacy config should parse");
+            assert_eq!(parsed.active_profile, LEGACY_PROFILE);
+            assert_eq!(
+                parsed.publish_id_file.as_deref(),
+                Some(std::path::Path::new("current_item_id.txt"))
+            );
+            let profile = parsed
+                .get_profile(LEGACY_PROFILE)
+                .expect("legacy profile should exist");
+            assert_eq!(profile.base_url.0.as_str(), "file:///path/to/beets/folder/");
+            assert_eq!(
+                profile.spigot_script,
+                crate::profile::Profile::template().spigot_script
+            );
+        }
+
+        #[test]
+        fn template_round_trip() {
+            let contents =
+                toml::to_string(&ConfigFile::template()).expect("template should serialize");
+            let parsed: ConfigFile = toml::from_str(&contents).expect("template should parse");
+            assert!(
+                parsed.get_profile(&parsed.active_profile).is_some(),
+                "template should contain the active profile:\n{contents}"
+            );
+        }
+    }
 }
 
 mod determined {
@@ -543,8 +847,7 @@ mod path_url {
     use super::BeetItem;
     use crate::determined::UrlSource;
 
-    // TODO add tests for Windows beet-path conversion to URL
-    #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
     #[serde(from = "url::Url")]
     pub(super) struct BaseUrl(pub(super) url::Url);
     impl BaseUrl {
@@ -562,7 +865,81 @@ mod path_url {
         }
     }
 
-    impl UrlSource<BeetItem> for BaseUrl {
+    /// Rule to replace a beet path prefix with a URL (e.g. `/mnt/music` to `smb://nas/music/`)
+    ///
+    /// Matches whole path components only. If `from` is a Windows path (drive letter or
+    /// backslashes), backslashes in the remainder of the path are treated as separators.
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    pub(super) struct PathRewrite {
+        pub from: String,
+        pub to: url::Url,
+    }
+    impl PathRewrite {
+        fn is_windows(&self) -> bool {
+            let mut chars = self.from.chars();
+            let drive_letter = matches!(
+                (chars.next(), chars.next()),
+                (Some(letter), Some(':')) if letter.is_ascii_alphabetic()
+            );
+            drive_letter || self.from.contains('\\')
+        }
+        fn is_separator(&self, c: char) -> bool {
+            c == '/' || (c == '\\' && self.is_windows())
+        }
+        /// Returns the remainder of the path after the `from` prefix, if it matches
+        fn strip_prefix<'a>(&self, path: &'a str) -> Option<&'a str> {
+            let rest = path.strip_prefix(&self.from)?;
+            let at_boundary = rest.is_empty()
+                || self.from.ends_with(|c| self.is_separator(c))
+                || rest.starts_with(|c| self.is_separator(c));
+            at_boundary.then(|| rest.trim_start_matches(|c| self.is_separator(c)))
+        }
+    }
+
+    /// Converts beet paths to URLs using the first matching [`PathRewrite`], falling back to
+    /// joining the path onto the [`BaseUrl`]
+    #[derive(Clone, Debug, PartialEq)]
+    pub(super) struct UrlMapping {
+        rewrites: Vec<PathRewrite>,
+        base_url: BaseUrl,
+    }
+    impl UrlMapping {
+        /// Validates the rewrite rules, in order of precedence
+        pub fn new(base_url: BaseUrl, rewrites: Vec<PathRewrite>) -> Result<Self, ErrorRewrite> {
+            let mut valid: Vec<PathRewrite> = Vec::with_capacity(rewrites.len());
+            for (index, mut rewrite) in rewrites.into_iter().enumerate() {
+                let make_error = |kind| ErrorRewrite {
+                    index,
+                    from: rewrite.from.clone(),
+                    kind,
+                };
+                if rewrite.from.is_empty() {
+                    return Err(make_error(ErrorRewriteKind::EmptyFrom));
+                }
+                if rewrite.to.cannot_be_a_base() {
+                    return Err(make_error(ErrorRewriteKind::CannotBeABase));
+                }
+                if let Some(shadowed_by) = valid
+                    .iter()
+                    .position(|earlier| earlier.strip_prefix(&rewrite.from).is_some())
+                {
+                    return Err(make_error(ErrorRewriteKind::Shadowed { shadowed_by }));
+                }
+                // ensure `to` is a "folder", so the remainder is joined (not replacing the last segment)
+                if !rewrite.to.path().ends_with('/') {
+                    let path = format!("{}/", rewrite.to.path());
+                    rewrite.to.set_path(&path);
+                }
+                valid.push(rewrite);
+            }
+            Ok(Self {
+                rewrites: valid,
+                base_url,
+            })
+        }
+    }
+
+    impl UrlSource<BeetItem> for UrlMapping {
         type Error = ErrorBeetPath;
 
         fn get_url(&mut self, item: &BeetItem) -> Result<url::Url, ErrorBeetPath> {
@@ -578,16 +955,36 @@ mod path_url {
                 .add(b'#')
                 .add(b'?')
                 .add(b'{')
-                .add(b'}');
-
-            let base_url = &self.0;
+                .add(b'}')
+                // literal in file names, not escapes
+                .add(b'%')
+                // separator for "special" URL schemes (e.g. `file`, `http`)
+                .add(b'\\');
 
             let path = item.get_path();
-            let path = path.strip_prefix('/').unwrap_or(path);
-            let path_percentencoded = percent_encoding::utf8_percent_encode(path, PATH).to_string();
-            let path = &path_percentencoded;
-
-            let url = base_url.join(path).map_err(|error| ErrorBeetPath {
+            let (base_url, relative_path) = self
+                .rewrites
+                .iter()
+                .find_map(|rewrite| {
+                    let rest = rewrite.strip_prefix(path)?;
+                    let rest = if rewrite.is_windows() {
+                        rest.replace('\\', "/")
+                    } else {
+                        rest.to_owned()
+                    };
+                    Some((&rewrite.to, rest))
+                })
+                .unwrap_or_else(|| {
+                    let path = path.strip_prefix('/').unwrap_or(path);
+                    (&self.base_url.0, path.to_owned())
+                });
+
+            let path_percentencoded =
+                percent_encoding::utf8_percent_encode(&relative_path, PATH).to_string();
+            // relative reference, so a colon in the first segment is not parsed as a scheme
+            let path = format!("./{path_percentencoded}");
+
+            let url = base_url.join(&path).map_err(|error| ErrorBeetPath {
                 item: item.clone(),
                 error,
             })?;
@@ -638,10 +1035,53 @@ mod path_url {
         }
     }
 
+    #[derive(Debug)]
+    pub(super) struct ErrorRewrite {
+        index: usize,
+        from: String,
+        kind: ErrorRewriteKind,
+    }
+    #[derive(Debug)]
+    enum ErrorRewriteKind {
+        EmptyFrom,
+        CannotBeABase,
+        Shadowed { shadowed_by: usize },
+    }
+    impl std::error::Error for ErrorRewrite {}
+    impl std::fmt::Display for ErrorRewrite {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let Self { index, from, kind } = self;
+            write!(f, "invalid path rewrite #{index} from {from:?}: ")?;
+            match kind {
+                ErrorRewriteKind::EmptyFrom => write!(f, "empty prefix"),
+                ErrorRewriteKind::CannotBeABase => write!(f, "target URL cannot be a base"),
+                ErrorRewriteKind::Shadowed { shadowed_by } => {
+                    write!(f, "never used, shadowed by rewrite #{shadowed_by}")
+                }
+            }
+        }
+    }
+
     #[cfg(test)]
     mod tests {
-        use super::BaseUrl;
-        use crate::{determined::UrlSource as _, todo_move_to_a_beet_lib::BeetItem};
+        use super::{BaseUrl, PathRewrite, UrlMapping};
+        use crate::{determined::UrlSource as _, BeetItem};
+
+        fn mapping(rewrites: &[(&str, &str)]) -> UrlMapping {
+            let rewrites = rewrites
+                .iter()
+                .map(|&(from, to)| PathRewrite {
+                    from: from.to_owned(),
+                    to: to.parse().expect("test rewrite url valid"),
+                })
+                .collect();
+            let base = BaseUrl("file:///some/base/".parse().expect("test base url valid"));
+            UrlMapping::new(base, rewrites).expect("test rewrites valid")
+        }
+        fn get_url(mapping: &mut UrlMapping, path: &str) -> url::Url {
+            let item = BeetItem::new(0, path.to_owned());
+            mapping.get_url(&item).expect("test item url valid")
+        }
 
         #[test]
         fn beet_path_not_fragment() {
@@ -649,10 +1089,7 @@ mod path_url {
                 "/path/to/file_containing_#_sign.txt",
                 "/path/to/file that contains #hash tag signs and other symbols {},%$#%#$@#?!@",
             ] {
-                let item = BeetItem::test_creation(0, input.to_string());
-                let mut base = BaseUrl("file:///some/base/".parse().expect("test base url valid"));
-
-                let result = base.get_url(&item).expect("test item url valid");
+                let result = get_url(&mut mapping(&[]), input);
                 assert_eq!(
                     result.fragment(),
                     None,
@@ -660,283 +1097,86 @@ mod path_url {
                 );
             }
         }
-    }
-}
-
-fn setup_spigot() -> eyre::Result<bucket_spigot::Network<BeetItem, String>> {
-    use bucket_spigot::{
-        order::OrderType,
-        path::{Path, PathRef},
-        ModifyCmd, Network,
-    };
-
-    let mut spigot = Network::default();
-    let init_commands = {
-        let root: Path = ".".parse().expect("valid path .");
-        let split: Path = ".0".parse().expect("valid path .0");
-        let bucket1: Path = ".0.0".parse().expect("valid path .0.0");
-        let bucket2: Path = ".0.1".parse().expect("valid path .0.1");
-
-        // TODO: use the Network creation script... Luke!
-        vec![
-            ModifyCmd::AddJoint { parent: root },
-            ModifyCmd::AddBucket {
-                parent: split.clone(),
-            },
-            ModifyCmd::AddBucket { parent: split },
-            ModifyCmd::SetFilters {
-                path: bucket1.clone(),
-                new_filters: [
-                    //
-                    "added:2020..",
-                    "grouping::^$",
-                ]
-                .into_iter()
-                .map(str::to_owned)
-                .collect(),
-            },
-            ModifyCmd::SetOrderType {
-                path: bucket1,
-                new_order_type: OrderType::Shuffle,
-            },
-            ModifyCmd::SetFilters {
-                path: bucket2.clone(),
-                new_filters: [
-                    //
-                    "grouping::1|2|3|4|5",
-                    "has_lyrics::^$",
-                ]
-                .into_iter()
-                .map(str::to_owned)
-                .collect(),
-            },
-            ModifyCmd::SetOrderType {
-                path: bucket2,
-                new_order_type: OrderType::Shuffle,
-            },
-        ]
-    };
-    for cmd in init_commands {
-        spigot.modify(cmd)?;
-    }
-
-    let buckets: Vec<_> = spigot
-        .get_buckets_needing_fill()
-        .map(PathRef::to_owned)
-        .collect();
-
-    for bucket in buckets {
-        let filters = spigot
-            .get_filters(bucket.as_ref())
-            .expect("path should be valid for bucket needing fill")
-            .into_iter()
-            .flat_map(|filter_set| filter_set.iter().cloned());
-        let new_contents = query_beet(filters)?;
-        info!("fill bucket {bucket} with {} items", new_contents.len());
-        spigot.modify(ModifyCmd::FillBucket {
-            bucket,
-            new_contents,
-        })?;
-    }
-
-    Ok(spigot)
-}
-
-// TODO move to a beet lib, likely also with BeetItem.url -> url::Url logic as well (see `mod path_url`)
-mod todo_move_to_a_beet_lib {
-    pub use self::beet_item::BeetItem;
-    use std::{borrow::Cow, io::BufRead, process::Command};
-    use tracing::{debug, trace};
-
-    pub(super) fn query_beet(
-        filters: impl Iterator<Item = String>,
-    ) -> Result<Vec<BeetItem>, Error> {
-        let make_error = |kind| Error { kind };
-
-        debug!("spawn `beet` command");
-
-        let mut command = Command::new("beet");
-        command
-            //
-            .arg("ls")
-            .arg("-f")
-            .arg("$id=$path")
-            .args(filters);
-
-        trace!(?command);
-
-        let output = command
-            .output()
-            .map_err(ErrorKind::Spawn)
-            .map_err(make_error)?;
-
-        if !output.stderr.is_empty() {
-            return Err(make_error(ErrorKind::Stderr {
-                stderr_str: String::from_utf8_lossy(&output.stderr).to_string(),
-            }));
-        }
-
-        if !output.status.success() {
-            return Err(make_error(ErrorKind::ExitFail {
-                code: output.status.code(),
-                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            }));
-        }
-
-        debug!("parse `beet` output ({} bytes)", output.stdout.len());
-
-        output
-            .stdout
-            .lines()
-            .map(|line| {
-                let line = line.map_err(ErrorKind::Read)?;
-                line.parse()
-                    .map_err(|error| ErrorKind::InvalidLine { line, error })
-            })
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(make_error)
-    }
 
-    #[derive(Debug)]
-    pub(super) struct Error {
-        kind: ErrorKind,
-    }
-    #[derive(Debug)]
-    enum ErrorKind {
-        Spawn(std::io::Error),
-        Read(std::io::Error),
-        Stderr {
-            stderr_str: String,
-        },
-        ExitFail {
-            code: Option<i32>,
-            stdout: String,
-        },
-        InvalidLine {
-            line: String,
-            error: beet_item::Error,
-        },
-    }
-    impl std::error::Error for Error {
-        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-            use ErrorKind as E;
-            match &self.kind {
-                E::Spawn(error) | E::Read(error) => Some(error),
-                E::Stderr { stderr_str: _ } | E::ExitFail { .. } => None,
-                E::InvalidLine { error, .. } => Some(error),
-            }
-        }
-    }
-    impl std::fmt::Display for Error {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            #[rustfmt::skip]
-            fn funnel<'a, F: Fn(usize, &'a str) -> Cow<'a, str>>(f: F) -> F { f }
-            let max_len = funnel(|max, raw_input: &str| {
-                if raw_input.len() > max {
-                    Cow::Owned(format!("{} ...", &raw_input[..max]))
-                } else {
-                    Cow::Borrowed(raw_input)
-                }
-            });
-            let (description, details) = match &self.kind {
-                ErrorKind::Spawn(_) => ("failed to spawn", None),
-                ErrorKind::Read(_) => ("failed to read from", None),
-                ErrorKind::Stderr { stderr_str } => {
-                    ("stderr output from", Some(Cow::Borrowed(&**stderr_str)))
-                }
-                ErrorKind::ExitFail { code, stdout } => {
-                    let stdout = max_len(200, stdout);
-                    let details = match code {
-                        Some(code) => Cow::Owned(format!("[code {code}] {stdout}")),
-                        None => stdout,
-                    };
-                    ("failure status code from", Some(details))
-                }
-                ErrorKind::InvalidLine { line, error: _ } => {
-                    let line = max_len(80, line);
-                    ("invalid output line from", Some(line))
-                }
-            };
-            write!(f, "{description} beet command")?;
-            if let Some(details) = details {
-                write!(f, ": {details}")?;
+        #[test]
+        fn beet_path_percent_encoding_round_trip() {
+            for input in [
+                "/music/file_containing_#_sign.mp3",
+                "/music/question?.mp3",
+                "/music/100%25 literal percent.mp3",
+                "/music/%E2%82%AC not an escape.mp3",
+                "/music/back\\slash.mp3",
+                "/music/ünïcödé 🎵/ファイル.flac",
+                "/music/semi;colon, comma & ampersand = equals+plus.ogg",
+                "/music/{braces} [brackets] <angles> `tick` \"quote\" 'apos'.mp3",
+                "/music/tab\tand\nnewline.mp3",
+                "/a:colon in first segment.mp3",
+            ] {
+                let result = get_url(&mut mapping(&[]), input);
+                assert_eq!(result.query(), None, "should not have query for {input:?}");
+                assert_eq!(result.fragment(), None, "no fragment for {input:?}");
+                let expected = format!("/some/base{input}");
+                assert_eq!(
+                    result.to_file_path().ok(),
+                    Some(expected.into()),
+                    "should round trip {input:?} as {result}"
+                );
             }
-            Ok(())
         }
-    }
 
-    mod beet_item {
-        use std::str::FromStr;
-
-        const SEPARATOR: &str = "=";
-
-        #[derive(Clone, Debug)]
-        pub struct BeetItem {
-            beet_id: u64,
-            // NOTE: not `PathBuf` because we already entered UTF-8 land by parsing Beet output
-            //       The string may need further modifications to represent a real path
-            path: String,
-        }
-        impl BeetItem {
-            pub fn get_beet_id(&self) -> u64 {
-                self.beet_id
-            }
-            pub fn get_path(&self) -> &str {
-                &self.path
-            }
-            #[cfg(test)]
-            pub(crate) fn test_creation(beet_id: u64, path: String) -> Self {
-                Self { beet_id, path }
-            }
-        }
-        impl FromStr for BeetItem {
-            type Err = Error;
-            fn from_str(s: &str) -> Result<Self, Self::Err> {
-                let Some((beet_id, path)) = s.split_once(SEPARATOR) else {
-                    return Err(Error {
-                        kind: ErrorKind::MissingSeparator,
-                    });
-                };
-                let beet_id = beet_id
-                    .parse()
-                    .map_err(ErrorKind::InvalidId)
-                    .map_err(|kind| Error { kind })?;
-                let path = path.into();
-                Ok(Self { beet_id, path })
-            }
-        }
-        impl std::fmt::Display for BeetItem {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                let Self { beet_id, path } = self;
-                write!(f, "{beet_id}{SEPARATOR}{path}")
+        #[test]
+        fn rewrite_first_match() {
+            let mut uut = mapping(&[
+                ("/mnt/music/lossless", "smb://nas/flac"),
+                ("/mnt/music", "smb://nas/music/"),
+                ("C:\\", "file:///C:/"),
+                ("D:", "file:///D:/"),
+            ]);
+            let cases = [
+                ("/mnt/music/a b.mp3", "smb://nas/music/a%20b.mp3"),
+                ("/mnt/music/lossless/x.flac", "smb://nas/flac/x.flac"),
+                // component boundary
+                (
+                    "/mnt/musicals/a.mp3",
+                    "file:///some/base/mnt/musicals/a.mp3",
+                ),
+                // windows separators
+                ("C:\\Music\\#1 hit.mp3", "file:///C:/Music/%231%20hit.mp3"),
+                ("D:\\Other\\a.mp3", "file:///D:/Other/a.mp3"),
+                // fallback
+                ("/home/music/a.mp3", "file:///some/base/home/music/a.mp3"),
+            ];
+            for (input, expected) in cases {
+                assert_eq!(get_url(&mut uut, input).as_str(), expected, "{input:?}");
             }
         }
 
-        #[derive(Debug)]
-        pub struct Error {
-            kind: ErrorKind,
-        }
-        #[derive(Debug)]
-        enum ErrorKind {
-            MissingSeparator,
-            InvalidId(std::num::ParseIntError),
-        }
-        impl std::error::Error for Error {
-            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-                match &self.kind {
-                    ErrorKind::MissingSeparator => None,
-                    ErrorKind::InvalidId(error) => Some(error),
-                }
-            }
-        }
-        impl std::fmt::Display for Error {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                let description = match self.kind {
-                    ErrorKind::MissingSeparator => "missing separator",
-                    ErrorKind::InvalidId(_) => "invalid id number",
-                };
-                write!(f, "{description}")
-            }
+        #[test]
+        fn rewrite_validation() {
+            let check = |rewrites: &[(&str, &str)], expected: &str| {
+                let rewrites = rewrites
+                    .iter()
+                    .map(|&(from, to)| PathRewrite {
+                        from: from.to_owned(),
+                        to: to.parse().expect("test rewrite url valid"),
+                    })
+                    .collect();
+                let base = BaseUrl("file:///".parse().expect("test base url valid"));
+                let error = UrlMapping::new(base, rewrites).expect_err("should be invalid");
+                assert_eq!(error.to_string(), expected);
+            };
+            check(
+                &[("", "file:///")],
+                "invalid path rewrite #0 from \"\": empty prefix",
+            );
+            check(
+                &[("/a", "mailto:someone@example.com")],
+                "invalid path rewrite #0 from \"/a\": target URL cannot be a base",
+            );
+            check(
+                &[("/mnt", "file:///x/"), ("/mnt/music", "file:///y/")],
+                "invalid path rewrite #1 from \"/mnt/music\": never used, shadowed by rewrite #0",
+            );
         }
     }
 }
@@ -0,0 +1,127 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Named sets of spigot network, base URL and history settings, selectable at runtime
+
+use crate::path_url::{BaseUrl, ErrorRewrite, PathRewrite, UrlMapping};
+use beets::BeetItem;
+use bucket_spigot::{path::PathRef, ModifyCmd, Network};
+use tracing::info;
+
+/// Default number of played items to keep in the VLC playlist
+const DEFAULT_KEEP_HISTORY: u16 = 5;
+
+/// Settings for one named profile (e.g. "work", "dinner", "party")
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    /// Base URL for the beet item paths (when no `path_rewrites` match)
+    pub base_url: BaseUrl,
+    /// Prefix rewrites for the beet item paths, first match wins
+    #[serde(default)]
+    pub path_rewrites: Vec<PathRewrite>,
+    /// Number of played items to keep in the VLC playlist
+    #[serde(default = "default_keep_history")]
+    pub keep_history: u16,
+    /// Commands to build the spigot network, one [`bucket_spigot::clap::ModifyCmd`] per line
+    ///
+    /// Buckets are filled by `beet` queries, using the filters on the path to each bucket.
+    pub spigot_script: String,
+}
+fn default_keep_history() -> u16 {
+    DEFAULT_KEEP_HISTORY
+}
+/// Spigot network used before profiles were configurable
+fn default_spigot_script() -> String {
+    [
+        "add-joint .",
+        "add-bucket .0",
+        "add-bucket .0",
+        "set-filters .0.0 added:2020.. grouping::^$",
+        "set-order-type .0.0 shuffle",
+        "set-filters .0.1 grouping::1|2|3|4|5 has_lyrics::^$",
+        "set-order-type .0.1 shuffle",
+        "",
+    ]
+    .join("\n")
+}
+impl Profile {
+    /// Builds the spigot network, filling all buckets from `beet`
+    pub fn build_spigot(&self, beet: &beets::Beet) -> eyre::Result<Network<BeetItem, String>> {
+        let mut spigot = self.parse_spigot_script()?;
+
+        let buckets: Vec<_> = spigot
+            .get_buckets_needing_fill()
+            .map(PathRef::to_owned)
+            .collect();
+
+        for bucket in buckets {
+            let filters = spigot
+                .get_filters(bucket.as_ref())
+                .expect("path should be valid for bucket needing fill")
+                .into_iter()
+                .flat_map(|filter_set| filter_set.iter().cloned());
+            let new_contents = beet.query(filters)?;
+            info!("fill bucket {bucket} with {} items", new_contents.len());
+            spigot.modify(ModifyCmd::FillBucket {
+                bucket,
+                new_contents,
+            })?;
+        }
+
+        Ok(spigot)
+    }
+    /// Returns the validated mapping from beet paths to URLs
+    pub fn url_mapping(&self) -> Result<UrlMapping, ErrorRewrite> {
+        UrlMapping::new(self.base_url.clone(), self.path_rewrites.clone())
+    }
+    fn parse_spigot_script(
+        &self,
+    ) -> Result<Network<BeetItem, String>, bucket_spigot::clap::NetworkScriptError> {
+        Network::from_commands_str(&self.spigot_script)
+    }
+    /// Returns an example profile, for the config file template
+    pub fn template() -> Self {
+        Self {
+            base_url: BaseUrl(
+                "file:///path/to/beets/folder/"
+                    .parse()
+                    .expect("default base_url should parse"),
+            ),
+            path_rewrites: vec![PathRewrite {
+                from: "/mnt/music".to_owned(),
+                to: "smb://nas/music/"
+                    .parse()
+                    .expect("default path_rewrites should parse"),
+            }],
+            keep_history: DEFAULT_KEEP_HISTORY,
+            spigot_script: default_spigot_script(),
+        }
+    }
+    /// Returns the profile equivalent to a legacy config file (before profiles), which used a
+    /// fixed spigot network
+    pub fn legacy(base_url: BaseUrl) -> Self {
+        Self {
+            base_url,
+            path_rewrites: vec![],
+            keep_history: DEFAULT_KEEP_HISTORY,
+            spigot_script: default_spigot_script(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profile;
+
+    #[test]
+    fn template_script_valid() {
+        let mut spigot = Profile::template()
+            .parse_spigot_script()
+            .expect("template script should parse");
+        let mut buckets: Vec<_> = spigot
+            .get_buckets_needing_fill()
+            .map(|path| path.to_string())
+            .collect();
+        buckets.sort();
+        assert_eq!(buckets, [".0.0", ".0.1"]);
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Timing of the next VLC poll, derived from the latest [`PlaybackStatus`]
+
+use std::time::Duration;
+use vlc_http::response::{playback::Mode, PlaybackStatus};
+
+/// Shortest delay between polls, to avoid hammering VLC at a track boundary
+const MIN_DELAY: Duration = Duration::from_millis(250);
+/// Longest delay while playing, to notice external changes (e.g. user skipping tracks)
+const MAX_PLAYING_DELAY: Duration = Duration::from_secs(10);
+/// Extra time after the expected track end, for VLC to advance to the next item
+const TRACK_END_MARGIN: Duration = Duration::from_millis(500);
+/// First delay while paused or stopped
+const IDLE_DELAY_START: Duration = Duration::from_secs(2);
+/// Longest delay while paused or stopped
+const IDLE_DELAY_MAX: Duration = Duration::from_secs(30);
+
+/// Summary of the [`PlaybackStatus`] fields relevant for scheduling
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaybackTiming {
+    mode: Mode,
+    /// Remaining time in the current item (at normal playback rate), if known
+    remaining: Option<Duration>,
+    rate_ratio: f64,
+}
+impl From<&PlaybackStatus> for PlaybackTiming {
+    fn from(status: &PlaybackStatus) -> Self {
+        #[expect(clippy::cast_precision_loss)] // durations are far below 2^52 seconds
+        let duration_secs = status.duration_secs as f64;
+        let remaining = (duration_secs > 0.0).then(|| {
+            let remaining_fraction = (1.0 - status.position_fraction).clamp(0.0, 1.0);
+            Duration::from_secs_f64(duration_secs * remaining_fraction)
+        });
+        Self {
+            mode: status.mode,
+            remaining,
+            rate_ratio: status.rate_ratio,
+        }
+    }
+}
+
+/// Decides the delay until the next poll, backing off while VLC is idle
+#[derive(Debug, Default)]
+pub struct Schedule {
+    /// Delay for the next idle poll (`None` if the last poll was not idle)
+    idle_delay: Option<Duration>,
+}
+impl Schedule {
+    /// Returns the delay until the next poll, for the latest playback timing
+    pub fn next_delay(&mut self, timing: PlaybackTiming) -> Duration {
+        let PlaybackTiming {
+            mode,
+            remaining,
+            rate_ratio,
+        } = timing;
+        match mode {
+            Mode::Playing => {
+                self.idle_delay = None;
+
+                let remaining = remaining.map(|remaining| {
+                    if rate_ratio > 0.0 {
+                        remaining.div_f64(rate_ratio)
+                    } else {
+                        remaining
+                    }
+                });
+                remaining
+                    .map_or(MAX_PLAYING_DELAY, |remaining| remaining + TRACK_END_MARGIN)
+                    .clamp(MIN_DELAY, MAX_PLAYING_DELAY)
+            }
+            Mode::Paused | Mode::Stopped => {
+                let delay = self.idle_delay.unwrap_or(IDLE_DELAY_START);
+                self.idle_delay = Some((delay * 2).min(IDLE_DELAY_MAX));
+                delay
+            }
+        }
+    }
+    /// Restarts the idle backoff, e.g. after an external event that may change playback
+    pub fn reset_backoff(&mut self) {
+        self.idle_delay = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mode, PlaybackTiming, Schedule};
+    use std::time::Duration;
+
+    fn timing(mode: Mode, remaining_secs: Option<u64>) -> PlaybackTiming {
+        PlaybackTiming {
+            mode,
+            remaining: remaining_secs.map(Duration::from_secs),
+            rate_ratio: 1.0,
+        }
+    }
+
+    #[test]
+    fn playing_waits_for_track_end() {
+        let mut uut = Schedule::default();
+        assert_eq!(
+            uut.next_delay(timing(Mode::Playing, Some(3))),
+            Duration::from_millis(3_500)
+        );
+        // capped, to notice external changes
+        assert_eq!(
+            uut.next_delay(timing(Mode::Playing, Some(300))),
+            Duration::from_secs(10)
+        );
+        // unknown length
+        assert_eq!(
+            uut.next_delay(timing(Mode::Playing, None)),
+            Duration::from_secs(10)
+        );
+        // at the very end
+        assert_eq!(
+            uut.next_delay(timing(Mode::Playing, Some(0))),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn playing_scales_by_rate() {
+        let mut uut = Schedule::default();
+        let fast = PlaybackTiming {
+            rate_ratio: 2.0,
+            ..timing(Mode::Playing, Some(4))
+        };
+        assert_eq!(uut.next_delay(fast), Duration::from_millis(2_500));
+    }
+
+    #[test]
+    fn idle_backs_off() {
+        let mut uut = Schedule::default();
+        let delays: Vec<_> = [
+            Mode::Paused,
+            Mode::Paused,
+            Mode::Stopped,
+            Mode::Paused,
+            Mode::Paused,
+            Mode::Paused,
+            Mode::Paused,
+        ]
+        .into_iter()
+        .map(|mode| uut.next_delay(timing(mode, Some(100))).as_secs())
+        .collect();
+        assert_eq!(delays, [2, 4, 8, 16, 30, 30, 30]);
+
+        // playing resets
+        uut.next_delay(timing(Mode::Playing, Some(100)));
+        assert_eq!(
+            uut.next_delay(timing(Mode::Paused, Some(100))),
+            Duration::from_secs(2)
+        );
+
+        // explicit reset
+        uut.reset_backoff();
+        assert_eq!(
+            uut.next_delay(timing(Mode::Stopped, None)),
+            Duration::from_secs(2)
+        );
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Supervises the connection to VLC, retrying with exponential backoff while VLC is unavailable
+
+use std::time::Duration;
+use tracing::{info, warn};
+use vlc_http::http_runner::ureq::ErrorCategory;
+
+/// First delay after losing the connection
+const BACKOFF_START: Duration = Duration::from_secs(1);
+/// Longest delay between reconnect attempts
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+type UreqError = vlc_http::http_runner::ureq::Error;
+
+/// Failure communicating with VLC, classified for recovery
+#[derive(Debug)]
+pub struct VlcError {
+    category: ErrorCategory,
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+impl VlcError {
+    pub fn category(&self) -> ErrorCategory {
+        self.category
+    }
+}
+impl<T> From<vlc_http::sync::Error<T, UreqError>> for VlcError
+where
+    T: vlc_http::Plan + Send + Sync + 'static,
+{
+    fn from(error: vlc_http::sync::Error<T, UreqError>) -> Self {
+        let category = error
+            .endpoint_error()
+            .map_or(ErrorCategory::BadResponse, UreqError::category);
+        Self {
+            category,
+            source: Box::new(error),
+        }
+    }
+}
+impl std::error::Error for VlcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+impl std::fmt::Display for VlcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self.category {
+            ErrorCategory::Unauthorized => "unauthorized (check the VLC password)",
+            ErrorCategory::Unreachable => "unreachable",
+            ErrorCategory::BadResponse => "bad response",
+            ErrorCategory::Unsupported => "unsupported (check the VLC scheme)",
+            _ => "failure",
+        };
+        write!(f, "VLC {description}")
+    }
+}
+
+/// State of the connection to VLC
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConnectionState {
+    /// No successful poll yet
+    Connecting,
+    Connected,
+    Disconnected {
+        category: ErrorCategory,
+        attempts: u32,
+    },
+}
+
+/// Tracks the connection state and decides the retry delay after failures
+#[derive(Debug)]
+pub struct Supervisor {
+    state: ConnectionState,
+    backoff: Duration,
+}
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self {
+            state: ConnectionState::Connecting,
+            backoff: BACKOFF_START,
+        }
+    }
+}
+impl Supervisor {
+    /// Records a successful poll
+    pub fn on_success(&mut self) {
+        match self.state {
+            ConnectionState::Connecting => info!("connected to VLC"),
+            ConnectionState::Connected => {}
+            ConnectionState::Disconnected { category, attempts } => {
+                info!(?category, attempts, "reconnected to VLC");
+            }
+        }
+        self.state = ConnectionState::Connected;
+        self.backoff = BACKOFF_START;
+    }
+    /// Records a failed poll, returning the delay before retrying
+    ///
+    /// Returns `None` if the failure is not recoverable by retrying
+    pub fn on_failure(&mut self, category: ErrorCategory) -> Option<Duration> {
+        if category == ErrorCategory::Unauthorized {
+            // password is fixed for the process lifetime, retrying cannot help
+            warn!(?category, "VLC rejected credentials, giving up");
+            return None;
+        }
+        if category == ErrorCategory::Unsupported {
+            // configuration is fixed for the process lifetime, retrying cannot help
+            warn!(?category, "VLC connection unsupported, giving up");
+            return None;
+        }
+
+        let attempts = match self.state {
+            ConnectionState::Connecting | ConnectionState::Connected => {
+                warn!(?category, from = ?self.state, "disconnected from VLC");
+                1
+            }
+            ConnectionState::Disconnected {
+                category: prev_category,
+                attempts,
+            } => {
+                if prev_category != category {
+                    warn!(from = ?prev_category, to = ?category, attempts, "VLC failure changed");
+                }
+                attempts.saturating_add(1)
+            }
+        };
+        self.state = ConnectionState::Disconnected { category, attempts };
+
+        let delay = self.backoff;
+        self.backoff = (self.backoff * 2).min(BACKOFF_MAX);
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionState, ErrorCategory, Supervisor};
+    use std::time::Duration;
+
+    #[test]
+    fn backoff_until_reconnect() {
+        let mut uut = Supervisor::default();
+        uut.on_success();
+
+        let delays: Vec<_> = (0..7)
+            .map(|_| {
+                uut.on_failure(ErrorCategory::Unreachable)
+                    .expect("unreachable is recoverable")
+                    .as_secs()
+            })
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(
+            uut.state,
+            ConnectionState::Disconnected {
+                category: ErrorCategory::Unreachable,
+                attempts: 7
+            }
+        );
+
+        uut.on_success();
+        assert_eq!(uut.state, ConnectionState::Connected);
+        assert_eq!(
+            uut.on_failure(ErrorCategory::BadResponse),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn unauthorized_is_fatal() {
+        let mut uut = Supervisor::default();
+        assert_eq!(uut.on_failure(ErrorCategory::Unauthorized), None);
+
+        uut.on_success();
+        assert_eq!(uut.on_failure(ErrorCategory::Unauthorized), None);
+    }
+
+    #[test]
+    fn unsupported_is_fatal() {
+        let mut uut = Supervisor::default();
+        assert_eq!(uut.on_failure(ErrorCategory::Unsupported), None);
+    }
+}
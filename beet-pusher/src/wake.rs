@@ -0,0 +1,202 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Events that interrupt the wait between VLC polls
+//!
+//! Sources of events:
+//! - Control socket, a line-based TCP protocol (one [`ControlCommand`] per line)
+//! - Config file watcher
+
+use std::{
+    io::{BufRead as _, BufReader, Write as _},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+use tracing::{debug, info, warn};
+
+/// Reason to wake the main loop before the scheduled poll
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Command received on the control socket
+    Control(ControlCommand),
+    /// Config file was modified
+    ConfigChanged,
+}
+
+/// Command accepted on the control socket
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Poll VLC now
+    Poll,
+    /// Re-read the config file now
+    ReloadConfig,
+    /// Switch to the named profile (until the next change to `active_profile` in the config file)
+    SwitchProfile(String),
+}
+impl std::str::FromStr for ControlCommand {
+    type Err = InvalidControlCommand;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once(char::is_whitespace) {
+            None if s == "poll" => Ok(Self::Poll),
+            None if s == "reload-config" => Ok(Self::ReloadConfig),
+            Some(("profile", name)) if !name.trim().is_empty() => {
+                Ok(Self::SwitchProfile(name.trim().to_owned()))
+            }
+            _ => Err(InvalidControlCommand(s.to_owned())),
+        }
+    }
+}
+#[derive(Debug)]
+pub struct InvalidControlCommand(String);
+impl std::error::Error for InvalidControlCommand {}
+impl std::fmt::Display for InvalidControlCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(command) = self;
+        write!(
+            f,
+            "unknown command {command:?}, expected one of: poll, reload-config, profile NAME"
+        )
+    }
+}
+
+/// Receiver of wake [`Event`]s, for use as an interruptible sleep
+pub struct Receiver {
+    rx: mpsc::Receiver<Event>,
+    // NOTE: keep a sender, to avoid disconnect when no event sources are configured
+    tx: mpsc::Sender<Event>,
+}
+impl Receiver {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self { rx, tx }
+    }
+    /// Returns a sender for a new event source
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.tx.clone()
+    }
+    /// Waits for the next event, or returns `None` if the timeout elapses first
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Event> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                unreachable!("receiver holds a sender, cannot disconnect")
+            }
+        }
+    }
+}
+
+/// Listens on the control socket in a background thread, forwarding commands as [`Event`]s
+///
+/// # Errors
+/// Returns an error if the socket cannot be bound
+pub fn spawn_control_socket(
+    address: SocketAddr,
+    tx: mpsc::Sender<Event>,
+) -> std::io::Result<std::thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(address)?;
+    info!(%address, "listening on control socket");
+
+    let handle = std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| handle_control_client(stream, &tx));
+            match result {
+                Ok(ControlClientEnd::Disconnected) => {}
+                Ok(ControlClientEnd::ReceiverDropped) => break,
+                Err(error) => warn!(%error, "control socket client failed"),
+            }
+        }
+    });
+    Ok(handle)
+}
+enum ControlClientEnd {
+    Disconnected,
+    ReceiverDropped,
+}
+fn handle_control_client(
+    stream: TcpStream,
+    tx: &mpsc::Sender<Event>,
+) -> std::io::Result<ControlClientEnd> {
+    let peer = stream.peer_addr()?;
+    debug!(%peer, "control socket client connected");
+
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse() {
+            Ok(command) => {
+                debug!(%peer, ?command, "control command");
+                if tx.send(Event::Control(command)).is_err() {
+                    return Ok(ControlClientEnd::ReceiverDropped);
+                }
+                writeln!(writer, "ok")?;
+            }
+            Err(error) => {
+                writeln!(writer, "error: {error}")?;
+            }
+        }
+    }
+    Ok(ControlClientEnd::Disconnected)
+}
+
+/// Watches the config file, sending [`Event::ConfigChanged`] on modification
+///
+/// The returned watcher must be kept alive for events to arrive.
+///
+/// # Errors
+/// Returns an error if the config file or its folder cannot be watched
+pub fn watch_config_file(
+    config_file: &Path,
+    tx: mpsc::Sender<Event>,
+) -> Result<hotwatch::Hotwatch, hotwatch::Error> {
+    // react quickly, editors tend to write all at once
+    const DEBOUNCE_DELAY: Duration = Duration::from_millis(250);
+
+    let config_file = config_file.canonicalize()?;
+    // watch the folder, as some editors replace the file (removing the watch on the old file)
+    let config_folder = config_file
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+
+    let mut hotwatch = hotwatch::Hotwatch::new_with_custom_delay(DEBOUNCE_DELAY)?;
+    hotwatch.watch(config_folder, move |event| {
+        use hotwatch::Event as E;
+        match event {
+            E::Create(path) | E::Write(path) | E::Rename(_, path) if path == config_file => {
+                debug!(path = %path.display(), "config file changed");
+                let _ = tx.send(Event::ConfigChanged);
+            }
+            _ => {}
+        }
+    })?;
+    Ok(hotwatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControlCommand;
+
+    #[test]
+    fn parse_control_command() {
+        assert_eq!(
+            "poll".parse::<ControlCommand>().ok(),
+            Some(ControlCommand::Poll)
+        );
+        assert_eq!(
+            " reload-config\r".parse::<ControlCommand>().ok(),
+            Some(ControlCommand::ReloadConfig)
+        );
+        assert_eq!(
+            "profile  dinner ".parse::<ControlCommand>().ok(),
+            Some(ControlCommand::SwitchProfile("dinner".to_owned()))
+        );
+        assert!("unknown".parse::<ControlCommand>().is_err());
+        assert!("profile".parse::<ControlCommand>().is_err());
+        assert!("poll now".parse::<ControlCommand>().is_err());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use crate::item::RecordError;
+use std::{borrow::Cow, time::Duration};
+
+/// Error running the `beet` command, or interpreting its output
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+}
+#[derive(Debug)]
+pub(crate) enum ErrorKind {
+    Spawn(std::io::Error),
+    Wait(std::io::Error),
+    Read(std::io::Error),
+    Timeout {
+        timeout: Duration,
+    },
+    ExitFail {
+        code: Option<i32>,
+        stderr_last_line: Option<String>,
+    },
+    InvalidRecord {
+        record: String,
+        error: RecordError,
+    },
+    InvalidField {
+        name: String,
+    },
+    InvalidItem {
+        item: String,
+    },
+}
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind }
+    }
+}
+impl Error {
+    /// Returns true if the command did not finish within the timeout
+    #[must_use]
+    pub fn is_timeout(&self) -> bool {
+        matches!(self.kind, ErrorKind::Timeout { .. })
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use ErrorKind as E;
+        match &self.kind {
+            E::Spawn(error) | E::Wait(error) | E::Read(error) => Some(error),
+            E::InvalidRecord { error, .. } => Some(error),
+            E::Timeout { .. }
+            | E::ExitFail { .. }
+            | E::InvalidField { .. }
+            | E::InvalidItem { .. } => None,
+        }
+    }
+}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[rustfmt::skip]
+        fn funnel<'a, F: Fn(usize, &'a str) -> Cow<'a, str>>(f: F) -> F { f }
+        let max_len = funnel(
+            |max, raw_input: &str| match raw_input.char_indices().nth(max) {
+                Some((end, _)) => Cow::Owned(format!("{} ...", &raw_input[..end])),
+                None => Cow::Borrowed(raw_input),
+            },
+        );
+        let (description, details) = match &self.kind {
+            ErrorKind::Spawn(_) => ("failed to spawn", None),
+            ErrorKind::Wait(_) => ("failed to wait for", None),
+            ErrorKind::Read(_) => ("failed to read from", None),
+            ErrorKind::Timeout { timeout } => (
+                "timed out waiting for",
+                Some(Cow::Owned(format!("{timeout:?}"))),
+            ),
+            ErrorKind::ExitFail {
+                code,
+                stderr_last_line,
+            } => {
+                let stderr = stderr_last_line.as_deref().map(|line| max_len(200, line));
+                let details = match (code, stderr) {
+                    (Some(code), Some(stderr)) => {
+                        Some(Cow::Owned(format!("[code {code}] {stderr}")))
+                    }
+                    (Some(code), None) => Some(Cow::Owned(format!("[code {code}]"))),
+                    (None, stderr) => stderr,
+                };
+                ("failure status code from", details)
+            }
+            ErrorKind::InvalidRecord { record, error: _ } => {
+                let record = max_len(80, record);
+                (
+                    "invalid output record from",
+                    Some(Cow::Owned(format!("{record:?}"))),
+                )
+            }
+            ErrorKind::InvalidField { name } => {
+                return write!(f, "invalid beet field name {name:?}");
+            }
+            ErrorKind::InvalidItem { item } => {
+                let item = max_len(80, item);
+                return write!(f, "invalid beet item {item:?}, expected id=path");
+            }
+        };
+        write!(f, "{description} beet command")?;
+        if let Some(details) = details {
+            write!(f, ": {details}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Typed items, and the `beet ls -f` format used to request them
+
+use crate::{error::ErrorKind, Error};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Separates fields within a record (ASCII "unit separator")
+const UNIT_SEPARATOR: char = '\u{1f}';
+/// Terminates each record (ASCII "record separator")
+const RECORD_SEPARATOR: u8 = 0x1e;
+
+/// Separates the id and path in the [`BeetItem`] display format
+const SEPARATOR_DISPLAY: char = '=';
+
+/// Fields always requested, as the first fields of each record
+const FIELDS_FIXED: &[&str] = &["id", "path"];
+
+/// Item in the beets library
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeetItem {
+    beet_id: u64,
+    // NOTE: not `PathBuf` because we already entered UTF-8 land by parsing Beet output
+    //       The string may need further modifications to represent a real path
+    path: String,
+    fields: BTreeMap<Field, String>,
+}
+impl BeetItem {
+    /// Creates an item with no extra fields
+    #[must_use]
+    pub fn new(beet_id: u64, path: String) -> Self {
+        Self {
+            beet_id,
+            path,
+            fields: BTreeMap::new(),
+        }
+    }
+    /// Returns the beets database id
+    #[must_use]
+    pub fn get_beet_id(&self) -> u64 {
+        self.beet_id
+    }
+    /// Returns the file path, as reported by beets
+    #[must_use]
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    /// Returns the file path, discarding all other fields
+    #[must_use]
+    pub fn into_path(self) -> String {
+        self.path
+    }
+    /// Returns the value of the specified extra field, if it was requested
+    pub fn get_field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+    /// Returns all extra fields and values, ordered by field name
+    pub fn fields(&self) -> impl Iterator<Item = (&Field, &str)> {
+        self.fields
+            .iter()
+            .map(|(field, value)| (field, value.as_str()))
+    }
+}
+impl std::fmt::Display for BeetItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            beet_id,
+            path,
+            fields: _,
+        } = self;
+        write!(f, "{beet_id}{SEPARATOR_DISPLAY}{path}")
+    }
+}
+/// Parses the [`Display`](`std::fmt::Display`) format (`id=path`), with no extra fields
+impl FromStr for BeetItem {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let make_error = || ErrorKind::InvalidItem { item: s.to_owned() };
+        let (beet_id, path) = s.split_once(SEPARATOR_DISPLAY).ok_or_else(make_error)?;
+        let beet_id = beet_id.parse().map_err(|_| make_error())?;
+        Ok(Self::new(beet_id, path.to_owned()))
+    }
+}
+
+/// Name of a beets field (e.g. `title`, `artist`, `added`) to request for each [`BeetItem`]
+///
+/// The `id` and `path` fields are always requested, so they are not accepted here.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Field(String);
+impl Field {
+    /// Returns the field name
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl std::borrow::Borrow<str> for Field {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+impl FromStr for Field {
+    type Err = Error;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut chars = name.chars();
+        let valid_start = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+        let valid_rest = chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if valid_start && valid_rest && !FIELDS_FIXED.contains(&name) {
+            Ok(Self(name.to_owned()))
+        } else {
+            Err(ErrorKind::InvalidField {
+                name: name.to_owned(),
+            }
+            .into())
+        }
+    }
+}
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Returns the `beet ls -f` template for the fixed fields, followed by the specified fields
+pub(crate) fn format_template(fields: &[Field]) -> String {
+    let mut template = String::new();
+    let names = FIELDS_FIXED
+        .iter()
+        .copied()
+        .chain(fields.iter().map(Field::as_str));
+    for (index, name) in names.enumerate() {
+        if index > 0 {
+            template.push(UNIT_SEPARATOR);
+        }
+        template.push('$');
+        template.push_str(name);
+    }
+    template.push(char::from(RECORD_SEPARATOR));
+    template
+}
+
+/// Parses the output of `beet ls -f` using the [`format_template`] for the specified fields
+///
+/// On failure, returns the offending record (lossy) and the error
+pub(crate) fn parse_output(
+    stdout: &[u8],
+    fields: &[Field],
+) -> Result<Vec<BeetItem>, (String, RecordError)> {
+    let mut records: Vec<_> = stdout
+        .split(|&byte| byte == RECORD_SEPARATOR)
+        // beet terminates each record with a newline, after the record separator
+        .map(|record| {
+            record
+                .strip_prefix(b"\r\n")
+                .or_else(|| record.strip_prefix(b"\n"))
+                .unwrap_or(record)
+        })
+        .collect();
+
+    // remainder after the last separator
+    if let Some(trailing) = records.pop() {
+        if !trailing.iter().all(u8::is_ascii_whitespace) {
+            let trailing = String::from_utf8_lossy(trailing).into_owned();
+            return Err((trailing, RecordError::Unterminated));
+        }
+    }
+
+    records
+        .into_iter()
+        .map(|record| {
+            let lossy = || String::from_utf8_lossy(record).into_owned();
+            let record = std::str::from_utf8(record)
+                .map_err(RecordError::InvalidUtf8)
+                .map_err(|error| (lossy(), error))?;
+            parse_record(record, fields).map_err(|error| (lossy(), error))
+        })
+        .collect()
+}
+
+fn parse_record(record: &str, fields: &[Field]) -> Result<BeetItem, RecordError> {
+    let expected = FIELDS_FIXED.len() + fields.len();
+    let values: Vec<_> = record.split(UNIT_SEPARATOR).collect();
+    let [beet_id, path, extra_values @ ..] = &values[..] else {
+        return Err(RecordError::FieldCount {
+            expected,
+            found: values.len(),
+        });
+    };
+    if values.len() != expected {
+        return Err(RecordError::FieldCount {
+            expected,
+            found: values.len(),
+        });
+    }
+    let beet_id = beet_id.parse().map_err(RecordError::InvalidId)?;
+    let fields = fields
+        .iter()
+        .cloned()
+        .zip(extra_values.iter().map(|&value| value.to_owned()))
+        .collect();
+    Ok(BeetItem {
+        beet_id,
+        path: (*path).to_owned(),
+        fields,
+    })
+}
+
+#[derive(Debug)]
+pub(crate) enum RecordError {
+    Unterminated,
+    InvalidUtf8(std::str::Utf8Error),
+    FieldCount { expected: usize, found: usize },
+    InvalidId(std::num::ParseIntError),
+}
+impl std::error::Error for RecordError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Unterminated | Self::FieldCount { .. } => None,
+            Self::InvalidUtf8(error) => Some(error),
+            Self::InvalidId(error) => Some(error),
+        }
+    }
+}
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unterminated => write!(f, "missing record separator"),
+            Self::InvalidUtf8(_) => write!(f, "invalid UTF-8"),
+            Self::FieldCount { expected, found } => {
+                write!(f, "expected {expected} fields, found {found}")
+            }
+            Self::InvalidId(_) => write!(f, "invalid id number"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_template, parse_output, BeetItem, Field, RecordError};
+
+    fn fields(names: &[&str]) -> Vec<Field> {
+        names
+            .iter()
+            .map(|name| name.parse().expect("valid test field"))
+            .collect()
+    }
+
+    #[test]
+    fn template() {
+        assert_eq!(format_template(&[]), "$id\u{1f}$path\u{1e}");
+        assert_eq!(
+            format_template(&fields(&["title", "album_artist"])),
+            "$id\u{1f}$path\u{1f}$title\u{1f}$album_artist\u{1e}"
+        );
+    }
+
+    #[test]
+    fn field_names() {
+        for valid in ["title", "mb_trackid", "_private", "disc2"] {
+            assert!(valid.parse::<Field>().is_ok(), "{valid:?}");
+        }
+        for invalid in ["", "id", "path", "2disc", "$title", "a b", "title,artist"] {
+            assert!(invalid.parse::<Field>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn odd_characters() {
+        let stdout = "1\u{1f}/music/a=b #1 {},%$?.mp3\u{1e}\n\
+            22\u{1f}/music/multi\nline\r\npath.flac\u{1e}\r\n\
+            333\u{1f}C:\\Music\\ünïcödé 🎵.ogg\u{1e}\n";
+        let items = parse_output(stdout.as_bytes(), &[]).expect("valid output");
+        assert_eq!(
+            items,
+            [
+                BeetItem::new(1, "/music/a=b #1 {},%$?.mp3".to_owned()),
+                BeetItem::new(22, "/music/multi\nline\r\npath.flac".to_owned()),
+                BeetItem::new(333, "C:\\Music\\ünïcödé 🎵.ogg".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extra_fields() {
+        let stdout = "5\u{1f}/x.mp3\u{1f}Title = \"quoted\"\u{1f}\u{1e}\n";
+        let items =
+            parse_output(stdout.as_bytes(), &fields(&["title", "artist"])).expect("valid output");
+        assert_eq!(items.len(), 1, "{items:?}");
+        let item = &items[0];
+        assert_eq!(item.get_beet_id(), 5);
+        assert_eq!(item.get_field("title"), Some("Title = \"quoted\""));
+        assert_eq!(item.get_field("artist"), Some(""));
+        assert_eq!(item.get_field("album"), None);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let item = BeetItem::new(42, "/music/a=b.mp3".to_owned());
+        assert_eq!(item.to_string(), "42=/music/a=b.mp3");
+        assert_eq!(item.to_string().parse::<BeetItem>().ok(), Some(item));
+        assert!("/music/a.mp3".parse::<BeetItem>().is_err());
+        assert!("x=/music/a.mp3".parse::<BeetItem>().is_err());
+    }
+
+    #[test]
+    fn empty_output() {
+        assert_eq!(parse_output(b"", &[]).expect("empty valid"), []);
+        assert_eq!(parse_output(b"\n", &[]).expect("blank valid"), []);
+    }
+
+    #[test]
+    fn invalid_output() {
+        let check =
+            |stdout: &[u8], expected_record: &str, check_error: fn(&RecordError) -> bool| {
+                let (record, error) = parse_output(stdout, &fields(&["title"]))
+                    .expect_err("invalid output should fail");
+                assert_eq!(record, expected_record);
+                assert!(check_error(&error), "unexpected {error:?}");
+            };
+        // unterminated (e.g. field value contains the record separator, or truncated output)
+        check(b"1\x1f/a\x1ftitle", "1\x1f/a\x1ftitle", |e| {
+            matches!(e, RecordError::Unterminated)
+        });
+        // missing field
+        check(b"1\x1f/a\x1e\n", "1\x1f/a", |e| {
+            matches!(
+                e,
+                RecordError::FieldCount {
+                    expected: 3,
+                    found: 2
+                }
+            )
+        });
+        // extra field (e.g. value contains the unit separator)
+        check(b"1\x1f/a\x1fb\x1fc\x1e\n", "1\x1f/a\x1fb\x1fc", |e| {
+            matches!(
+                e,
+                RecordError::FieldCount {
+                    expected: 3,
+                    found: 4
+                }
+            )
+        });
+        check(b"x\x1f/a\x1ft\x1e\n", "x\x1f/a\x1ft", |e| {
+            matches!(e, RecordError::InvalidId(_))
+        });
+        check(b"1\x1f/\xff\x1ft\x1e\n", "1\x1f/\u{fffd}\x1ft", |e| {
+            matches!(e, RecordError::InvalidUtf8(_))
+        });
+    }
+}
@@ -0,0 +1,200 @@
+// soundbox-ii/beets music library query helper *don't keep your sounds boxed up*
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+//! Queries the [beets](https://beets.io/) music library database, by running the `beet` command
+//!
+//! Items are requested using a `beet ls -f` format template with control-character separators,
+//! so that paths and tag values may contain any printable characters (including newlines).
+//!
+//! The `fake-beet` binary (in the repository root) is a stand-in for `beet` in tests.
+
+pub use error::Error;
+pub use item::{BeetItem, Field};
+
+use std::{
+    ffi::OsStr,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    time::{Duration, Instant},
+};
+use tracing::{debug, trace};
+
+use error::ErrorKind;
+
+mod error;
+mod item;
+
+/// Default limit for the `beet` command runtime
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval for checking if the `beet` process exited
+const POLL_EXIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runner for the `beet` command
+#[derive(Clone, Debug)]
+pub struct Beet {
+    command: PathBuf,
+    timeout: Option<Duration>,
+    fields: Vec<Field>,
+}
+impl Beet {
+    /// Creates a runner for the specified `beet` executable, with the [`DEFAULT_TIMEOUT`]
+    pub fn new(command: impl Into<PathBuf>) -> Self {
+        Self {
+            command: command.into(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            fields: vec![],
+        }
+    }
+    /// Sets the limit for the command runtime (`None` for unlimited)
+    #[must_use]
+    pub fn with_timeout(self, timeout: Option<Duration>) -> Self {
+        Self { timeout, ..self }
+    }
+    /// Sets the extra fields to request for each [`BeetItem`], in addition to the id and path
+    #[must_use]
+    pub fn with_fields(self, fields: Vec<Field>) -> Self {
+        Self { fields, ..self }
+    }
+    /// Returns the command executable
+    #[must_use]
+    pub fn command(&self) -> &Path {
+        &self.command
+    }
+    /// Returns the first line of the `beet --version` output
+    ///
+    /// Useful to verify the command exists, before the first query.
+    ///
+    /// # Errors
+    /// Returns an error if the command fails, or does not finish within the timeout
+    pub fn version(&self) -> Result<String, Error> {
+        let stdout = self.run(["--version"])?;
+        let stdout = String::from_utf8_lossy(&stdout);
+        Ok(stdout.lines().next().unwrap_or_default().trim().to_owned())
+    }
+    /// Lists the items matching all of the specified filters
+    ///
+    /// # Errors
+    /// Returns an error if the command fails, does not finish within the timeout, or the output
+    /// is not in the requested format
+    pub fn query<I>(&self, filters: I) -> Result<Vec<BeetItem>, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let template = item::format_template(&self.fields);
+        let args = ["ls".as_ref(), "-f".as_ref(), OsStr::new(&template)]
+            .into_iter()
+            .map(OsStr::to_os_string)
+            .chain(filters.into_iter().map(|arg| arg.as_ref().to_os_string()));
+
+        let stdout = self.run(args)?;
+
+        debug!("parse `beet` output ({} bytes)", stdout.len());
+        item::parse_output(&stdout, &self.fields)
+            .map_err(|(record, error)| ErrorKind::InvalidRecord { record, error })
+            .map_err(Error::from)
+    }
+    fn run<I>(&self, args: I) -> Result<Vec<u8>, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        debug!("spawn `beet` command");
+
+        let mut command = Command::new(&self.command);
+        command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        trace!(?command);
+
+        let mut child = command.spawn().map_err(ErrorKind::Spawn)?;
+
+        // read concurrently, to avoid blocking the child on a full pipe
+        let stdout = spawn_read_to_end(child.stdout.take());
+        let stderr = spawn_read_to_end(child.stderr.take());
+
+        let status = wait_timeout(&mut child, self.timeout)?;
+
+        let stdout = stdout.join().map_err(ErrorKind::Read)?;
+        let stderr = stderr.join().map_err(ErrorKind::Read)?;
+        let stderr = String::from_utf8_lossy(&stderr);
+
+        if !status.success() {
+            return Err(ErrorKind::ExitFail {
+                code: status.code(),
+                stderr_last_line: stderr
+                    .lines()
+                    .rev()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(str::to_owned),
+            }
+            .into());
+        }
+        if !stderr.trim().is_empty() {
+            debug!(%stderr, "stderr output from beet command");
+        }
+        Ok(stdout)
+    }
+}
+
+fn wait_timeout(child: &mut Child, timeout: Option<Duration>) -> Result<ExitStatus, ErrorKind> {
+    let Some(timeout) = timeout else {
+        return child.wait().map_err(ErrorKind::Wait);
+    };
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(ErrorKind::Wait)? {
+            return Ok(status);
+        }
+        let Some(remaining) = timeout.checked_sub(start.elapsed()) else {
+            // ignore kill errors, the process may have exited in the meantime
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(ErrorKind::Timeout { timeout });
+        };
+        std::thread::sleep(POLL_EXIT_INTERVAL.min(remaining));
+    }
+}
+
+struct ReadHandle(Option<std::thread::JoinHandle<std::io::Result<Vec<u8>>>>);
+impl ReadHandle {
+    fn join(self) -> std::io::Result<Vec<u8>> {
+        let Some(handle) = self.0 else {
+            return Ok(vec![]);
+        };
+        handle
+            .join()
+            .unwrap_or_else(|_| Err(std::io::Error::other("reader thread panicked")))
+    }
+}
+fn spawn_read_to_end<R>(reader: Option<R>) -> ReadHandle
+where
+    R: Read + Send + 'static,
+{
+    ReadHandle(reader.map(|mut reader| {
+        std::thread::spawn(move || {
+            let mut buf = vec![];
+            reader.read_to_end(&mut buf)?;
+            Ok(buf)
+        })
+    }))
+}
@@ -0,0 +1,77 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Runs queries against the `fake-beet` test double
+//!
+//! Build it first using `cargo build` in the `fake-beet` folder.
+
+use beets::{Beet, Field};
+use std::{path::PathBuf, time::Duration};
+
+fn fake_beet() -> Beet {
+    let fakebeet = "../fake-beet/target/debug/fake-beet";
+    assert!(
+        PathBuf::from(fakebeet).exists(),
+        "fakebeet not found at path {fakebeet:?}"
+    );
+    Beet::new(fakebeet)
+}
+
+#[test]
+#[ignore = "fake_beet not hooked up for nix builds"]
+fn version() {
+    let version = fake_beet().version().expect("version succeeds");
+    assert_eq!(version, "beets version FAKE");
+}
+
+#[test]
+#[ignore = "fake_beet not hooked up for nix builds"]
+fn query_fields() {
+    let fields = ["title", "album_artist"]
+        .into_iter()
+        .map(str::parse)
+        .collect::<Result<Vec<Field>, _>>()
+        .expect("valid fields");
+    let items = fake_beet()
+        .with_fields(fields)
+        .query(["artist:a b", "3"])
+        .expect("query succeeds");
+
+    let ids: Vec<_> = items.iter().map(beets::BeetItem::get_beet_id).collect();
+    assert_eq!(ids, [1, 2, 3]);
+    for item in &items {
+        let id = item.get_beet_id();
+        assert_eq!(
+            item.get_path(),
+            format!("/fake-beet/{id} = #odd {{chars}},%$?\n/artist:a b 3.mp3")
+        );
+        assert_eq!(item.get_field("title"), Some(&*format!("fake-title-{id}")));
+        assert_eq!(
+            item.get_field("album_artist"),
+            Some(&*format!("fake-album_artist-{id}"))
+        );
+    }
+}
+
+#[test]
+#[ignore = "fake_beet not hooked up for nix builds"]
+fn query_failure() {
+    let error = fake_beet()
+        .query(["should_fail"])
+        .expect_err("fail argument triggers failure");
+    assert!(!error.is_timeout());
+    assert!(
+        error.to_string().contains("synthetic fail message"),
+        "{error}"
+    );
+}
+
+#[test]
+#[ignore = "fake_beet not hooked up for nix builds"]
+fn query_timeout() {
+    // fake-beet waits before responding
+    let error = fake_beet()
+        .with_timeout(Some(Duration::from_millis(100)))
+        .query(["1"])
+        .expect_err("query times out");
+    assert!(error.is_timeout(), "{error}");
+}
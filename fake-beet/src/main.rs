@@ -5,6 +5,9 @@
 //!
 //! When supplied an argument containing `fail`, the result is a failure.
 //! If the final argument is a number, then that number of repeated lines will be output.
+//!
+//! For `ls -f FORMAT`, each line is the format with `$field` names replaced by fake values
+//! (`$id` is the line number, `$path` contains the arguments and some odd characters).
 use std::{str::FromStr, time::Duration};
 
 fn main() -> Result<(), String> {
@@ -20,15 +23,20 @@ fn main() -> Result<(), String> {
         }
     }
 
-    match (args.first(), args.get(1)) {
+    let format = match (args.first(), args.get(1)) {
         (Some(first), Some(second)) if first == "ls" && second == "-p" => {
-            args.remove(0);
-            args.remove(0);
+            args.drain(..2);
+            None
+        }
+        (Some(first), Some(second)) if first == "ls" && second == "-f" && args.len() >= 3 => {
+            let format = args.remove(2);
+            args.drain(..2);
+            Some(format)
         }
         first_two_args => {
-            return Err(format!("fake beet did not recognize the command {first_two_args:?}.  Perhaps you meant to use \"ls\" \"-p\"?"))
+            return Err(format!("fake beet did not recognize the command {first_two_args:?}.  Perhaps you meant to use \"ls\" \"-p\" or \"ls\" \"-f\" FORMAT?"))
         }
-    }
+    };
 
     let result = parse_result(&args);
 
@@ -38,7 +46,11 @@ fn main() -> Result<(), String> {
     if result.is_ok() {
         let count = parse_count(&args).unwrap_or(1);
         for n in 0..count {
-            println!("fake-beet({current}/{count}) {args:?}", current = n + 1);
+            let current = n + 1;
+            match &format {
+                None => println!("fake-beet({current}/{count}) {args:?}"),
+                Some(format) => println!("{}", fill_format(format, current, &args)),
+            }
         }
         eprintln!("this std-err message should not appear in the output");
         eprintln!(" (since std-err is usually used for human-readable output)");
@@ -49,6 +61,30 @@ fn main() -> Result<(), String> {
     result
 }
 
+fn fill_format(format: &str, id: u32, args: &[String]) -> String {
+    let mut output = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            output.push(c);
+            continue;
+        }
+        let mut field = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            field.push(c);
+        }
+        match field.as_str() {
+            "id" => output.push_str(&id.to_string()),
+            "path" => output.push_str(&format!(
+                "/fake-beet/{id} = #odd {{chars}},%$?\n/{args}.mp3",
+                args = args.join(" ")
+            )),
+            _ => output.push_str(&format!("fake-{field}-{id}")),
+        }
+    }
+    output
+}
+
 fn parse_count(args: &[String]) -> Option<u32> {
     u32::from_str(args.last()?).ok()
 }
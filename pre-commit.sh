@@ -34,6 +34,10 @@ true \
   && echo "Outstanding cargo fmt files:" && cargo fmt --all -- --check -l && echo "[none]" \
   && cargo clippy --workspace --all-targets --color always \
   && cargo test --workspace --color always \
+  && cargo clippy --package vlc-http --all-targets --no-default-features --color always \
+  && cargo test --package vlc-http --no-default-features --color always \
+  && cargo clippy --package vlc-http --all-targets --all-features --color always \
+  && cargo test --package vlc-http --all-features --color always \
   && cargo doc --workspace --no-deps -q --color always \
   && true # trailing commas for the win
 RESULT=$?
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! Command-line interface for [`Sequencer`]
 
 use crate::{
@@ -29,11 +29,14 @@ pub trait FilterArgParser {
     /// Filter type constructed from the arguments
     type Filter;
     /// Converts the specified arguments to a filter
+    ///
+    /// # Errors
+    /// Returns a message if the arguments are not valid for the source type
     fn parse_filter_args(
         &self,
         args: Vec<String>,
         source_type: Option<Self::Type>,
-    ) -> Option<Self::Filter>;
+    ) -> Result<Option<Self::Filter>, String>;
 }
 /// Cli parameters
 pub struct OutputParams {
@@ -100,7 +103,7 @@ where
                 let source_type = self.calculate_existing_type(&parent_path, requested_type)?;
                 let node_path = if let Some(filter) = self
                     .filter_arg_parser
-                    .parse_filter_args(items_filter, source_type)
+                    .parse_filter_args(items_filter, source_type)?
                 {
                     self.run(command::AddTerminalNode {
                         parent_path,
@@ -122,7 +125,7 @@ where
                 let source_type = self.calculate_existing_type(&path, requested_type)?;
                 let filter = self
                     .filter_arg_parser
-                    .parse_filter_args(items_filter, source_type);
+                    .parse_filter_args(items_filter, source_type)?;
                 let filter_print = filter.clone();
                 let old = self.run(command::SetNodeFilter { path, filter });
                 self.output_summary(format_args!(
@@ -157,6 +160,12 @@ where
                 self.run(command::UpdateNodes { path })?;
                 self.output_summary(format_args!("updated nodes under path {path_print}"))
             }
+            NodeCommand::Refresh { path } => {
+                let path = path.unwrap_or_else(|| ".".to_string());
+                let path_print = path.clone();
+                self.run(command::RefreshNodes { path })?;
+                self.output_summary(format_args!("refreshed nodes under path {path_print}"))
+            }
             NodeCommand::Remove { id } => {
                 let id_print = id.clone();
                 self.run(command::RemoveNode { id })?;
@@ -217,6 +226,26 @@ where
     pub fn pop_next(&mut self) -> Option<q_filter_tree::SequenceAndItem<T::Item>> {
         self.sequencer.pop_next()
     }
+    /// Sets whether lookups are deferred, see [`Sequencer::set_deferred_lookups`]
+    pub fn set_deferred_lookups(&mut self, deferred: bool) {
+        self.sequencer.set_deferred_lookups(deferred);
+    }
+    /// Removes the pending lookups, see [`Sequencer::take_pending_lookups`]
+    pub fn take_pending_lookups(&mut self) -> Vec<crate::lookup::PendingLookup<Option<F>>> {
+        self.sequencer.take_pending_lookups()
+    }
+    /// Sets the items from a deferred lookup, see [`Sequencer::apply_lookup`]
+    ///
+    /// # Errors
+    /// Returns an error if the lookup is outdated, or the node was removed (or replaced), or is no
+    /// longer terminal
+    pub fn apply_lookup(
+        &mut self,
+        id: &crate::lookup::LookupId,
+        items: Vec<T::Item>,
+    ) -> Result<(), Error> {
+        self.sequencer.apply_lookup(id, items)
+    }
     /// Prints the specified information (unless quiet mode is set)
     pub fn output(&self, fmt_args: std::fmt::Arguments) {
         self.output_summary(fmt_args);
@@ -285,6 +314,12 @@ where
         /// Path of the target node to update (optional, default is all nodes)
         path: Option<String>,
     },
+    /// Clear cached items for all terminal nodes reachable from the specified parent node, then
+    /// update the items
+    Refresh {
+        /// Path of the target node to refresh (optional, default is all nodes)
+        path: Option<String>,
+    },
     /// Remove a node
     Remove {
         /// Id of the target node to delete
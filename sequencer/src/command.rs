@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! Command types for running a [`Sequencer`]
 
 use crate::{sources::ItemSource, Error, NodeIdStr, Sequencer};
@@ -60,6 +60,12 @@ command_enum! {
             /// Target node path
             path: String,
         },
+        /// Clear the cached lookups for all terminal nodes reachable from the specified parent,
+        /// then update the items
+        RefreshNodes -> Success {
+            /// Target node path
+            path: String,
+        },
         /// Removes the specified node
         RemoveNode -> Success {
             /// Target node id
@@ -160,6 +166,12 @@ command_runnable! {
             seq.update_nodes(&path)
         }
     }
+    impl<F> Runnable<F> for RefreshNodes {
+        fn run(self, seq) -> Result<(), Error> {
+            let Self { path } = self;
+            seq.refresh_nodes(&path)
+        }
+    }
     impl<F> Runnable<F> for RemoveNode {
         fn run(self, seq) -> Result<(), Error> {
             let Self { id } = self;
@@ -1,5 +1,5 @@
 // soundbox-ii/sequencer music playback controller *don't keep your sounds boxed up*
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //
 // This program is free software: you can redistribute it and/or modify
 // it under the terms of the GNU General Public License as published by
@@ -52,6 +52,9 @@ pub mod command;
 
 pub mod cli;
 
+use lookup::{DeferredLookups, LookupId, PendingLookup};
+pub mod lookup;
+
 pub mod persistence;
 
 // conversions, for ergonomic use with `ItemSource`
@@ -66,6 +69,8 @@ type NodeInfo<T, F> = q_filter_tree::NodeInfo<Item<T, F>, F>;
 pub struct Sequencer<T: ItemSource<F>, F> {
     inner: SequencerTree<Item<T, F>, F>,
     item_source: T,
+    /// Lookups awaiting [`Self::take_pending_lookups`] and [`Self::apply_lookup`] (if deferred)
+    deferred_lookups: Option<DeferredLookups<F>>,
 }
 /// Tree of filters for selecting tracks, using [`q_filter_tree`] back-end
 pub struct SequencerTree<T, F> {
@@ -80,13 +85,40 @@ impl<T: ItemSource<F>, F> Sequencer<T, F> {
         Self {
             inner: SequencerTree::new(root_filter),
             item_source,
+            deferred_lookups: None,
         }
     }
     /// Creates a new Sequencer from the specified tree
     ///
     /// (e.g. from [`persistence::SequencerConfigFile::read_from_file`])
     pub fn new_from_tree(item_source: T, inner: SequencerTree<Item<T, F>, F>) -> Self {
-        Self { item_source, inner }
+        Self {
+            item_source,
+            inner,
+            deferred_lookups: None,
+        }
+    }
+    /// Sets whether lookups for terminal nodes are deferred, instead of run during each command
+    ///
+    /// Deferred lookups are retrieved by [`Self::take_pending_lookups`] (e.g. to run on a
+    /// [`lookup::LookupWorker`]), and the results applied by [`Self::apply_lookup`].
+    /// Disabling discards any pending lookups.
+    pub fn set_deferred_lookups(&mut self, deferred: bool) {
+        if deferred {
+            self.deferred_lookups
+                .get_or_insert_with(DeferredLookups::new);
+        } else {
+            self.deferred_lookups = None;
+        }
+    }
+    /// Removes the lookups queued since the last call (if deferred)
+    ///
+    /// See [`Self::set_deferred_lookups`]
+    pub fn take_pending_lookups(&mut self) -> Vec<PendingLookup<F>> {
+        self.deferred_lookups
+            .as_mut()
+            .map(DeferredLookups::take_pending)
+            .unwrap_or_default()
     }
 
     /// Replaces the inner tree
@@ -335,7 +367,12 @@ where
         let parent_path = (&parent_path).into();
         let mut tree_guard = self.inner.guard();
         let new_node_id = tree_guard.add_terminal_node(parent_path, filter)?;
-        Self::inner_update_node(&self.item_source, &new_node_id, &mut tree_guard)?;
+        Self::inner_update_node(
+            &self.item_source,
+            self.deferred_lookups.as_mut(),
+            &new_node_id,
+            &mut tree_guard,
+        )?;
         Ok(serialize_id(new_node_id)?)
     }
     /// Sets the filter of the specified node
@@ -347,7 +384,12 @@ where
         let mut tree_guard = self.inner.guard();
         let node_path = parse_path(node_path_str)?;
         let old_filter = tree_guard.set_node_filter((&node_path).into(), filter)?;
-        Self::inner_update_node(&self.item_source, &node_path, &mut tree_guard)?;
+        Self::inner_update_node(
+            &self.item_source,
+            self.deferred_lookups.as_mut(),
+            &node_path,
+            &mut tree_guard,
+        )?;
         Ok(old_filter)
     }
     /// Sets the weight of the specified item in the node
@@ -400,13 +442,48 @@ where
         let node_path = parse_path(node_path_str)?;
         // update node (recursively)
         let mut tree_guard = self.inner.guard();
-        Self::inner_update_node(&self.item_source, &node_path, &mut tree_guard)?;
+        Self::inner_update_node(
+            &self.item_source,
+            self.deferred_lookups.as_mut(),
+            &node_path,
+            &mut tree_guard,
+        )?;
         // TODO deleteme, no reason to repeat back (sanitized?) version of input param
         // Ok(serialize_path(node_path)?)
         Ok(())
     }
+    /// Clears cached lookups for the specified node (and any children), then updates the items
+    ///
+    /// See [`ItemSource::invalidate`]
+    ///
+    /// # Errors
+    /// Returns an [`Error`] when inputs do not match the inner tree state
+    fn refresh_nodes(&mut self, node_path_str: &str) -> Result<(), Error> {
+        use q_filter_tree::iter::IterMutBreadcrumb;
+        let node_path = parse_path(node_path_str)?;
+        let mut tree_guard = self.inner.guard();
+        let item_source = &self.item_source;
+        tree_guard
+            .guard
+            .as_mut()
+            .enumerate_mut_subtree_filters(&node_path)?
+            .with_all(|args, _path, mut node_ref| {
+                let is_items = node_ref.child_nodes().is_none();
+                if is_items {
+                    item_source.invalidate(args);
+                }
+                Ok::<_, Error>(())
+            })?;
+        Self::inner_update_node(
+            item_source,
+            self.deferred_lookups.as_mut(),
+            &node_path,
+            &mut tree_guard,
+        )
+    }
     fn inner_update_node<'a, 'b>(
         item_source: &T,
+        mut deferred_lookups: Option<&mut DeferredLookups<F>>,
         path: impl Into<NodePathRefTyped<'a>>,
         tree_guard: &mut SequencerTreeGuard<'b, Item<T, F>, F>,
     ) -> Result<(), Error> {
@@ -415,9 +492,15 @@ where
             .guard
             .as_mut()
             .enumerate_mut_subtree_filters(path)?
-            .with_all(|args, _path, mut node_ref| {
+            .with_all(|args, path, mut node_ref| {
                 let is_items = node_ref.child_nodes().is_none();
-                if is_items {
+                if !is_items {
+                    return Ok(());
+                }
+                if let Some(deferred_lookups) = deferred_lookups.as_deref_mut() {
+                    let node_id = path.clone_owned().with_sequence(&node_ref);
+                    deferred_lookups.push(node_id, args.to_vec());
+                } else {
                     // TODO add `NodeId` to the item, so user can diagnose
                     //   where specific queued item came from
                     let items = item_source
@@ -428,6 +511,35 @@ where
                 Ok(())
             })
     }
+    /// Sets the items for the terminal node, from a deferred lookup
+    ///
+    /// See [`Self::set_deferred_lookups`]
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the lookup is outdated (a newer lookup for the node was queued, or
+    /// the lookup was already applied), the node was removed (or replaced), or is no longer terminal
+    pub fn apply_lookup(&mut self, id: &LookupId, items: Vec<Item<T, F>>) -> Result<(), Error> {
+        use q_filter_tree::id::SequenceSource as _;
+        let node_id = id.node_id();
+        let is_latest = self
+            .deferred_lookups
+            .as_mut()
+            .is_some_and(|deferred_lookups| deferred_lookups.take_latest(id));
+        if !is_latest {
+            let generation = id.generation();
+            return Err(format!("lookup {generation} for node {node_id} is outdated").into());
+        }
+        let mut tree_guard = self.inner.guard();
+        let mut node_ref = node_id.try_ref(&mut tree_guard.guard)?;
+        if node_ref.sequence() != node_id.sequence() {
+            return Err(format!("node {node_id} was replaced before the lookup finished").into());
+        }
+        if node_ref.child_nodes().is_some() {
+            return Err(format!("node {node_id} is no longer a terminal node").into());
+        }
+        node_ref.merge_child_items_uniform(items);
+        Ok(())
+    }
     /// Removes a `Node` at the specified id (path`#`sequence)
     ///
     /// # Errors
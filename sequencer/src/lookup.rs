@@ -0,0 +1,416 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Background lookups for [`ItemSource`]s, for use with
+//! [`Sequencer::set_deferred_lookups`](`crate::Sequencer::set_deferred_lookups`)
+//!
+//! Slow sources (e.g. [`Beet`](`crate::sources::Beet`) running a child process) block the caller
+//! for each node update. Instead, the [`LookupWorker`] runs the pending lookups concurrently, and
+//! reports a [`LookupEvent`] for each node as the lookup progresses. Apply the found items using
+//! [`Sequencer::apply_lookup`](`crate::Sequencer::apply_lookup`).
+
+use crate::sources::ItemSource;
+use q_filter_tree::id::NodeIdTyped;
+use std::{
+    num::NonZeroUsize,
+    sync::{mpsc, Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+/// Identifies a lookup for a terminal node
+///
+/// Each lookup for a node has a new generation, so that only the latest result is applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupId {
+    node_id: NodeIdTyped,
+    generation: u64,
+}
+impl LookupId {
+    /// Returns the id of the terminal node
+    pub fn node_id(&self) -> &NodeIdTyped {
+        &self.node_id
+    }
+    /// Returns the generation of the lookup (increasing for each lookup)
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// Terminal node awaiting items from an [`ItemSource`] lookup
+#[derive(Clone, Debug)]
+pub struct PendingLookup<F> {
+    id: LookupId,
+    args: Vec<F>,
+}
+impl<F> PendingLookup<F> {
+    /// Returns the id of the lookup
+    pub fn id(&self) -> &LookupId {
+        &self.id
+    }
+    /// Returns the id of the terminal node
+    pub fn node_id(&self) -> &NodeIdTyped {
+        &self.id.node_id
+    }
+    /// Returns the filter arguments, from the root to the terminal node
+    pub fn args(&self) -> &[F] {
+        &self.args
+    }
+}
+
+/// Deferred lookups for a [`Sequencer`](`crate::Sequencer`), tracking the latest lookup of each
+/// node
+#[derive(Clone, Debug)]
+pub(crate) struct DeferredLookups<F> {
+    pending: Vec<PendingLookup<F>>,
+    /// Latest lookup of each node, not yet applied
+    latest: Vec<LookupId>,
+    next_generation: u64,
+}
+impl<F> DeferredLookups<F> {
+    pub fn new() -> Self {
+        Self {
+            pending: vec![],
+            latest: vec![],
+            next_generation: 0,
+        }
+    }
+    /// Queues a lookup for the node, superseding any previous lookup for the node
+    pub fn push(&mut self, node_id: NodeIdTyped, args: Vec<F>) {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        let id = LookupId {
+            node_id,
+            generation,
+        };
+        self.latest.retain(|latest| latest.node_id != id.node_id);
+        self.latest.push(id.clone());
+        self.pending.push(PendingLookup { id, args });
+    }
+    /// Removes the lookups queued since the last call
+    pub fn take_pending(&mut self) -> Vec<PendingLookup<F>> {
+        std::mem::take(&mut self.pending)
+    }
+    /// Returns `true` if the lookup is the latest for its node (and not yet applied), marking it as
+    /// applied
+    pub fn take_latest(&mut self, id: &LookupId) -> bool {
+        let position = self.latest.iter().position(|latest| latest == id);
+        position
+            .map(|index| self.latest.swap_remove(index))
+            .is_some()
+    }
+}
+
+/// Progress of a lookup for a terminal node, reported by the [`LookupWorker`]
+#[derive(Debug)]
+pub enum LookupEvent<I> {
+    /// Lookup started
+    Started(LookupId),
+    /// Lookup finished, with items to apply to the node
+    Finished(LookupId, Vec<I>),
+    /// Lookup failed
+    Failed(LookupId, String),
+    /// Lookup did not finish within the timeout (the result will be discarded)
+    TimedOut(LookupId, Duration),
+}
+impl<I> LookupEvent<I> {
+    /// Returns the id of the lookup
+    pub fn id(&self) -> &LookupId {
+        match self {
+            Self::Started(id)
+            | Self::Finished(id, _)
+            | Self::Failed(id, _)
+            | Self::TimedOut(id, _) => id,
+        }
+    }
+    /// Returns the id of the terminal node
+    pub fn node_id(&self) -> &NodeIdTyped {
+        self.id().node_id()
+    }
+}
+impl<I> std::fmt::Display for LookupEvent<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node_id = self.node_id();
+        match self {
+            Self::Started(_) => write!(f, "node {node_id}: lookup started"),
+            Self::Finished(_, items) => {
+                write!(f, "node {node_id}: lookup found {} items", items.len())
+            }
+            Self::Failed(_, err) => write!(f, "node {node_id}: item lookup error: {err}"),
+            Self::TimedOut(_, timeout) => {
+                write!(f, "node {node_id}: lookup timed out after {timeout:?}")
+            }
+        }
+    }
+}
+
+/// Parameters for the [`LookupWorker`]
+#[derive(Clone, Copy, Debug)]
+pub struct LookupWorkerParams {
+    /// Maximum number of lookups to run at once (including timed-out lookups, until they return)
+    pub concurrency: NonZeroUsize,
+    /// Time limit for each lookup (unlimited, if `None`)
+    pub timeout: Option<Duration>,
+}
+impl LookupWorkerParams {
+    /// Default number of lookups to run at once
+    pub const DEFAULT_CONCURRENCY: NonZeroUsize = NonZeroUsize::MIN.saturating_add(3);
+    /// Default time limit for each lookup
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+}
+impl Default for LookupWorkerParams {
+    fn default() -> Self {
+        Self {
+            concurrency: Self::DEFAULT_CONCURRENCY,
+            timeout: Some(Self::DEFAULT_TIMEOUT),
+        }
+    }
+}
+
+/// Runs [`PendingLookup`]s on background threads, reporting each [`LookupEvent`] to the callback
+///
+/// Lookups exceeding the timeout are reported (and the result discarded), but not cancelled. The
+/// lookup still counts toward the concurrency limit until the [`ItemSource`] returns, so sources
+/// running external commands should enforce their own time limit (e.g.
+/// [`beets::DEFAULT_TIMEOUT`]).
+///
+/// Dropping the worker stops the threads after their current lookup, discarding queued lookups.
+pub struct LookupWorker<F> {
+    lookup_tx: mpsc::Sender<PendingLookup<F>>,
+}
+impl<F> LookupWorker<F>
+where
+    F: Send + 'static,
+{
+    /// Spawns the worker threads, using the specified source and event callback
+    ///
+    /// The callback is called from the worker threads.
+    ///
+    /// # Errors
+    /// Returns an error if the operating system fails to spawn a thread
+    pub fn new<S, U>(
+        item_source: S,
+        params: LookupWorkerParams,
+        on_event: U,
+    ) -> std::io::Result<Self>
+    where
+        S: ItemSource<F> + Send + Sync + 'static,
+        S::Item: Send + 'static,
+        U: Fn(LookupEvent<S::Item>) + Send + Sync + 'static,
+    {
+        let (lookup_tx, lookup_rx) = mpsc::channel();
+        let runner = Arc::new(Runner {
+            item_source: Arc::new(item_source),
+            timeout: params.timeout,
+            on_event,
+            lookup_rx: Mutex::new(lookup_rx),
+        });
+        for index in 0..params.concurrency.get() {
+            let runner = Arc::clone(&runner);
+            std::thread::Builder::new()
+                .name(format!("sequencer-lookup-{index}"))
+                .spawn(move || runner.run())?;
+        }
+        Ok(Self { lookup_tx })
+    }
+    /// Queues the lookup to run on the next available thread
+    pub fn submit(&self, lookup: PendingLookup<F>) {
+        // NOTE: threads only stop when the sender is dropped (or a callback panics)
+        let _ = self.lookup_tx.send(lookup);
+    }
+}
+
+struct Runner<S, F, U> {
+    item_source: Arc<S>,
+    timeout: Option<Duration>,
+    on_event: U,
+    lookup_rx: Mutex<mpsc::Receiver<PendingLookup<F>>>,
+}
+impl<S, F, U> Runner<S, F, U>
+where
+    S: ItemSource<F> + Send + Sync + 'static,
+    S::Item: Send + 'static,
+    F: Send + 'static,
+    U: Fn(LookupEvent<S::Item>),
+{
+    fn run(&self) {
+        loop {
+            let next = self
+                .lookup_rx
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .recv();
+            let Ok(PendingLookup { id, args }) = next else {
+                // worker dropped
+                break;
+            };
+            (self.on_event)(LookupEvent::Started(id.clone()));
+            self.lookup(id, args);
+        }
+    }
+    /// Runs the lookup and reports the result, returning only after the lookup returns (even if
+    /// timed out)
+    fn lookup(&self, id: LookupId, args: Vec<F>) {
+        let to_event = |id, result: Result<_, _>| match result {
+            Ok(items) => LookupEvent::Finished(id, items),
+            Err(err) => LookupEvent::Failed(id, err),
+        };
+        let Some(timeout) = self.timeout else {
+            let result = self.item_source.lookup(&args);
+            (self.on_event)(to_event(id, result.map_err(|err| err.to_string())));
+            return;
+        };
+        let (result_tx, result_rx) = mpsc::sync_channel(1);
+        let item_source = Arc::clone(&self.item_source);
+        let spawned = std::thread::Builder::new()
+            .name("sequencer-lookup-timed".to_owned())
+            .spawn(move || {
+                let result = item_source.lookup(&args).map_err(|err| err.to_string());
+                let _ = result_tx.send(result);
+            });
+        if let Err(err) = spawned {
+            let err = format!("failed to spawn lookup thread: {err}");
+            (self.on_event)(LookupEvent::Failed(id, err));
+            return;
+        }
+        let event = match result_rx.recv_timeout(timeout) {
+            Ok(result) => to_event(id, result),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                (self.on_event)(LookupEvent::TimedOut(id, timeout));
+                // hold this thread (the concurrency slot) until the abandoned lookup returns
+                let _ = result_rx.recv();
+                return;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                LookupEvent::Failed(id, "lookup thread panicked".to_owned())
+            }
+        };
+        (self.on_event)(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeferredLookups, LookupEvent, LookupWorker, LookupWorkerParams};
+    use crate::sources::ItemSource;
+    use q_filter_tree::id::{NodeIdTyped, SequenceSource};
+    use std::{
+        num::NonZeroUsize,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        },
+        time::Duration,
+    };
+
+    /// Sleeps for the milliseconds specified by the last arg, tracking the concurrent lookups
+    #[derive(Default)]
+    struct Sleepy {
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+    impl ItemSource<u64> for Arc<Sleepy> {
+        type Item = u64;
+        type Error = String;
+
+        fn lookup(&self, args: &[u64]) -> Result<Vec<u64>, String> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            let millis = args.last().copied().unwrap_or_default();
+            std::thread::sleep(Duration::from_millis(millis));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            if millis == 0 {
+                Err("no delay".to_owned())
+            } else {
+                Ok(vec![millis])
+            }
+        }
+    }
+
+    fn node_id(sequence: usize) -> NodeIdTyped {
+        crate::parse_id(&format!(".0#{sequence}")).expect("valid id")
+    }
+
+    fn run_all(params: LookupWorkerParams, delays: &[u64]) -> (Vec<LookupEvent<u64>>, Arc<Sleepy>) {
+        let source = Arc::new(Sleepy::default());
+        let (event_tx, event_rx) = mpsc::channel();
+        let event_tx = std::sync::Mutex::new(event_tx);
+        let worker = LookupWorker::new(Arc::clone(&source), params, move |event| {
+            let _ = event_tx.lock().expect("not poisoned").send(event);
+        })
+        .expect("spawns threads");
+        let mut deferred = DeferredLookups::new();
+        for (sequence, &delay) in delays.iter().enumerate() {
+            deferred.push(node_id(sequence), vec![1, delay]);
+        }
+        for lookup in deferred.take_pending() {
+            worker.submit(lookup);
+        }
+        let events = (0..delays.len() * 2)
+            .map(|_| {
+                event_rx
+                    .recv_timeout(Duration::from_secs(5))
+                    .expect("event within 5 seconds")
+            })
+            .collect();
+        (events, source)
+    }
+
+    #[test]
+    fn concurrency_limit() {
+        let params = LookupWorkerParams {
+            concurrency: NonZeroUsize::new(2).expect("nonzero"),
+            timeout: None,
+        };
+        let (events, source) = run_all(params, &[30, 30, 30, 30, 30]);
+        // NOTE: fewer may overlap on a busy machine
+        assert!(source.max_running.load(Ordering::SeqCst) <= 2);
+
+        let mut finished: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                LookupEvent::Finished(id, items) => Some((id.node_id().sequence(), items.clone())),
+                _ => None,
+            })
+            .collect();
+        finished.sort_unstable();
+        let expected: Vec<_> = (0..5).map(|sequence| (sequence, vec![30])).collect();
+        assert_eq!(finished, expected);
+    }
+
+    #[test]
+    fn timed_out_holds_concurrency() {
+        let params = LookupWorkerParams {
+            concurrency: NonZeroUsize::new(1).expect("nonzero"),
+            timeout: Some(Duration::from_millis(20)),
+        };
+        let (events, source) = run_all(params, &[100, 100, 100]);
+        assert_eq!(source.max_running.load(Ordering::SeqCst), 1);
+        let timed_out = events
+            .iter()
+            .filter(|event| matches!(event, LookupEvent::TimedOut(..)))
+            .count();
+        assert_eq!(timed_out, 3);
+    }
+
+    #[test]
+    fn failure_and_timeout() {
+        let params = LookupWorkerParams {
+            concurrency: NonZeroUsize::new(3).expect("nonzero"),
+            timeout: Some(Duration::from_millis(200)),
+        };
+        let (events, _) = run_all(params, &[10, 0, 2_000]);
+        let outcome = |sequence| {
+            events
+                .iter()
+                .filter(|event| event.node_id().sequence() == sequence)
+                .map(|event| match event {
+                    LookupEvent::Started(_) => "started".to_owned(),
+                    LookupEvent::Finished(_, items) => format!("finished {items:?}"),
+                    LookupEvent::Failed(_, err) => format!("failed {err}"),
+                    LookupEvent::TimedOut(_, timeout) => format!("timed out {timeout:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(outcome(0), ["started", "finished [10]"]);
+        assert_eq!(outcome(1), ["started", "failed no delay"]);
+        assert_eq!(outcome(2), ["started", "timed out 200ms"]);
+    }
+}
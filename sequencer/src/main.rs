@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! Binary for running [`Sequencer`] interactively
 
 // TODO: only while building
@@ -24,6 +24,7 @@ use sequencer::{
 use std::{
     fs::File,
     io::{stdin, BufRead, BufReader},
+    time::Duration,
 };
 
 const COMMAND_NAME: &str = "sequencer";
@@ -91,7 +92,9 @@ enum PersistenceCommand {
 struct Cli {
     #[allow(clippy::struct_field_names)]
     sequencer_cli: sequencer::cli::Cli<source::Source, source::FilterArgParser, source::TypedArg>,
-    sequencer_config_file: Option<SequencerConfigFile<String, Option<source::TypedArg>>>,
+    sequencer_config_file: Option<SequencerConfigFile<source::QueueItem, Option<source::TypedArg>>>,
+    /// Shares the lookup cache with the `sequencer_cli` source, for storing the cache
+    cache_source: source::Source,
     /// Terminates on the first error encountered (implied for `--script` mode)
     fatal: bool,
     /// If true, echo all commands to stdout
@@ -103,9 +106,13 @@ mod source {
     use serde::{Deserialize, Serialize};
     use std::path::PathBuf;
 
+    pub(crate) use sequencer::sources::QueueItem;
     use sequencer::{
         persistence::OptionStructSerializeDeserialize,
-        sources::{Beet, FileLines, FolderListing},
+        sources::{
+            Beet, CacheParams, Cached, FileLines, FolderFilter, FolderListing, PathError,
+            PlaylistEntry, PlaylistFile,
+        },
         DebugItemSource,
     };
     sequencer::source_multi_select! {
@@ -115,18 +122,21 @@ mod source {
             #[derive(Copy, Clone, ValueEnum)]
             type Type = Type;
             /// Beet
-            beet: Option<Beet> as Beet where arg type = Vec<String>,
+            beet: Cached<Option<Beet>> as Beet where arg type = Vec<String>,
             /// File lines
-            file_lines: FileLines as FileLines where arg type = String,
+            file_lines: Cached<FileLines> as FileLines where arg type = String,
             /// Folder listing
-            folder_listing: FolderListing as FolderListing where arg type = String,
+            folder_listing: Cached<FolderListing> as FolderListing where arg type = FolderFilter,
+            /// Playlist file (M3U/M3U8 or PLS)
+            playlist_file: Cached<PlaylistFile, PlaylistEntry>
+                as PlaylistFile where arg type = String,
             /// Debug
             debug: DebugItemSource as Debug where arg type = String,
         }
         #[derive(Clone, Debug, Serialize, Deserialize)]
         /// Typed argument
         impl ItemSource<Option<TypedArg>> {
-            type Item = String;
+            type Item = QueueItem;
             /// Typed Error
             type Error = TypedLookupError;
         }
@@ -136,15 +146,25 @@ mod source {
         pub fn new(
             root_folder: PathBuf,
             beet_cmd: Option<String>,
+            cache: &CacheParams,
         ) -> Result<Self, super::MainError> {
             let beet = beet_cmd.map(Beet::new).transpose()?;
             Ok(Self {
                 debug: DebugItemSource,
-                file_lines: FileLines::new(root_folder.clone())?,
-                folder_listing: FolderListing::new(root_folder)?,
-                beet,
+                file_lines: cache.wrap(FileLines::new(root_folder.clone())?, "file_lines")?,
+                folder_listing: cache
+                    .wrap(FolderListing::new(root_folder.clone())?, "folder_listing")?,
+                playlist_file: cache.wrap(PlaylistFile::new(root_folder)?, "playlist_file")?,
+                beet: cache.wrap(beet, "beet")?,
             })
         }
+        /// Stores the cached lookups (if changed), see [`Cached::flush`]
+        pub fn flush_cache(&self) -> Result<(), PathError> {
+            self.beet.flush()?;
+            self.file_lines.flush()?;
+            self.folder_listing.flush()?;
+            self.playlist_file.flush()
+        }
     }
     pub(super) struct FilterArgParser {
         pub default_type: Type,
@@ -156,18 +176,21 @@ mod source {
             &self,
             args: Vec<String>,
             source_type: Option<Type>,
-        ) -> Option<TypedArg> {
+        ) -> Result<Option<TypedArg>, String> {
             if args.is_empty() {
-                None
+                Ok(None)
             } else {
                 let joined = args.join(" ");
                 let filter = match source_type.unwrap_or(self.default_type) {
                     Type::Debug => TypedArg::Debug(joined),
                     Type::FileLines => TypedArg::FileLines(joined),
-                    Type::FolderListing => TypedArg::FolderListing(joined),
+                    Type::FolderListing => TypedArg::FolderListing(
+                        FolderFilter::from_args(&args).map_err(|err| err.to_string())?,
+                    ),
+                    Type::PlaylistFile => TypedArg::PlaylistFile(joined),
                     Type::Beet => TypedArg::Beet(args),
                 };
-                Some(filter)
+                Ok(Some(filter))
             }
         }
     }
@@ -179,7 +202,10 @@ impl Cli {
     {
         for (line_number, line) in input.lines().enumerate() {
             let line = line?;
-            match self.exec_line(&line) {
+            let result = self.exec_line(&line);
+            // store lookups once per command, instead of per lookup
+            self.cache_source.flush_cache()?;
+            match result {
                 Ok(Some(shared::Shutdown)) => {
                     self.sequencer_cli.output(format_args!("exited cleanly"));
                     return Ok(());
@@ -249,7 +275,16 @@ impl Cli {
             let popped = self.sequencer_cli.pop_next();
             if let Some(item) = popped {
                 let (node_seq, item) = item.into_parts();
-                println!("Item {item:?}, from node #{node_seq}");
+                let source::QueueItem {
+                    path,
+                    title,
+                    duration_secs,
+                } = item;
+                let title = title.map(|title| format!(" {title:?}")).unwrap_or_default();
+                let duration = duration_secs
+                    .map(|secs| format!(" ({secs}s)"))
+                    .unwrap_or_default();
+                println!("Item {path:?}{title}{duration}, from node #{node_seq}");
             } else {
                 println!("No items remaining");
                 break;
@@ -272,7 +307,7 @@ impl Cli {
     fn exec_persist_command(&mut self, cmd: PersistenceCommand) -> Result<(), MainError> {
         match cmd {
             PersistenceCommand::Load { file } => {
-                let result: Result<(_, sequencer::SequencerTree<String, _>), _> =
+                let result: Result<(_, sequencer::SequencerTree<source::QueueItem, _>), _> =
                     SequencerConfigFile::read_from_file(file);
                 match result {
                     Ok((scf, tree)) => {
@@ -309,6 +344,7 @@ impl Cli {
 }
 
 #[derive(Parser)]
+#[allow(clippy::struct_excessive_bools)] // independent command-line flags
 struct MainArgs {
     /// Command to use for the [`Beet`] item source type
     #[clap(long)]
@@ -328,12 +364,35 @@ struct MainArgs {
     /// Terminates on the first error encountered (implied for `--script` mode)
     #[clap(long, action)]
     fatal: bool,
+    /// Reuse item source lookups until the files change (or the nodes are refreshed)
+    #[clap(long, action)]
+    cache: bool,
+    /// Maximum age of cached lookups, in seconds (implies `--cache`)
+    #[clap(long)]
+    cache_ttl_secs: Option<u64>,
+    /// Folder to store cached lookups across runs (implies `--cache`)
+    #[clap(long)]
+    cache_dir: Option<std::path::PathBuf>,
+}
+impl From<&MainArgs> for sequencer::sources::CacheParams {
+    fn from(args: &MainArgs) -> Self {
+        let enabled = args.cache || args.cache_ttl_secs.is_some() || args.cache_dir.is_some();
+        if enabled {
+            Self {
+                ttl: args.cache_ttl_secs.map(Duration::from_secs),
+                folder: args.cache_dir.clone(),
+            }
+        } else {
+            Self::disabled()
+        }
+    }
 }
 #[derive(Clone, ValueEnum)]
 enum ItemSourceType {
     Debug,
     FileLines,
     FolderListing,
+    PlaylistFile,
     Beet,
 }
 impl From<&MainArgs> for OutputParams {
@@ -374,14 +433,16 @@ fn main() -> Result<(), MainError> {
     let source_type = args.source_type.unwrap_or(source::Type::FileLines);
     let root_path = ".".into();
     let params = OutputParams::from(&args);
+    let cache_params = sequencer::sources::CacheParams::from(&args);
     let beet_cmd = args.beet_cmd; // .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "missing beet_cmd"))?;
-    let source = source::Source::new(root_path, beet_cmd)?;
+    let source = source::Source::new(root_path, beet_cmd, &cache_params)?;
     let filter_arg_parser = source::FilterArgParser {
         default_type: source_type,
     };
     let fatal = args.fatal | args.script.is_some();
     let preloaded_tree = None;
     let mut cli = Cli {
+        cache_source: source.clone(),
         sequencer_cli: sequencer::cli::Cli::new(source, filter_arg_parser, params, preloaded_tree),
         sequencer_config_file: None,
         fatal,
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 //! Converts from KDL types to a Rust type by driving a `serde::Deserializer`
 
@@ -14,6 +14,8 @@ pub struct DeserializeVisitor {
     entries: VecDeque<Entry>,
     current_key: Option<String>,
     current_value: Option<Value>,
+    /// Additional values for the current property key (repeated properties form a sequence)
+    repeated_values: Option<(String, VecDeque<Value>)>,
     variant: Option<String>,
 }
 #[derive(Debug, Clone, PartialEq)]
@@ -43,6 +45,10 @@ pub enum Error {
     PendingKey(String),
     PendingValue(Value),
     PendingVariant(String),
+    RepeatedProperty {
+        key: String,
+        values: Vec<Value>,
+    },
     DuplicateVariant {
         prev_variant: String,
         variant: String,
@@ -93,6 +99,10 @@ impl std::fmt::Display for Error {
             Self::PendingVariant(variant) => {
                 write!(f, "finished, but pending variant: {variant:?}")
             }
+            Self::RepeatedProperty { key, values } => write!(
+                f,
+                "expected single value for key {key:?}, found repeated values: {values:?}"
+            ),
             Self::DuplicateVariant {
                 prev_variant,
                 variant,
@@ -143,6 +153,7 @@ impl DeserializeVisitor {
             entries,
             current_key,
             current_value,
+            repeated_values,
             variant,
         } = self;
         if !entries.is_empty() {
@@ -151,6 +162,11 @@ impl DeserializeVisitor {
             Err(Error::PendingKey(current_key))
         } else if let Some(current_value) = current_value {
             Err(Error::PendingValue(current_value))
+        } else if let Some((key, values)) = repeated_values {
+            Err(Error::RepeatedProperty {
+                key,
+                values: values.into(),
+            })
         } else if let Some(variant) = variant {
             Err(Error::PendingVariant(variant))
         } else {
@@ -194,9 +210,14 @@ impl DeserializeVisitor {
             entries,
             current_key,
             current_value,
+            repeated_values,
             variant,
         } = self;
-        entries.is_empty() && current_key.is_none() && current_value.is_none() && variant.is_none()
+        entries.is_empty()
+            && current_key.is_none()
+            && current_value.is_none()
+            && repeated_values.is_none()
+            && variant.is_none()
     }
 }
 impl KdlEntryVisitor for DeserializeVisitor {
@@ -505,7 +526,21 @@ impl<'de> serde::de::Deserializer<'de> for &mut DeserializeVisitor {
         V: serde::de::Visitor<'de>,
     {
         // Err(Error::unimplemented_type("seq"))
-        visitor.visit_seq(self)
+        if let Some(first) = self.current_value.take() {
+            // property value, with any repeated values for the same key
+            let mut values = self
+                .repeated_values
+                .take()
+                .map(|(_key, values)| values)
+                .unwrap_or_default();
+            values.push_front(first);
+            visitor.visit_seq(RepeatedValues {
+                deserializer: self,
+                values,
+            })
+        } else {
+            visitor.visit_seq(self)
+        }
     }
 
     fn deserialize_tuple<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
@@ -563,8 +598,29 @@ impl<'de> serde::de::MapAccess<'de> for &mut DeserializeVisitor {
     where
         K: serde::de::DeserializeSeed<'de>,
     {
+        if let Some((key, values)) = self.repeated_values.take() {
+            return Err(Error::RepeatedProperty {
+                key,
+                values: values.into(),
+            }
+            .into());
+        }
         match self.entries.pop_front() {
             Some(Entry::Property { key, value }) => {
+                let mut repeated = VecDeque::new();
+                self.entries.retain(|entry| match entry {
+                    Entry::Property {
+                        key: other,
+                        value: other_value,
+                    } if *other == key => {
+                        repeated.push_back(other_value.clone());
+                        false
+                    }
+                    _ => true,
+                });
+                if !repeated.is_empty() {
+                    self.repeated_values = Some((key.clone(), repeated));
+                }
                 let existing_key = self.current_key.replace(key);
                 let existing_value = self.current_value.replace(value);
 
@@ -659,3 +715,25 @@ impl<'de> serde::de::SeqAccess<'de> for &mut DeserializeVisitor {
         }
     }
 }
+
+/// Sequence of the values for a repeated property key
+struct RepeatedValues<'a> {
+    deserializer: &'a mut DeserializeVisitor,
+    values: VecDeque<Value>,
+}
+impl<'de> serde::de::SeqAccess<'de> for RepeatedValues<'_> {
+    type Error = SuperError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        let Some(value) = self.values.pop_front() else {
+            return Ok(None);
+        };
+        if let Some(existing_value) = self.deserializer.current_value.replace(value) {
+            return Err(Error::NextValueExistingValue(existing_value).into());
+        }
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+}
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 use crate::persistence::KdlEntryVisitor;
 use serde::Serialize;
@@ -25,28 +25,6 @@ impl<V> std::fmt::Display for NeverSerialize<V> {
         }
     }
 }
-impl<V> serde::ser::SerializeSeq for NeverSerialize<V>
-where
-    V: KdlEntryVisitor,
-{
-    type Ok = ();
-    type Error = super::Error<V::Error>;
-
-    fn serialize_element<T>(&mut self, _value: &T) -> Result<(), Self::Error>
-    where
-        T: ?Sized + Serialize,
-    {
-        match *self {
-            NeverSerialize::Never { never, _marker } => match never {},
-        }
-    }
-
-    fn end(self) -> Result<Self::Ok, Self::Error> {
-        match self {
-            NeverSerialize::Never { never, _marker } => match never {},
-        }
-    }
-}
 impl<V> serde::ser::SerializeTuple for NeverSerialize<V>
 where
     V: KdlEntryVisitor,
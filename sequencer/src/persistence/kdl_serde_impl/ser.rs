@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 //! Converts from a Rust type to KDL by acting as a `serde::Serializer`
 
@@ -127,15 +127,15 @@ where
         }
     }
 }
-impl<V> serde::ser::Serializer for &mut Serializer<V>
+impl<'a, V> serde::ser::Serializer for &'a mut Serializer<V>
 where
     V: KdlEntryVisitor,
 {
     type Ok = ();
     type Error = SuperError<V::Error>;
 
+    type SerializeSeq = SerializeRepeated<'a, V>;
     // No additional state needed
-    type SerializeSeq = NeverSerialize<V>;
     type SerializeTuple = NeverSerialize<V>;
     type SerializeTupleStruct = NeverSerialize<V>;
     type SerializeTupleVariant = Self;
@@ -275,7 +275,10 @@ where
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(self.unimplemented_type("seq"))
+        Ok(SerializeRepeated {
+            key: self.pending_key.take(),
+            serializer: self,
+        })
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
@@ -357,6 +360,30 @@ where
         }
     }
 }
+/// Serializes each sequence element as a separate entry, repeating the key (if any)
+pub struct SerializeRepeated<'a, V> {
+    serializer: &'a mut Serializer<V>,
+    key: Option<&'static str>,
+}
+impl<V> serde::ser::SerializeSeq for SerializeRepeated<'_, V>
+where
+    V: KdlEntryVisitor,
+{
+    type Ok = ();
+    type Error = SuperError<V::Error>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.serializer.pending_key = self.key;
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
 impl<V> serde::ser::SerializeStructVariant for &mut Serializer<V>
 where
     V: KdlEntryVisitor,
@@ -1,6 +1,10 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
-use crate::persistence::{OptionStructSerializeDeserialize, SequencerConfig};
+use crate::{
+    persistence::{OptionStructSerializeDeserialize, SequencerConfig},
+    sources::FolderFilter,
+    SequencerTree,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -234,11 +238,6 @@ mod error_messages_for_unimplemented {
                 #[serde(default)]
                 nothingness_in_a_package: (),
             }
-            {
-                for r#"type "seq""#;
-                #[serde(default)]
-                illegal_vec_field_name: Vec<()>,
-            }
             {
                 for r#"type "tuple""#;
                 #[serde(default)]
@@ -251,7 +250,7 @@ mod error_messages_for_unimplemented {
             }
         };
         // verify macro counter is as expected (avoid dead macro)
-        assert_eq!(cases_count_type, 9);
+        assert_eq!(cases_count_type, 8);
     }
 
     #[test]
@@ -347,3 +346,82 @@ mod error_messages_for_unimplemented {
         }
     }
 }
+
+const INPUT_FOLDER_FILTER: &str = r#"
+root path="music" exclude="Live Recordings" exclude="*demo*" {
+    /* lossless only */
+    leaf weight=3 path="albums" include="*.flac" extension="flac" include="*/disc?/*" extension="FLAC" max_depth=2
+    leaf path="linked" follow_symlinks=true
+    leaf;
+}
+"#;
+
+#[test]
+fn round_trip_folder_filter() {
+    let (mut config, seq_tree) =
+        SequencerConfig::<(), Option<FolderFilter>>::parse_from_str(INPUT_FOLDER_FILTER)
+            .expect("valid KDL");
+
+    {
+        let tree = &seq_tree.tree;
+        let albums_path = tree.root_id().append(0);
+        let (weight, albums_node) = albums_path.try_ref_shared(tree).expect("node exists");
+        assert_eq!(weight, 3);
+        assert_eq!(
+            albums_node.filter,
+            Some(FolderFilter {
+                path: "albums".to_string(),
+                include: vec!["*.flac".to_string(), "*/disc?/*".to_string()],
+                extensions: vec!["flac".to_string(), "FLAC".to_string()],
+                max_depth: std::num::NonZeroUsize::new(2),
+                ..FolderFilter::default()
+            })
+        );
+        let linked_path = tree.root_id().append(1);
+        let (_, linked_node) = linked_path.try_ref_shared(tree).expect("node exists");
+        assert_eq!(
+            linked_node.filter,
+            Some(FolderFilter {
+                follow_symlinks: true,
+                ..FolderFilter::new("linked".to_string())
+            })
+        );
+    }
+
+    // complete round-trip
+    let output = config
+        .update_to_string(&seq_tree)
+        .expect("re-serialize works");
+    assert_eq!(output, INPUT_FOLDER_FILTER);
+}
+
+#[test]
+fn folder_filter_new_document() {
+    let filter = FolderFilter {
+        exclude: vec!["Live Recordings".to_string(), "*demo*".to_string()],
+        max_depth: std::num::NonZeroUsize::new(4),
+        follow_symlinks: true,
+        ..FolderFilter::new("music".to_string())
+    };
+    let seq_tree = SequencerTree::<(), _>::new(Some(filter));
+
+    let output = SequencerConfig::default()
+        .update_to_string(&seq_tree)
+        .expect("serialize works");
+    assert_eq!(
+        output,
+        "root path=\"music\" exclude=\"Live Recordings\" exclude=\"*demo*\" max_depth=4 follow_symlinks=true\n"
+    );
+}
+
+#[test]
+fn folder_filter_repeated_scalar() {
+    const INPUT: &str = r#"root path="music" path="videos""#;
+    let Err(err) = SequencerConfig::<(), Option<FolderFilter>>::parse_from_str(INPUT) else {
+        panic!("expected error for repeated path");
+    };
+    assert!(
+        format!("{err:?}").contains(r#"expected single value for key "path""#),
+        "{err:?}"
+    );
+}
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! [`ItemSource`] types
 
 use std::{
@@ -6,13 +6,19 @@ use std::{
     path::{Path, PathBuf},
 };
 
-pub use file::FolderListing;
 pub use file::Lines as FileLines;
+pub use file::{FolderFilter, FolderFilterArgError, FolderListing};
 mod file;
 
+pub use playlist::{PlaylistEntry, PlaylistFile};
+mod playlist;
+
 pub use beet::Beet;
 mod beet;
 
+pub use cache::{CacheError, CacheParams, Cached, LastModified};
+mod cache;
+
 pub mod multi_select;
 
 /// Source of items for the [`Sequencer`](`super::Sequencer`)
@@ -28,9 +34,57 @@ pub trait ItemSource<T> {
     /// # Errors
     /// Returns an error if the underlying lookup operation fails
     fn lookup(&self, args: &[T]) -> Result<Vec<Self::Item>, Self::Error>;
+    /// Discards any cached results for the specified arguments, so the next
+    /// [`lookup`](`Self::lookup`) retrieves fresh items
+    ///
+    /// The default implementation does nothing (for sources without a cache)
+    fn invalidate(&self, args: &[T]) {
+        let _ = args;
+    }
+}
+
+/// Common item for heterogeneous [`ItemSource`]s (e.g. in a
+/// [`source_multi_select!`](`crate::source_multi_select`)), keeping any hints from the source
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct QueueItem {
+    /// Path or URL of the item
+    pub path: String,
+    /// Title hint (e.g. from a playlist file)
+    pub title: Option<String>,
+    /// Duration hint, in seconds (e.g. from a playlist file)
+    pub duration_secs: Option<u64>,
+}
+impl From<String> for QueueItem {
+    fn from(path: String) -> Self {
+        Self {
+            path,
+            title: None,
+            duration_secs: None,
+        }
+    }
+}
+impl From<PlaylistEntry> for QueueItem {
+    fn from(entry: PlaylistEntry) -> Self {
+        let PlaylistEntry {
+            path,
+            title,
+            duration_secs,
+        } = entry;
+        Self {
+            path,
+            title,
+            duration_secs,
+        }
+    }
+}
+impl AsRef<str> for QueueItem {
+    fn as_ref(&self) -> &str {
+        &self.path
+    }
 }
 
-/// Handle to a root folder, for use in creating [`FileLines`] or [`FolderListing`]
+/// Handle to a root folder, for use in creating [`FileLines`], [`FolderListing`] or
+/// [`PlaylistFile`]
 // TODO is this an antipattern? (TOC-TOU) don't want the inner-error handler to be weak
 // Unclear; "fail-fast" behavior is best, in case the first lookup occurs later in execution
 #[derive(Clone)]
@@ -98,3 +152,10 @@ impl PathError {
         move |error| Self::new(&path, error)
     }
 }
+
+/// Returns the modification time of the file or folder, if available
+fn modified_time(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,400 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Caching wrapper for slow [`ItemSource`]s (e.g. [`Beet`](`super::Beet`) queries)
+//!
+//! Cached lookups are keyed by the filter-argument chain, and refetched when:
+//!
+//! - the entry is older than the time-to-live (if any)
+//! - the source reports files modified after the entry was fetched (see [`LastModified`])
+//! - the entry was explicitly invalidated (see [`ItemSource::invalidate`])
+//!
+//! Persisted caches are only written by [`Cached::flush`], so many lookups cost a single write.
+
+use super::{ItemSource, PathError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+/// Source with items read from files, allowing [`Cached`] lookups to detect changes
+pub trait LastModified<T> {
+    /// Returns the latest modification time of the files read by a lookup for the args
+    ///
+    /// Returns `None` if unknown (e.g. not backed by files, or the files are missing)
+    fn last_modified(&self, args: &[T]) -> Option<SystemTime>;
+}
+
+/// Caches the lookups of the inner [`ItemSource`]
+///
+/// Clones share the same cache.
+#[derive(Clone)]
+pub struct Cached<S, I = String> {
+    inner: S,
+    ttl: Option<Duration>,
+    persist_path: Option<PathBuf>,
+    entries: Arc<Mutex<Entries<I>>>,
+}
+struct Entries<I> {
+    by_key: BTreeMap<String, Entry<I>>,
+    /// Changed since the last [`Cached::flush`]
+    unsaved: bool,
+}
+impl<I> Default for Entries<I> {
+    fn default() -> Self {
+        Self {
+            by_key: BTreeMap::new(),
+            unsaved: false,
+        }
+    }
+}
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry<I> {
+    fetched: SystemTime,
+    items: Vec<I>,
+}
+impl<S, I> Cached<S, I> {
+    /// Creates a cache for the specified source, with no expiry
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            ttl: None,
+            persist_path: None,
+            entries: Arc::default(),
+        }
+    }
+    /// Sets the maximum age of cached lookups
+    ///
+    /// A zero `ttl` disables caching, passing all lookups to the inner source.
+    #[must_use]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+    /// Sets the file to load the cache from, and store the cache to on [`Self::flush`]
+    ///
+    /// # Errors
+    /// Returns an error if the existing file cannot be read or parsed
+    pub fn with_persistence(mut self, path: PathBuf) -> Result<Self, PathError>
+    where
+        I: DeserializeOwned,
+    {
+        let by_key = match std::fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents).map_err(|err| {
+                let err = std::io::Error::new(std::io::ErrorKind::InvalidData, err);
+                PathError::new(&path, err)
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(PathError::new(&path, err)),
+        };
+        self.entries = Arc::new(Mutex::new(Entries {
+            by_key,
+            unsaved: false,
+        }));
+        self.persist_path = Some(path);
+        Ok(self)
+    }
+    /// Returns the inner source
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+    /// Removes all cached lookups
+    pub fn clear(&self) {
+        let mut entries = self.lock_entries();
+        entries.by_key.clear();
+        entries.unsaved = true;
+    }
+    /// Stores the cache to the persistence file (if any), when changed since the last flush
+    ///
+    /// Lookups only update the cache in memory, so call this periodically and before exiting.
+    ///
+    /// # Errors
+    /// Returns an error if the persistence file cannot be written
+    pub fn flush(&self) -> Result<(), PathError>
+    where
+        I: Serialize,
+    {
+        let Some(path) = &self.persist_path else {
+            return Ok(());
+        };
+        let mut entries = self.lock_entries();
+        if !entries.unsaved {
+            return Ok(());
+        }
+        let contents = serde_json::to_vec(&entries.by_key).map_err(|err| {
+            let err = std::io::Error::new(std::io::ErrorKind::InvalidData, err);
+            PathError::new(path, err)
+        })?;
+        // write-then-rename, to keep the previous cache intact if interrupted
+        let temp_path = temp_path_for(path);
+        std::fs::write(&temp_path, contents)
+            .and_then(|()| std::fs::rename(&temp_path, path))
+            .map_err(PathError::with_path_fn(path.clone()))?;
+        entries.unsaved = false;
+        Ok(())
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, Entries<I>> {
+        // NOTE: entries are replaced whole, so a panic while locked cannot leave partial state
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    fn is_fresh<T>(&self, entry: &Entry<I>, args: &[T], now: SystemTime) -> bool
+    where
+        S: LastModified<T>,
+    {
+        let age = now.duration_since(entry.fetched).unwrap_or_default();
+        let is_expired = self.ttl.is_some_and(|ttl| age >= ttl);
+        // NOTE: modified in the same instant as fetched may have been after the fetch
+        let is_modified = self
+            .inner
+            .last_modified(args)
+            .is_some_and(|modified| modified >= entry.fetched);
+        !is_expired && !is_modified
+    }
+    fn lookup_at<T>(&self, args: &[T], now: SystemTime) -> Result<Vec<I>, CacheError<S::Error>>
+    where
+        S: ItemSource<T, Item = I> + LastModified<T>,
+        T: Serialize,
+        I: Serialize + Clone + PartialEq,
+    {
+        let is_disabled = self.ttl.is_some_and(|ttl| ttl.is_zero());
+        let key = match serde_json::to_string(args) {
+            Ok(key) if !is_disabled => key,
+            // not cacheable
+            _ => return self.inner.lookup(args).map_err(CacheError::Lookup),
+        };
+        if let Some(entry) = self.lock_entries().by_key.get(&key) {
+            if self.is_fresh(entry, args, now) {
+                return Ok(entry.items.clone());
+            }
+        }
+
+        let items = self.inner.lookup(args).map_err(CacheError::Lookup)?;
+        let mut entries = self.lock_entries();
+        let entry = Entry {
+            fetched: now,
+            items: items.clone(),
+        };
+        entries.by_key.insert(key, entry);
+        entries.unsaved = true;
+        Ok(items)
+    }
+}
+impl<S, T, I> ItemSource<T> for Cached<S, I>
+where
+    S: ItemSource<T, Item = I> + LastModified<T>,
+    T: Serialize,
+    I: Serialize + Clone + PartialEq,
+{
+    type Item = I;
+    type Error = CacheError<S::Error>;
+
+    fn lookup(&self, args: &[T]) -> Result<Vec<Self::Item>, Self::Error> {
+        self.lookup_at(args, SystemTime::now())
+    }
+    fn invalidate(&self, args: &[T]) {
+        if let Ok(key) = serde_json::to_string(args) {
+            let mut entries = self.lock_entries();
+            if entries.by_key.remove(&key).is_some() {
+                entries.unsaved = true;
+            }
+        }
+        self.inner.invalidate(args);
+    }
+}
+
+/// Caching parameters for wrapping several sources in [`Cached`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheParams {
+    /// Maximum age of cached lookups (zero disables caching, `None` is unlimited)
+    pub ttl: Option<Duration>,
+    /// Folder for storing the cached lookups, one file per item source
+    pub folder: Option<PathBuf>,
+}
+impl CacheParams {
+    /// Parameters that pass all lookups to the inner source
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self {
+            ttl: Some(Duration::ZERO),
+            folder: None,
+        }
+    }
+    /// Wraps the source in a [`Cached`], persisted to the file `name.json` in the folder (if any)
+    ///
+    /// # Errors
+    /// Returns an error if the existing persisted cache cannot be read or parsed
+    pub fn wrap<S, I>(&self, inner: S, name: &str) -> Result<Cached<S, I>, PathError>
+    where
+        I: DeserializeOwned,
+    {
+        let mut cached = Cached::new(inner);
+        if let Some(ttl) = self.ttl {
+            cached = cached.with_ttl(ttl);
+        }
+        match &self.folder {
+            Some(folder) => cached.with_persistence(folder.join(format!("{name}.json"))),
+            None => Ok(cached),
+        }
+    }
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    path.with_file_name(temp_name)
+}
+
+/// Error from a [`Cached`] lookup
+pub enum CacheError<E> {
+    /// Inner lookup failed
+    Lookup(E),
+}
+impl<E> std::fmt::Display for CacheError<E>
+where
+    E: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lookup(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cached, LastModified};
+    use crate::sources::ItemSource;
+    use std::{
+        cell::Cell,
+        time::{Duration, SystemTime},
+    };
+
+    /// Counts lookups, reporting the configured modification time
+    #[derive(Default)]
+    struct Counting {
+        lookups: Cell<usize>,
+        modified: Cell<Option<SystemTime>>,
+    }
+    impl ItemSource<String> for Counting {
+        type Item = String;
+        type Error = shared::Never;
+
+        fn lookup(&self, args: &[String]) -> Result<Vec<String>, Self::Error> {
+            let count = self.lookups.get() + 1;
+            self.lookups.set(count);
+            Ok(vec![format!("{} #{count}", args.join("/"))])
+        }
+    }
+    impl LastModified<String> for Counting {
+        fn last_modified(&self, _args: &[String]) -> Option<SystemTime> {
+            self.modified.get()
+        }
+    }
+
+    fn at_secs(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+    fn lookup(cache: &Cached<Counting>, args: &[&str], secs: u64) -> String {
+        let args: Vec<_> = args.iter().map(ToString::to_string).collect();
+        let Ok(mut items) = cache.lookup_at(&args, at_secs(secs));
+        items.pop().unwrap_or_default()
+    }
+
+    #[test]
+    fn keyed_by_args() {
+        let cache = Cached::new(Counting::default());
+        assert_eq!(lookup(&cache, &["a", "b"], 0), "a/b #1");
+        assert_eq!(lookup(&cache, &["a", "c"], 0), "a/c #2");
+        assert_eq!(lookup(&cache, &["a", "b"], 100), "a/b #1");
+        assert_eq!(lookup(&cache, &["a"], 100), "a #3");
+    }
+
+    #[test]
+    fn ttl_expiry() {
+        let cache = Cached::new(Counting::default()).with_ttl(Duration::from_secs(10));
+        assert_eq!(lookup(&cache, &["a"], 0), "a #1");
+        assert_eq!(lookup(&cache, &["a"], 9), "a #1");
+        assert_eq!(lookup(&cache, &["a"], 10), "a #2");
+        assert_eq!(lookup(&cache, &["a"], 15), "a #2");
+    }
+
+    #[test]
+    fn ttl_zero_disabled() {
+        let cache = Cached::new(Counting::default()).with_ttl(Duration::ZERO);
+        assert_eq!(lookup(&cache, &["a"], 0), "a #1");
+        assert_eq!(lookup(&cache, &["a"], 0), "a #2");
+        assert!(cache.lock_entries().by_key.is_empty());
+    }
+
+    #[test]
+    fn modified_invalidates() {
+        let cache = Cached::new(Counting::default());
+        cache.inner().modified.set(Some(at_secs(5)));
+        assert_eq!(lookup(&cache, &["a"], 10), "a #1");
+        assert_eq!(lookup(&cache, &["a"], 20), "a #1");
+
+        cache.inner().modified.set(Some(at_secs(30)));
+        assert_eq!(lookup(&cache, &["a"], 40), "a #2");
+        assert_eq!(lookup(&cache, &["a"], 50), "a #2");
+    }
+
+    #[test]
+    fn explicit_invalidate() {
+        let cache = Cached::new(Counting::default());
+        assert_eq!(lookup(&cache, &["a"], 0), "a #1");
+        assert_eq!(lookup(&cache, &["b"], 0), "b #2");
+
+        cache.invalidate(&["a".to_string()]);
+        assert_eq!(lookup(&cache, &["a"], 0), "a #3");
+        assert_eq!(lookup(&cache, &["b"], 0), "b #2");
+    }
+
+    #[test]
+    fn persistence() {
+        let path =
+            std::env::temp_dir().join(format!("sequencer-cache-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let load = || {
+            Cached::new(Counting::default())
+                .with_persistence(path.clone())
+                .map_err(|err| err.to_string())
+                .expect("loads")
+        };
+
+        let flush = |cache: &Cached<Counting>| {
+            cache
+                .flush()
+                .map_err(|err| err.to_string())
+                .expect("stores");
+        };
+
+        let cache = load();
+        assert_eq!(lookup(&cache, &["a"], 0), "a #1");
+        assert_eq!(lookup(&cache, &["b"], 0), "b #2");
+        // not stored until flushed
+        assert!(!path.exists());
+        flush(&cache);
+
+        // restart
+        let cache = load();
+        cache.invalidate(&["a".to_string()]);
+
+        // restart (invalidate not flushed)
+        let cache = load();
+        assert_eq!(lookup(&cache, &["c"], 100), "c #1");
+        assert_eq!(lookup(&cache, &["a"], 100), "a #1");
+        cache.invalidate(&["a".to_string()]);
+        flush(&cache);
+
+        // restart
+        let cache = load();
+        assert_eq!(lookup(&cache, &["d"], 100), "d #1");
+        assert_eq!(lookup(&cache, &["a"], 100), "a #2");
+        assert_eq!(lookup(&cache, &["b"], 100), "b #2");
+        assert_eq!(lookup(&cache, &["c"], 100), "c #1");
+
+        std::fs::remove_file(&path).expect("file exists");
+    }
+}
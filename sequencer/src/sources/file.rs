@@ -1,14 +1,19 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 use std::{
     ffi::OsStr,
     fs::File,
     io::{BufRead, BufReader},
-    path::PathBuf,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 use walkdir::WalkDir;
 
-use super::{ItemSource, PathError, RootFolder};
+use super::{ItemSource, LastModified, PathError, RootFolder};
+
+pub use filter::{ArgError as FolderFilterArgError, FolderFilter};
+mod filter;
 
 /// Reads items as lines from the filename specified by the filter args
 #[derive(Clone)]
@@ -51,8 +56,18 @@ where
             .map_err(err_with_path)
     }
 }
+impl<T> LastModified<T> for Lines
+where
+    T: AsRef<OsStr>,
+{
+    fn last_modified(&self, args: &[T]) -> Option<SystemTime> {
+        super::modified_time(&self.root.clone_to_child_path(args))
+    }
+}
 
 /// Lists files recursively from the folder specified by the filter args
+///
+/// Use [`FolderFilter`] args to limit the listed files (e.g. by glob pattern or extension)
 #[derive(Clone)]
 pub struct FolderListing {
     root: RootFolder,
@@ -65,6 +80,100 @@ impl FolderListing {
     pub fn new(root: PathBuf) -> Result<Self, PathError> {
         Ok(Self::from(RootFolder::new(root)?))
     }
+    fn list<'a>(
+        &self,
+        filters: impl Iterator<Item = &'a FolderFilter> + Clone,
+    ) -> Result<Vec<String>, PathError> {
+        let (folder_path, matchers) = self.folder_and_matchers(filters.clone())?;
+
+        let mut files: Vec<_> = Self::walk_allowed(&folder_path, filters, &matchers)
+            // ignore folders
+            .filter(|entry| !entry.path().is_dir())
+            .filter(|entry| {
+                let relative = relative_path(&folder_path, entry);
+                matchers
+                    .iter()
+                    .all(|matcher| matcher.is_file_allowed(&relative))
+            })
+            // clone into String, ignore non-UTF8 filenames
+            .filter_map(|entry| entry.path().to_str().map(String::from))
+            .collect();
+        // ensure determinstic ordering
+        files.sort();
+        Ok(files)
+    }
+    /// Returns the latest modification time of the walked folders
+    ///
+    /// Folders are modified when files are added, removed, or renamed directly within them, so
+    /// each folder listed within `max_depth` (and not excluded) is checked.
+    fn last_modified_folder<'a>(
+        &self,
+        filters: impl Iterator<Item = &'a FolderFilter> + Clone,
+    ) -> Option<SystemTime> {
+        let (folder_path, matchers) = self.folder_and_matchers(filters.clone()).ok()?;
+        let max_depth = Self::max_depth(filters.clone());
+        Self::walk_allowed(&folder_path, filters, &matchers)
+            // ignore folders at the max depth (contents not listed)
+            .filter(|entry| {
+                entry.file_type().is_dir()
+                    && max_depth.is_none_or(|max_depth| entry.depth() < max_depth.get())
+            })
+            .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+            .max()
+    }
+    fn folder_and_matchers<'a>(
+        &self,
+        filters: impl Iterator<Item = &'a FolderFilter> + Clone,
+    ) -> Result<(PathBuf, Vec<filter::Matcher>), PathError> {
+        let path_elems: Vec<_> = filters.clone().map(|filter| &filter.path).collect();
+        let folder_path = self.root.clone_to_child_path(&path_elems);
+        let matchers = filters
+            .map(filter::Matcher::new)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| {
+                let err = std::io::Error::new(std::io::ErrorKind::InvalidInput, err);
+                PathError::new(&folder_path, err)
+            })?;
+        Ok((folder_path, matchers))
+    }
+    /// Walks the folder, skipping excluded folders (except the listed folder itself)
+    fn walk_allowed<'a, 'b>(
+        folder_path: &'b PathBuf,
+        filters: impl Iterator<Item = &'a FolderFilter> + Clone,
+        matchers: &'b [filter::Matcher],
+    ) -> impl Iterator<Item = walkdir::DirEntry> + 'b {
+        Self::walk(folder_path, filters)
+            .into_iter()
+            .filter_entry(move |entry| {
+                entry.depth() == 0 || !entry.file_type().is_dir() || {
+                    let relative = relative_path(folder_path, entry);
+                    matchers
+                        .iter()
+                        .all(|matcher| matcher.is_folder_allowed(&relative))
+                }
+            })
+            // ignore errors (usually permission errors)
+            .filter_map(Result::ok)
+    }
+    fn walk<'a>(
+        folder_path: &PathBuf,
+        mut filters: impl Iterator<Item = &'a FolderFilter> + Clone,
+    ) -> WalkDir {
+        let max_depth = Self::max_depth(filters.clone());
+        let follow_symlinks = filters.any(|filter| filter.follow_symlinks);
+        let walk = WalkDir::new(folder_path).follow_links(follow_symlinks);
+        match max_depth {
+            Some(max_depth) => walk.max_depth(max_depth.get()),
+            None => walk,
+        }
+    }
+    fn max_depth<'a>(filters: impl Iterator<Item = &'a FolderFilter>) -> Option<NonZeroUsize> {
+        filters.filter_map(|filter| filter.max_depth).min()
+    }
+}
+fn relative_path(folder_path: &Path, entry: &walkdir::DirEntry) -> PathBuf {
+    let path = entry.path();
+    path.strip_prefix(folder_path).unwrap_or(path).to_owned()
 }
 impl From<RootFolder> for FolderListing {
     fn from(root: RootFolder) -> Self {
@@ -79,18 +188,104 @@ where
     type Error = PathError;
 
     fn lookup(&self, args: &[T]) -> Result<Vec<Self::Item>, Self::Error> {
-        let folder_path = self.root.clone_to_child_path(args);
-        let mut files: Vec<_> = WalkDir::new(folder_path)
-            .into_iter()
-            // ignore errors (usually permission errors)
-            .filter_map(Result::ok)
-            // ignore folders
-            .filter(|entry| !entry.path().is_dir())
-            // clone into String, ignore non-UTF8 filenames
-            .filter_map(|entry| entry.path().to_str().map(String::from))
+        let filters: Vec<_> = args
+            .iter()
+            .map(|path| FolderFilter::new(path.as_ref().to_string_lossy().into_owned()))
             .collect();
-        // ensure determinstic ordering
-        files.sort();
-        Ok(files)
+        self.list(filters.iter())
+    }
+}
+impl ItemSource<FolderFilter> for FolderListing {
+    type Item = String;
+    type Error = PathError;
+
+    fn lookup(&self, args: &[FolderFilter]) -> Result<Vec<Self::Item>, Self::Error> {
+        self.list(args.iter())
+    }
+}
+impl ItemSource<&FolderFilter> for FolderListing {
+    type Item = String;
+    type Error = PathError;
+
+    fn lookup(&self, args: &[&FolderFilter]) -> Result<Vec<Self::Item>, Self::Error> {
+        self.list(args.iter().copied())
+    }
+}
+impl<T> LastModified<T> for FolderListing
+where
+    T: AsRef<OsStr>,
+{
+    fn last_modified(&self, args: &[T]) -> Option<SystemTime> {
+        let filters: Vec<_> = args
+            .iter()
+            .map(|path| FolderFilter::new(path.as_ref().to_string_lossy().into_owned()))
+            .collect();
+        self.last_modified_folder(filters.iter())
+    }
+}
+impl LastModified<FolderFilter> for FolderListing {
+    fn last_modified(&self, args: &[FolderFilter]) -> Option<SystemTime> {
+        self.last_modified_folder(args.iter())
+    }
+}
+impl LastModified<&FolderFilter> for FolderListing {
+    fn last_modified(&self, args: &[&FolderFilter]) -> Option<SystemTime> {
+        self.last_modified_folder(args.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FolderFilter, FolderListing};
+    use std::{
+        fs::File,
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    fn set_modified(path: &Path, secs: u64) {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        File::open(path)
+            .and_then(|folder| folder.set_modified(time))
+            .expect("set folder modified time");
+    }
+
+    #[test]
+    fn last_modified_nested() {
+        let root =
+            std::env::temp_dir().join(format!("sequencer-folder-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for folder in ["music/album/disc1", "music/Live Recordings"] {
+            std::fs::create_dir_all(root.join(folder)).expect("create folder");
+        }
+        for folder in ["music", "music/album", "music/album/disc1"] {
+            set_modified(&root.join(folder), 100);
+        }
+        set_modified(&root.join("music/Live Recordings"), 400);
+        let listing = FolderListing::new(root.clone())
+            .map_err(|err| err.to_string())
+            .expect("root is a folder");
+        let last_modified = |args: &[&str]| {
+            let filter = FolderFilter::from_args(args).expect("valid args");
+            listing
+                .last_modified_folder(std::iter::once(&filter))
+                .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+        };
+
+        assert_eq!(last_modified(&["music"]), Some(400));
+        let exclude_live = ["music", "exclude=Live Recordings"];
+        assert_eq!(last_modified(&exclude_live), Some(100));
+
+        // nested change
+        set_modified(&root.join("music/album/disc1"), 200);
+        assert_eq!(last_modified(&exclude_live), Some(200));
+        // ...beyond the max depth
+        assert_eq!(
+            last_modified(&["music", "exclude=Live Recordings", "max_depth=2"]),
+            Some(100)
+        );
+
+        std::fs::remove_dir_all(&root).expect("remove test folder");
     }
 }
@@ -0,0 +1,279 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::{num::NonZeroUsize, path::Path};
+
+/// Filter argument for [`FolderListing`](`super::FolderListing`), selecting a folder and the files
+/// to list within it
+///
+/// Glob patterns containing `/` match the path relative to the listed folder, other patterns match
+/// the file (or folder) name only. Excluded folders are not walked.
+///
+/// Persisted as KDL properties, repeating the key for each list entry:
+///
+/// ```text
+/// leaf path="music" include="*.flac" include="*.mp3" exclude="Live Recordings" max_depth=2
+/// ```
+///
+/// The same keys are used for command-line style arguments, see [`Self::from_args`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderFilter {
+    /// Folder to list, relative to the root folder
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    /// Glob patterns for files to list (all files, if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Glob patterns for files and folders to skip
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// File extensions to list, case-insensitive (all extensions, if empty)
+    #[serde(rename = "extension", default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// Maximum folder depth to walk, where `1` lists only the files directly in the folder
+    /// (unlimited, if `None`)
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "some_value")]
+    pub max_depth: Option<NonZeroUsize>,
+    /// Follow symbolic links when walking folders
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub follow_symlinks: bool,
+}
+impl FolderFilter {
+    // NOTE: keys match the KDL property names
+    const KEY_INCLUDE: &'static str = "include=";
+    const KEY_EXCLUDE: &'static str = "exclude=";
+    const KEY_EXTENSION: &'static str = "extension=";
+    const KEY_MAX_DEPTH: &'static str = "max_depth=";
+    const FLAG_FOLLOW_SYMLINKS: &'static str = "follow_symlinks";
+
+    /// Creates a filter for the specified folder, listing all files
+    #[must_use]
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            ..Self::default()
+        }
+    }
+    /// Parses command-line style arguments
+    ///
+    /// Recognized options are `include=GLOB`, `exclude=GLOB`, `extension=EXTENSION`,
+    /// `max_depth=N` and `follow_symlinks` (options may repeat). All other arguments are joined by
+    /// spaces to form the folder path.
+    ///
+    /// # Errors
+    /// Returns an error if the `max_depth` value is not a positive number
+    pub fn from_args<T: AsRef<str>>(args: &[T]) -> Result<Self, ArgError> {
+        let mut filter = Self::default();
+        let mut path_parts = vec![];
+        for arg in args.iter().map(AsRef::as_ref) {
+            if let Some(pattern) = arg.strip_prefix(Self::KEY_INCLUDE) {
+                filter.include.push(pattern.to_owned());
+            } else if let Some(pattern) = arg.strip_prefix(Self::KEY_EXCLUDE) {
+                filter.exclude.push(pattern.to_owned());
+            } else if let Some(extension) = arg.strip_prefix(Self::KEY_EXTENSION) {
+                filter.extensions.push(extension.to_owned());
+            } else if let Some(depth) = arg.strip_prefix(Self::KEY_MAX_DEPTH) {
+                let depth = depth.parse().map_err(|_| ArgError {
+                    key: Self::KEY_MAX_DEPTH,
+                    value: depth.to_owned(),
+                })?;
+                filter.max_depth = Some(depth);
+            } else if arg == Self::FLAG_FOLLOW_SYMLINKS {
+                filter.follow_symlinks = true;
+            } else {
+                path_parts.push(arg);
+            }
+        }
+        filter.path = path_parts.join(" ");
+        Ok(filter)
+    }
+}
+impl crate::persistence::OptionStructSerializeDeserialize for FolderFilter {}
+
+/// Error parsing [`FolderFilter`] arguments
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArgError {
+    key: &'static str,
+    value: String,
+}
+impl std::fmt::Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { key, value } = self;
+        write!(f, "invalid folder filter argument {key}{value:?}")
+    }
+}
+impl std::error::Error for ArgError {}
+
+/// [`FolderFilter`] with parsed glob patterns
+pub(super) struct Matcher {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    extensions: Vec<String>,
+}
+impl Matcher {
+    const OPTIONS: MatchOptions = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    pub fn new(filter: &FolderFilter) -> Result<Self, glob::PatternError> {
+        let parse_all = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| Pattern::new(pattern))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let extensions = filter
+            .extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.').to_owned())
+            .collect();
+        Ok(Self {
+            include: parse_all(&filter.include)?,
+            exclude: parse_all(&filter.exclude)?,
+            extensions,
+        })
+    }
+    /// Returns `true` if the folder (relative to the listed folder) should be walked
+    pub fn is_folder_allowed(&self, relative: &Path) -> bool {
+        !Self::any_matches(&self.exclude, relative)
+    }
+    /// Returns `true` if the file (relative to the listed folder) should be listed
+    pub fn is_file_allowed(&self, relative: &Path) -> bool {
+        let is_extension_allowed = self.extensions.is_empty()
+            || relative.extension().is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|allowed| extension.eq_ignore_ascii_case(allowed))
+            });
+        let is_included = self.include.is_empty() || Self::any_matches(&self.include, relative);
+        is_extension_allowed && is_included && !Self::any_matches(&self.exclude, relative)
+    }
+    fn any_matches(patterns: &[Pattern], relative: &Path) -> bool {
+        patterns.iter().any(|pattern| {
+            let is_path_pattern = pattern.as_str().contains('/');
+            if is_path_pattern {
+                pattern.matches_path_with(relative, Self::OPTIONS)
+            } else {
+                relative
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| pattern.matches_with(name, Self::OPTIONS))
+            }
+        })
+    }
+}
+
+/// Serializes a present optional value as the value alone (absent values are skipped)
+mod some_value {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[allow(clippy::ref_option)] // reference type required by serde derive
+    pub fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        T::deserialize(deserializer).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FolderFilter, Matcher};
+    use std::{num::NonZeroUsize, path::Path};
+
+    #[test]
+    fn from_args() {
+        let filter = FolderFilter::from_args(&[
+            "my",
+            "music",
+            "include=*.flac",
+            "extension=mp3",
+            "exclude=Live Recordings",
+            "include=*.ogg",
+            "max_depth=2",
+            "follow_symlinks",
+        ])
+        .expect("valid args");
+        assert_eq!(
+            filter,
+            FolderFilter {
+                path: "my music".to_owned(),
+                include: vec!["*.flac".to_owned(), "*.ogg".to_owned()],
+                exclude: vec!["Live Recordings".to_owned()],
+                extensions: vec!["mp3".to_owned()],
+                max_depth: NonZeroUsize::new(2),
+                follow_symlinks: true,
+            }
+        );
+        assert_eq!(
+            FolderFilter::from_args(&["music"]),
+            Ok(FolderFilter::new("music".to_owned()))
+        );
+    }
+
+    #[test]
+    fn from_args_invalid_depth() {
+        let err = FolderFilter::from_args(&["music", "max_depth=x"]).expect_err("invalid depth");
+        assert_eq!(
+            err.to_string(),
+            r#"invalid folder filter argument max_depth="x""#
+        );
+        // depth 0 would list nothing (not even the folder contents)
+        let err = FolderFilter::from_args(&["music", "max_depth=0"]).expect_err("zero depth");
+        assert_eq!(
+            err.to_string(),
+            r#"invalid folder filter argument max_depth="0""#
+        );
+    }
+
+    #[test]
+    fn matcher() {
+        let filter = FolderFilter {
+            include: vec!["*.flac".to_owned(), "album/*".to_owned()],
+            exclude: vec!["*demo*".to_owned()],
+            extensions: vec![".FLAC".to_owned(), "mp3".to_owned()],
+            ..FolderFilter::default()
+        };
+        let matcher = Matcher::new(&filter).expect("valid patterns");
+        let allowed = |path: &str| matcher.is_file_allowed(Path::new(path));
+
+        assert!(allowed("song.flac"));
+        assert!(allowed("nested/folder/song.flac"));
+        assert!(allowed("album/song.mp3"));
+        // extensions are case-insensitive, patterns are not
+        assert!(allowed("album/SONG.MP3"));
+        assert!(!allowed("SONG.FLAC"));
+        // include path patterns do not cross folders
+        assert!(!allowed("album/disc1/song.mp3"));
+        // extension not allowed
+        assert!(!allowed("album/cover.jpg"));
+        assert!(!allowed("nested/song.mp3"));
+        // excluded
+        assert!(!allowed("song-demo.flac"));
+        assert!(!matcher.is_folder_allowed(Path::new("nested/demos")));
+        assert!(matcher.is_folder_allowed(Path::new("nested/album")));
+    }
+
+    #[test]
+    fn matcher_invalid() {
+        let filter = FolderFilter {
+            exclude: vec!["[".to_owned()],
+            ..FolderFilter::default()
+        };
+        assert!(Matcher::new(&filter).is_err());
+    }
+}
@@ -1,14 +1,20 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! Multiple-select adapter for various [`ItemSource`](`super::ItemSource`)s
-use super::{beet, file};
+use super::{beet, file, playlist};
 
 /// Creates a multiple-select adapter for various heterogeneous [`ItemSource`](`super::ItemSource`)s
 ///
+/// Items from each source are converted [`Into`] the common `Item` type. Use [`QueueItem`] to keep
+/// the hints of [`PlaylistFile`] entries (converting into `String` keeps only the path).
+///
+/// [`PlaylistFile`]: `super::PlaylistFile`
+/// [`QueueItem`]: `super::QueueItem`
+///
 /// # Example
 /// ```
 /// #[macro_use]
 /// use sequencer::source_multi_select;
-/// use sequencer::sources::{Beet, FileLines, FolderListing};
+/// use sequencer::sources::{Beet, FileLines, FolderListing, PlaylistFile};
 ///
 /// source_multi_select! {
 ///     /// Multiple-select for various [`ItemSource`]s
@@ -22,10 +28,12 @@ use super::{beet, file};
 ///         file_lines: FileLines as FileLines where arg type = String,
 ///         /// FolderListing
 ///         folder_listing: FolderListing as FolderListing where arg type = String,
+///         /// PlaylistFile
+///         playlist_file: PlaylistFile as PlaylistFile where arg type = String,
 ///     }
 ///     /// Custom typed arg
 ///     impl ItemSource<Option<TypedArgCustom>> {
-///         type Item = String;
+///         type Item = sequencer::sources::QueueItem;
 ///         /// This is the custom error type
 ///         type Error = ErrorCustom;
 ///     }
@@ -175,10 +183,23 @@ macro_rules! source_multi_select {
                     $args::try_from(args).map_err(|e| e.map_or($error::NoType, $error::TypeMismatch))?;
                 match typed_args {
                     $(
-                        $args::$field_variant(args) => Ok(self.$field.lookup(&args).map_err($error::$field_variant)?),
+                        $args::$field_variant(args) => {
+                            let items = self.$field.lookup(&args).map_err($error::$field_variant)?;
+                            Ok(items.into_iter().map(Into::into).collect())
+                        }
                     )+
                 }
             }
+            fn invalidate(&self, args: &[Option<$arg>]) {
+                // NOTE: mismatched args have no cached lookups
+                if let Ok(typed_args) = $args::try_from(args) {
+                    match typed_args {
+                        $(
+                            $args::$field_variant(args) => self.$field.invalidate(&args),
+                        )+
+                    }
+                }
+            }
         }
     };
 }
@@ -195,10 +216,12 @@ source_multi_select! {
         file_lines: file::Lines as FileLines where arg type = String,
         /// FolderListing
         folder_listing: file::FolderListing as FolderListing where arg type = String,
+        /// PlaylistFile
+        playlist_file: playlist::PlaylistFile as PlaylistFile where arg type = String,
     }
     /// Custom typed arg
     impl ItemSource<Option<TypedArgCustom>> {
-        type Item = String;
+        type Item = super::QueueItem;
         /// This is the custom error type
         type Error = ErrorCustom;
     }
@@ -0,0 +1,279 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use super::{ItemSource, LastModified, PathError, RootFolder};
+
+/// Reads items from the playlist file (M3U/M3U8 or PLS) specified by the filter args
+///
+/// Relative entries are resolved against the folder containing the playlist file.
+/// The format is chosen by the file extension (`.pls` for PLS, otherwise M3U).
+#[derive(Clone)]
+pub struct PlaylistFile {
+    root: RootFolder,
+}
+impl PlaylistFile {
+    /// Attempts to create an instance with the specified root path
+    ///
+    /// # Errors
+    /// Returns an error if the specified root path is not a directory
+    pub fn new(root: PathBuf) -> Result<Self, PathError> {
+        Ok(Self::from(RootFolder::new(root)?))
+    }
+}
+impl From<RootFolder> for PlaylistFile {
+    fn from(root: RootFolder) -> Self {
+        Self { root }
+    }
+}
+impl<T> ItemSource<T> for PlaylistFile
+where
+    T: AsRef<OsStr>,
+{
+    type Item = PlaylistEntry;
+    type Error = PathError;
+
+    fn lookup(&self, args: &[T]) -> Result<Vec<Self::Item>, Self::Error> {
+        let file_path = self.root.clone_to_child_path(args);
+        let contents =
+            std::fs::read(&file_path).map_err(PathError::with_path_fn(file_path.clone()))?;
+        // NOTE: legacy `.m3u` files may not be UTF-8, keep the readable entries
+        let contents = String::from_utf8_lossy(&contents);
+        let contents = contents.trim_start_matches('\u{FEFF}');
+
+        let playlist_dir = file_path.parent().unwrap_or(&file_path);
+        let is_pls = file_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pls"));
+        let entries = if is_pls {
+            parse_pls(contents, playlist_dir)
+        } else {
+            parse_m3u(contents, playlist_dir)
+        };
+        Ok(entries)
+    }
+}
+impl<T> LastModified<T> for PlaylistFile
+where
+    T: AsRef<OsStr>,
+{
+    fn last_modified(&self, args: &[T]) -> Option<SystemTime> {
+        super::modified_time(&self.root.clone_to_child_path(args))
+    }
+}
+
+/// Entry in a playlist file, with the optional hints from the playlist
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlaylistEntry {
+    /// Path (resolved relative to the playlist) or URL of the item
+    pub path: String,
+    /// Title hint
+    pub title: Option<String>,
+    /// Duration hint, in seconds
+    pub duration_secs: Option<u64>,
+}
+impl From<PlaylistEntry> for String {
+    fn from(entry: PlaylistEntry) -> Self {
+        entry.path
+    }
+}
+
+fn parse_m3u(contents: &str, playlist_dir: &Path) -> Vec<PlaylistEntry> {
+    const EXTINF: &str = "#EXTINF:";
+
+    let mut entries = vec![];
+    let mut pending_info = None;
+    for line in contents.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix(EXTINF) {
+            // `#EXTINF:<duration> [attributes],<title>`
+            let (duration_and_attrs, title) = info.split_once(',').unwrap_or((info, ""));
+            let duration = duration_and_attrs.split_whitespace().next().unwrap_or("");
+            pending_info = Some((parse_duration_secs(duration), non_empty(title)));
+        } else if line.is_empty() || line.starts_with('#') {
+            // ignore blank lines, comments and other extended tags
+        } else {
+            let (duration_secs, title) = pending_info.take().unwrap_or_default();
+            entries.push(PlaylistEntry {
+                path: resolve(line, playlist_dir),
+                title,
+                duration_secs,
+            });
+        }
+    }
+    entries
+}
+
+fn parse_pls(contents: &str, playlist_dir: &Path) -> Vec<PlaylistEntry> {
+    #[derive(Default)]
+    struct Fields<'a> {
+        file: Option<&'a str>,
+        title: Option<&'a str>,
+        length: Option<&'a str>,
+    }
+    // ordered by the entry number, not the line order
+    let mut numbered: BTreeMap<u32, Fields<'_>> = BTreeMap::new();
+    for line in contents.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            // ignore section headers and blank lines
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let split_number = |prefix: &str| {
+            let number = key.get(prefix.len()..)?;
+            let is_prefix = key.get(..prefix.len())?.eq_ignore_ascii_case(prefix);
+            is_prefix.then(|| number.parse::<u32>().ok()).flatten()
+        };
+        if let Some(number) = split_number("File") {
+            numbered.entry(number).or_default().file = Some(value);
+        } else if let Some(number) = split_number("Title") {
+            numbered.entry(number).or_default().title = Some(value);
+        } else if let Some(number) = split_number("Length") {
+            numbered.entry(number).or_default().length = Some(value);
+        }
+        // ignore `NumberOfEntries`, `Version` and unknown keys
+    }
+    numbered
+        .into_values()
+        .filter_map(|fields| {
+            let Fields {
+                file,
+                title,
+                length,
+            } = fields;
+            Some(PlaylistEntry {
+                path: resolve(non_empty(file?)?.as_str(), playlist_dir),
+                title: title.and_then(non_empty),
+                duration_secs: length.and_then(parse_duration_secs),
+            })
+        })
+        .collect()
+}
+
+/// Returns the URL unchanged, or the path resolved relative to the playlist folder
+fn resolve(entry: &str, playlist_dir: &Path) -> String {
+    let is_url = entry
+        .split_once("://")
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains(['/', '\\']));
+    if is_url {
+        entry.to_owned()
+    } else {
+        // NOTE: joining an absolute path replaces the base
+        playlist_dir.join(entry).to_string_lossy().into_owned()
+    }
+}
+
+/// Parses seconds (possibly fractional), with negative meaning "unknown"
+fn parse_duration_secs(value: &str) -> Option<u64> {
+    let secs = value.trim().parse::<f64>().ok()?;
+    if secs.is_finite() && secs >= 0.0 {
+        // NOTE: sub-second precision is not needed for a hint
+        #[allow(clippy::cast_possible_truncation)]
+        #[allow(clippy::cast_sign_loss)]
+        Some(secs.round() as u64)
+    } else {
+        None
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_m3u, parse_pls, PlaylistEntry};
+    use std::path::Path;
+
+    fn entry(path: &str, title: Option<&str>, duration_secs: Option<u64>) -> PlaylistEntry {
+        PlaylistEntry {
+            path: path.to_owned(),
+            title: title.map(String::from),
+            duration_secs,
+        }
+    }
+
+    #[test]
+    fn m3u_extended() {
+        let contents = "#EXTM3U
+#EXTINF:123,Artist - Title
+music/first.mp3
+
+#EXTINF:-1 tvg-id=\"x\",Stream
+http://example.com/stream
+# plain comment
+/absolute/third.ogg
+#EXTINF:4.6,
+../up.flac
+";
+        let entries = parse_m3u(contents, Path::new("/root/lists"));
+        assert_eq!(
+            entries,
+            vec![
+                entry(
+                    "/root/lists/music/first.mp3",
+                    Some("Artist - Title"),
+                    Some(123)
+                ),
+                entry("http://example.com/stream", Some("Stream"), None),
+                entry("/absolute/third.ogg", None, None),
+                entry("/root/lists/../up.flac", None, Some(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn m3u_plain() {
+        let entries = parse_m3u("a.mp3\r\nb.mp3\r\n", Path::new("lists"));
+        assert_eq!(
+            entries,
+            vec![
+                entry("lists/a.mp3", None, None),
+                entry("lists/b.mp3", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn pls() {
+        let contents = "[playlist]
+File2=second.mp3
+Title2=Second
+Length2=-1
+File1=file:///music/first.mp3
+Title1=First
+Length1=200
+Title3=Missing file
+NumberOfEntries=3
+Version=2
+";
+        let entries = parse_pls(contents, Path::new("/root/lists"));
+        assert_eq!(
+            entries,
+            vec![
+                entry("file:///music/first.mp3", Some("First"), Some(200)),
+                entry("/root/lists/second.mp3", Some("Second"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn queue_item_keeps_hints() {
+        use crate::sources::QueueItem;
+
+        let entries = parse_m3u("#EXTINF:7,Seven\nseven.mp3\n", Path::new("lists"));
+        // NOTE: `source_multi_select!` converts using `Into`
+        let items: Vec<QueueItem> = entries.into_iter().map(Into::into).collect();
+        let expected = QueueItem {
+            path: "lists/seven.mp3".to_owned(),
+            title: Some("Seven".to_owned()),
+            duration_secs: Some(7),
+        };
+        assert_eq!(items, vec![expected]);
+    }
+}
@@ -1,8 +1,9 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 use std::collections::VecDeque;
 
 use crate::{DebugItemSource, Error, ItemSource, Sequencer};
 use q_filter_tree::{OrderType, SequenceAndItem};
+use shared::IgnoreNever;
 
 mod fail;
 
@@ -173,3 +174,66 @@ fn set_filter_updates_only_subtree() -> Result<(), Error> {
     assert_next(&mut s, &filters2, 3, 1, NodeSeq(4));
     Ok(())
 }
+#[test]
+fn deferred_lookups() -> Result<(), Error> {
+    let mut s = Sequencer::new(UpdateTrackingItemSource(3), String::default());
+    s.set_deferred_lookups(true);
+    s.add_node(".", "parent".to_string())?;
+    s.add_terminal_node(".0", "leaf".to_string())?;
+    s.add_terminal_node(".0", "removed".to_string())?;
+    assert_eq!(s.pop_next(), None);
+
+    let pending = s.take_pending_lookups();
+    let node_ids: Vec<_> = pending.iter().map(|p| p.node_id().to_string()).collect();
+    assert_eq!(node_ids, [".0.0#2", ".0.1#3"]);
+    assert!(s.take_pending_lookups().is_empty());
+
+    let _removed = s.remove_node(".0.1#3")?;
+    for lookup in &pending {
+        let items = s.item_source.lookup(lookup.args()).ignore_never();
+        let result = s.apply_lookup(lookup.id(), items);
+        assert_eq!(result.is_ok(), lookup.node_id().to_string() == ".0.0#2");
+    }
+    let filters = vec!["", "parent", "leaf"];
+    assert_next(&mut s, &filters, 0, 3, NodeSeq(2));
+    assert_next(&mut s, &filters, 1, 3, NodeSeq(2));
+    // already applied
+    let items = s.item_source.lookup(pending[0].args()).ignore_never();
+    assert!(s.apply_lookup(pending[0].id(), items).is_err());
+
+    // updates are deferred
+    s.update_nodes(".")?;
+    let node_ids: Vec<_> = s
+        .take_pending_lookups()
+        .iter()
+        .map(|p| p.node_id().to_string())
+        .collect();
+    assert_eq!(node_ids, [".0.0#2"]);
+    Ok(())
+}
+#[test]
+fn deferred_lookups_latest_only() -> Result<(), Error> {
+    let mut s = Sequencer::new(UpdateTrackingItemSource(2), String::default());
+    s.set_deferred_lookups(true);
+    s.add_terminal_node(".", "leaf".to_string())?;
+    let older = s.take_pending_lookups();
+    s.item_source.set_rev(1);
+    s.update_nodes(".0")?;
+    let newer = s.take_pending_lookups();
+    assert_eq!(older[0].node_id(), newer[0].node_id());
+
+    // newer lookup finishes first
+    let items = s.item_source.lookup(newer[0].args()).ignore_never();
+    s.apply_lookup(newer[0].id(), items)?;
+    s.item_source.set_rev(0);
+    let items = s.item_source.lookup(older[0].args()).ignore_never();
+    let error = s
+        .apply_lookup(older[0].id(), items)
+        .expect_err("older lookup is outdated");
+    assert_eq!(error.to_string(), "lookup 0 for node .0#1 is outdated");
+
+    let filters = vec!["", "leaf"];
+    assert_next(&mut s, &filters, 0, 1, NodeSeq(1));
+    assert_next(&mut s, &filters, 1, 1, NodeSeq(1));
+    Ok(())
+}
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! Parses command-line arguments
 
 use self::args::{RawArgs, RawArgsUnpacked};
@@ -30,6 +30,9 @@ use std::path::PathBuf;
 // - Sequencer:
 //   - BEET_CMD [BEET_CMD]
 //   - ROOT_FOLDER [ROOT_FOLDER]
+//   - CACHE [CACHE=1]
+//   - CACHE_TTL_SECS [CACHE_TTL_SECS]
+//   - CACHE_DIR [CACHE_DIR]
 //
 
 mod args;
@@ -49,6 +52,9 @@ mod env_vars {
     // Sequencer
     pub const BEET_CMD: &str = "BEET_CMD";
     pub const ROOT_FOLDER: &str = "ROOT_FOLDER";
+    pub const CACHE: &str = "CACHE";
+    pub const CACHE_TTL_SECS: &str = "CACHE_TTL_SECS";
+    pub const CACHE_DIR: &str = "CACHE_DIR";
 }
 
 /// Final structured configuration
@@ -83,6 +89,8 @@ pub struct Sequencer {
     pub beet_cmd: sequencer::sources::Beet,
     /// File to load state, then periodically store
     pub state_file: Option<PathBuf>,
+    /// Caching of item source lookups
+    pub cache: sequencer::sources::CacheParams,
 }
 impl Config {
     pub fn is_interactive(&self) -> bool {
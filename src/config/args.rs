@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 use super::{env_vars, Input, SequencerError, UsageError, Value, VlcHttpError, WebError};
 use clap::Parser;
@@ -6,6 +6,7 @@ use serde::Deserialize;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 arg_util::derive_unpack! {
     #[derive(Parser, Deserialize, Default, Debug)]
@@ -71,7 +72,7 @@ arg_util::derive_unpack! {
     }
     #[derive(Parser, Deserialize, Default, Debug)]
     pub(super) struct RawSequencer impl unpacked as RawSequencerUnpacked {
-        /// Root folder for querying `FileLines` or `FolderListing` sources (overrides environment variable)
+        /// Root folder for querying `FileLines`, `FolderListing` or `PlaylistFile` sources (overrides environment variable)
         #[clap(long = env_vars::ROOT_FOLDER)]
         root_folder: Option<String>,
         /// Executable to run for querying `Beet` sources (overrides environment variable)
@@ -80,6 +81,16 @@ arg_util::derive_unpack! {
         /// File to load state, then periodically store
         #[clap(long = env_vars::STATE_FILE)]
         state_file: Option<PathBuf>,
+        /// Reuse item source lookups until the files change (overrides environment variable)
+        #[clap(long = env_vars::CACHE)]
+        #[serde(default)]
+        cache: bool,
+        /// Maximum age of cached lookups, in seconds (implies `CACHE`, overrides environment variable)
+        #[clap(long = env_vars::CACHE_TTL_SECS)]
+        cache_ttl_secs: Option<u64>,
+        /// Folder to store cached lookups across restarts (implies `CACHE`, overrides environment variable)
+        #[clap(long = env_vars::CACHE_DIR)]
+        cache_dir: Option<String>,
     }
 }
 
@@ -227,7 +238,7 @@ impl TryFrom<Input<RawCli>> for super::Cli {
 }
 
 impl TryFrom<Input<RawSequencer>> for super::Sequencer {
-    type Error = SequencerError;
+    type Error = super::Error;
 
     fn try_from(raw: Input<RawSequencer>) -> Result<Self, Self::Error> {
         const DEFAULT_ROOT_FOLDER: Value<&str> = Value::define_default(".");
@@ -236,6 +247,9 @@ impl TryFrom<Input<RawSequencer>> for super::Sequencer {
             root_folder,
             beet_cmd,
             state_file,
+            cache,
+            cache_ttl_secs,
+            cache_dir,
         } = raw.into();
         let root_folder = {
             let folder = root_folder
@@ -261,10 +275,36 @@ impl TryFrom<Input<RawSequencer>> for super::Sequencer {
                 .map_err(|error| SequencerError::BeetCommand { source, error })?
         };
         let state_file = state_file.get_first().map(Value::into_inner);
+        let cache = {
+            let parse_secs = |key, value: String| {
+                value.parse().map_err(|err| UsageError::Env {
+                    key,
+                    message: format!("invalid number {value}: {err}"),
+                })
+            };
+            let enabled = cache.env(env_vars::CACHE).or_parse_bool().into_inner();
+            let ttl_secs = cache_ttl_secs
+                .env(env_vars::CACHE_TTL_SECS)
+                .try_get_first(parse_secs)?
+                .map(Value::into_inner);
+            let folder = cache_dir
+                .env(env_vars::CACHE_DIR)
+                .get_first_str()
+                .map(|folder| PathBuf::from(folder.into_inner()));
+            if enabled || ttl_secs.is_some() || folder.is_some() {
+                sequencer::sources::CacheParams {
+                    ttl: ttl_secs.map(Duration::from_secs),
+                    folder,
+                }
+            } else {
+                sequencer::sources::CacheParams::disabled()
+            }
+        };
         Ok(Self {
             root_folder,
             beet_cmd,
             state_file,
+            cache,
         })
     }
 }
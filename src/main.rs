@@ -98,6 +98,7 @@ fn print_startup_info(config: &config::Config) {
                 root_folder,
                 beet_cmd,
                 state_file,
+                cache,
             },
         cli_config: config::Cli { run_script, .. },
     } = config;
@@ -110,6 +111,15 @@ fn print_startup_info(config: &config::Config) {
         root_folder.as_ref().display()
     );
     println!("{ITEM}Sequencer beet command: {}", beet_cmd.display());
+    let sequencer::sources::CacheParams { ttl, folder } = cache;
+    match ttl {
+        Some(ttl) if ttl.is_zero() => {}
+        Some(ttl) => println!("{ITEM}Cache lookups for {} seconds", ttl.as_secs()),
+        None => println!("{ITEM}Cache lookups until the files change"),
+    }
+    if let Some(folder) = folder {
+        println!("{ITEM}Store cached lookups in folder: {}", folder.display());
+    }
     if let Some(run_script) = run_script {
         println!("{ITEM}Startup by running script: {}", run_script.display());
     }
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2023  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! Handles [`sequencer`]-related items, named `seq` to avoid namespace ambiguity
 // e.g. (create::sequencer vs ::sequencer)
 
@@ -6,7 +6,9 @@ use crate::{
     cli::{self, SequencerState},
     config,
 };
+use sequencer::lookup::{LookupEvent, LookupWorker, LookupWorkerParams};
 use shared::Shutdown;
+use std::time::Duration;
 use tokio::sync::{mpsc, oneshot, watch};
 
 // TODO remove unused
@@ -47,6 +49,18 @@ pub(crate) struct Task {
     cli: SequencerCli,
     channels: Channels,
     config_file: Option<SequencerConfigFile>,
+    lookups: Lookups,
+}
+/// Background lookups for the terminal nodes, to avoid blocking on slow item sources
+struct Lookups {
+    worker: LookupWorker<SequencerFilter>,
+    event_rx: mpsc::UnboundedReceiver<LookupEvent<source::QueueItem>>,
+    /// Shares the lookup cache with the worker, for storing the cache
+    source: source::Source,
+}
+impl Lookups {
+    /// Delay from the first lookup event to storing the cache, to batch several lookups
+    const CACHE_FLUSH_DELAY: Duration = Duration::from_secs(10);
 }
 impl Task {
     pub(crate) fn new(config: config::Sequencer, channels: Channels) -> Result<Self, ()> {
@@ -55,6 +69,7 @@ impl Task {
             root_folder,
             beet_cmd,
             state_file, // TODO play in sequencer::main first, then instantiate SequencerConfigFile (need KDL traits to line up)
+            cache,
         } = config;
         let (config_file, existing_tree) = None.transpose()?.unzip();
         // TODO let (config_file, existing_tree) = state_file
@@ -65,18 +80,39 @@ impl Task {
         //     })
         //     .transpose()?
         //     .unzip();
+        let item_source = source::Source::new(root_folder, beet_cmd, &cache)
+            .map_err(|err| eprintln!("failed to load sequencer cache: {err}"))?;
+        let lookups = {
+            let (event_tx, event_rx) = mpsc::unbounded_channel();
+            let on_event = move |event| {
+                // NOTE: receiver is only dropped on shutdown
+                let _ = event_tx.send(event);
+            };
+            // TODO configurable? or no?
+            let params = LookupWorkerParams::default();
+            let worker = LookupWorker::new(item_source.clone(), params, on_event)
+                .map_err(|err| eprintln!("failed to start sequencer lookup worker: {err}"))?;
+            Lookups {
+                worker,
+                event_rx,
+                source: item_source.clone(),
+            }
+        };
         let cli = {
-            let item_source = source::Source::new(root_folder, beet_cmd);
             let filter_arg_parser = source::FilterArgParser {
                 default_ty: source::Type::Beet, // TODO configurable? or no?
             };
             let params = sequencer::cli::OutputParams { quiet: false };
-            sequencer::cli::Cli::new(item_source, filter_arg_parser, params, existing_tree)
+            let mut cli =
+                sequencer::cli::Cli::new(item_source, filter_arg_parser, params, existing_tree);
+            cli.set_deferred_lookups(true);
+            cli
         };
         Ok(Self {
             cli,
             channels,
             config_file,
+            lookups,
         })
     }
     pub(crate) async fn run(self) -> Result<shared::Never, Shutdown> {
@@ -84,7 +120,14 @@ impl Task {
             mut cli,
             channels,
             mut config_file,
+            lookups,
         } = self;
+        let Lookups {
+            worker: lookup_worker,
+            event_rx: mut lookup_event_rx,
+            source: lookup_source,
+        } = lookups;
+        let mut cache_flush_deadline = None;
         let Channels {
             mut sequencer_rx,
             mut sequencer_cli_rx,
@@ -133,10 +176,28 @@ impl Task {
                         false
                     }
                 }
+                Some(event) = lookup_event_rx.recv() => {
+                    // items lookup progress
+                    cache_flush_deadline.get_or_insert_with(|| {
+                        tokio::time::Instant::now() + Lookups::CACHE_FLUSH_DELAY
+                    });
+                    Self::exec_lookup_event(event, &mut cli)
+                }
+                () = tokio::time::sleep_until(
+                    cache_flush_deadline.unwrap_or_else(tokio::time::Instant::now)
+                ), if cache_flush_deadline.is_some() => {
+                    cache_flush_deadline = None;
+                    Self::flush_cache(&lookup_source);
+                    false
+                }
                 else => {
                     break;
                 }
             };
+            // start lookups for the terminal nodes modified by the command
+            for lookup in cli.take_pending_lookups() {
+                lookup_worker.submit(lookup);
+            }
             // TODO should "new_urls publish" go at the beginning of the loop?  (when SequencerTree can be pre-loaded?)
             // update cmd_playlist items
             let new_urls = cli
@@ -164,8 +225,32 @@ impl Task {
                 }
             }
         }
+        Self::flush_cache(&lookup_source);
         Err(Shutdown)
     }
+    fn flush_cache(source: &source::Source) {
+        if let Err(err) = source.flush_cache() {
+            eprintln!("failed to store sequencer cache: {err}");
+        }
+    }
+    fn exec_lookup_event(
+        event: LookupEvent<source::QueueItem>,
+        sequencer_cli: &mut SequencerCli,
+    ) -> bool {
+        println!("{event}");
+        if let LookupEvent::Finished(id, items) = event {
+            match sequencer_cli.apply_lookup(&id, items) {
+                Ok(()) => true,
+                Err(err) => {
+                    // e.g. node removed, or a newer lookup queued, while the lookup was running
+                    eprintln!("Error: {err}");
+                    false
+                }
+            }
+        } else {
+            false
+        }
+    }
     fn exec_remove(removed: &url::Url, sequencer_cli: &mut SequencerCli) {
         println!("remove_rx changed! removed {removed}");
         let popped = sequencer_cli.pop_next();
@@ -180,7 +265,11 @@ impl Task {
 
 pub mod source {
     use clap::ValueEnum;
-    use sequencer::sources::{Beet, FileLines, FolderListing, RootFolder};
+    pub use sequencer::sources::QueueItem;
+    use sequencer::sources::{
+        Beet, CacheParams, Cached, FileLines, FolderFilter, FolderListing, PathError,
+        PlaylistEntry, PlaylistFile, RootFolder,
+    };
     use serde::Serialize;
 
     sequencer::source_multi_select! {
@@ -190,16 +279,19 @@ pub mod source {
             #[derive(Copy, Clone, ValueEnum)]
             type Type = Type;
             /// Beet
-            beet: Beet as Beet where arg type = Vec<String>,
+            beet: Cached<Beet> as Beet where arg type = Vec<String>,
             /// File lines
-            file_lines: FileLines as FileLines where arg type = String,
+            file_lines: Cached<FileLines> as FileLines where arg type = String,
             /// Folder listing
-            folder_listing: FolderListing as FolderListing where arg type = String,
+            folder_listing: Cached<FolderListing> as FolderListing where arg type = FolderFilter,
+            /// Playlist file (M3U/M3U8 or PLS)
+            playlist_file: Cached<PlaylistFile, PlaylistEntry>
+                as PlaylistFile where arg type = String,
         }
         #[derive(Clone, Debug, Serialize)]
         /// Typed argument
         impl ItemSource<Option<TypedArg>> {
-            type Item = String;
+            type Item = QueueItem;
             /// Typed Error
             type Error = TypedLookupError;
         }
@@ -207,14 +299,27 @@ pub mod source {
     // TODO does `TypedArg` (e.g. source_multi_select! macro) need refactoring to fit the KDL auto-serde trait?
     // impl sequencer::persistence::StructSerializeDeserialize for TypedArg {}
     impl Source {
-        pub(crate) fn new(root_folder: RootFolder, beet: Beet) -> Self {
+        pub(crate) fn new(
+            root_folder: RootFolder,
+            beet: Beet,
+            cache: &CacheParams,
+        ) -> Result<Self, PathError> {
             let file_lines = FileLines::from(root_folder.clone());
-            let folder_listing = FolderListing::from(root_folder);
-            Self {
-                beet,
-                file_lines,
-                folder_listing,
-            }
+            let folder_listing = FolderListing::from(root_folder.clone());
+            let playlist_file = PlaylistFile::from(root_folder);
+            Ok(Self {
+                beet: cache.wrap(beet, "beet")?,
+                file_lines: cache.wrap(file_lines, "file_lines")?,
+                folder_listing: cache.wrap(folder_listing, "folder_listing")?,
+                playlist_file: cache.wrap(playlist_file, "playlist_file")?,
+            })
+        }
+        /// Stores the cached lookups (if changed), see [`Cached::flush`]
+        pub(crate) fn flush_cache(&self) -> Result<(), PathError> {
+            self.beet.flush()?;
+            self.file_lines.flush()?;
+            self.folder_listing.flush()?;
+            self.playlist_file.flush()
         }
     }
 
@@ -229,18 +334,21 @@ pub mod source {
             &self,
             items_filter: Vec<String>,
             source_type: Option<Type>,
-        ) -> Option<TypedArg> {
+        ) -> Result<Option<TypedArg>, String> {
             if items_filter.is_empty() {
-                None
+                Ok(None)
             } else {
                 let joined = items_filter.join(" ");
                 let filter = match source_type.unwrap_or(self.default_ty) {
                     // Type::Debug => source::TypedArg::Debug(joined),
                     Type::FileLines => TypedArg::FileLines(joined),
-                    Type::FolderListing => TypedArg::FolderListing(joined),
+                    Type::FolderListing => TypedArg::FolderListing(
+                        FolderFilter::from_args(&items_filter).map_err(|err| err.to_string())?,
+                    ),
+                    Type::PlaylistFile => TypedArg::PlaylistFile(joined),
                     Type::Beet => TypedArg::Beet(items_filter),
                 };
-                Some(filter)
+                Ok(Some(filter))
             }
         }
     }
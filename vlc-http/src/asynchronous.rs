@@ -0,0 +1,84 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Convenience functions for [`Plan`]s in an `async` context
+//!
+//! Mirrors [`sync`](`crate::sync`), for use without blocking the executor.
+
+use crate::{
+    goal::Step,
+    sync::{Error, ErrorKind},
+    ClientState, Endpoint, Plan, Response,
+};
+use std::{future::Future, time::Instant};
+
+/// IO portion that resolves [`Endpoint`]s into the [`Response`], without blocking
+pub trait EndpointRequestor {
+    /// Error for sending the request and parsing the response
+    type Error;
+    /// Request the specified [`Endpoint`] and return the parsed [`Response`]
+    ///
+    /// # Errors
+    /// Returns an error when requesting the [`Endpoint`] or parsing the [`Response`] fails
+    fn request(
+        &mut self,
+        endpoint: Endpoint,
+    ) -> impl Future<Output = Result<Response, Self::Error>> + Send;
+}
+
+impl<F, Fut, E> EndpointRequestor for F
+where
+    F: FnMut(Endpoint) -> Fut,
+    Fut: Future<Output = Result<Response, E>> + Send,
+{
+    type Error = E;
+    fn request(
+        &mut self,
+        endpoint: Endpoint,
+    ) -> impl Future<Output = Result<Response, Self::Error>> + Send {
+        (self)(endpoint)
+    }
+}
+
+/// Convenience function for running a [`Plan`] to completion, awaiting until the output is
+/// obtained or an error occurs.
+///
+/// NOTE: The returned future may be dropped at any `.await` point (e.g. when selecting between
+/// other competing futures), leaving the [`ClientState`] updated with all completed responses.
+///
+/// # Errors
+/// Returns an error if the endpoint generation fails, `endpoint_caller` fails, or the
+/// `max_iter_count` is exceeded.
+pub async fn complete_plan<'a, T, E, F>(
+    mut source: T,
+    client_state: &'a mut ClientState,
+    endpoint_caller: &mut F,
+    max_iter_count: usize,
+) -> Result<T::Output<'a>, Error<T, E>>
+where
+    T: Plan,
+    F: EndpointRequestor<Error = E>,
+    Error<T, E>: std::error::Error,
+{
+    for _ in 0..max_iter_count {
+        let endpoint = match source.next(client_state) {
+            Ok(Step::Need(endpoint)) => endpoint,
+            Ok(Step::Done(_)) => break, // final output borrow occurs below
+            Err(error) => return Err(Error::new(source, ErrorKind::Poll(error))),
+        };
+        client_state.notify_endpoint(&endpoint, Instant::now());
+        let response = match endpoint_caller.request(endpoint).await {
+            Ok(response) => response,
+            Err(error) => return Err(Error::new(source, ErrorKind::EndpointFn(error))),
+        };
+
+        client_state.update(response);
+    }
+    let kind = match source.next(client_state) {
+        Ok(Step::Done(output)) => return Ok(output),
+        Ok(Step::Need(next_endpoint)) => ErrorKind::IterationCountExceeded {
+            max_iter_count,
+            next_endpoint,
+        },
+        Err(error) => ErrorKind::Poll(error),
+    };
+    Err(Error::new(source, kind))
+}
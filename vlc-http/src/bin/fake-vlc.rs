@@ -0,0 +1,43 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Fake VLC HTTP server, for end-to-end tests without a real `cvlc`
+
+use clap::Parser as _;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use vlc_http::fake::{Model, Server};
+
+/// Serves a simulated VLC playlist and playback status over HTTP
+#[derive(clap::Parser)]
+#[clap(version)]
+struct Args {
+    /// Address to listen on
+    #[clap(long, env = "VLC_HOST", default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    host: IpAddr,
+    /// Port to listen on
+    #[clap(long, env = "VLC_PORT", default_value_t = 8080)]
+    port: u16,
+    /// Password required for requests (blank username)
+    #[clap(long, env = "VLC_PASSWORD")]
+    password: String,
+    /// Initial playlist items (URLs)
+    items: Vec<String>,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let Args {
+        host,
+        port,
+        password,
+        items,
+    } = Args::parse();
+
+    let mut model = Model::default();
+    model.initialize_items(items);
+
+    let listener = std::net::TcpListener::bind(SocketAddr::new(host, port))?;
+    println!("fake VLC listening on {}", listener.local_addr()?);
+
+    Server::new(model, password).serve(listener).await?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Line completion from the clap command tree
+
+use vlc_http::clap::clap_crate as clap;
+
+/// Completes subcommand names, long flags and possible values for the REPL line
+pub struct Helper {
+    command: clap::Command,
+}
+impl Helper {
+    pub fn new(command: clap::Command) -> Self {
+        Self { command }
+    }
+}
+
+impl rustyline::completion::Completer for Helper {
+    type Candidate = String;
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_line(&self.command, &line[..pos]))
+    }
+}
+impl rustyline::hint::Hinter for Helper {
+    type Hint = String;
+}
+impl rustyline::highlight::Highlighter for Helper {}
+impl rustyline::validate::Validator for Helper {}
+impl rustyline::Helper for Helper {}
+
+/// Returns the start of the word being completed, and the candidates for that word
+pub fn complete_line(root: &clap::Command, line: &str) -> (usize, Vec<String>) {
+    let prefix = line.rsplit(char::is_whitespace).next().unwrap_or_default();
+    let prefix_start = line.len() - prefix.len();
+    let completed = &line[..prefix_start];
+
+    let mut command = root;
+    for word in completed.split_whitespace() {
+        if word.starts_with('-') {
+            continue;
+        }
+        if let Some(subcommand) = command.find_subcommand(word) {
+            command = subcommand;
+        }
+    }
+
+    let candidates: Vec<String> = if prefix.starts_with('-') {
+        command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set())
+            .filter_map(clap::Arg::get_long)
+            .map(|long| format!("--{long}"))
+            .chain(["--help".to_owned()])
+            .filter(|candidate| candidate.starts_with(prefix))
+            .collect()
+    } else {
+        let subcommands = command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+            .map(|subcommand| subcommand.get_name().to_owned());
+        let values = command
+            .get_positionals()
+            .flat_map(clap::Arg::get_possible_values)
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_owned());
+        subcommands
+            .chain(values)
+            .filter(|candidate| candidate.starts_with(prefix))
+            .collect()
+    };
+    (prefix_start, candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory as _;
+
+    fn complete(line: &str) -> (usize, Vec<String>) {
+        complete_line(&crate::ReplLine::command(), line)
+    }
+
+    #[test]
+    fn top_level() {
+        let (start, candidates) = complete("");
+        assert_eq!(start, 0);
+        assert!(candidates.contains(&"command".to_owned()), "{candidates:?}");
+        assert!(candidates.contains(&"watch".to_owned()), "{candidates:?}");
+
+        assert_eq!(
+            complete("qu"),
+            (0, vec!["query".to_owned(), "quit".to_owned()])
+        );
+    }
+
+    #[test]
+    fn nested_subcommand() {
+        let (start, candidates) = complete("command seek-");
+        assert_eq!(start, "command ".len());
+        assert_eq!(
+            candidates,
+            ["seek-next", "seek-previous", "seek-to", "seek-relative"]
+        );
+    }
+
+    #[test]
+    fn possible_values() {
+        let (_, candidates) = complete("action playback-mode repeat-a");
+        assert_eq!(candidates, ["repeat-all"]);
+    }
+
+    #[test]
+    fn long_flags() {
+        let (start, candidates) = complete("watch --c");
+        assert_eq!(start, "watch ".len());
+        assert_eq!(candidates, ["--count"]);
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Interactive shell for the VLC HTTP interface
+//!
+//! Each line is parsed as a [`ReplAction`], with tab-completion from the clap command tree.
+//! Use `--json` to print query results as JSON (one value per line) for piping into other tools.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use vlc_http::{
+    clap::clap_crate::{self as clap, CommandFactory as _, Parser as _},
+    http_runner::ureq::HttpRunner,
+    sync::EndpointRequestor as _,
+    ClientState,
+};
+
+mod complete;
+mod output;
+
+/// Maximum nesting of `script` lines within scripts
+const MAX_SCRIPT_DEPTH: usize = 8;
+const MAX_ITER_COUNT: usize = 100;
+/// Longest sleep in `watch` between checks for Ctrl-C
+const WATCH_INTERRUPT_POLL: Duration = Duration::from_millis(100);
+
+#[derive(clap::Parser, Debug)]
+#[clap(version)]
+struct Args {
+    #[clap(flatten)]
+    auth: vlc_http::clap::AuthInput,
+    /// Print query results as JSON (one value per line)
+    #[clap(long)]
+    json: bool,
+    /// Run a single action, then exit (instead of the interactive prompt)
+    #[clap(subcommand)]
+    oneshot_action: Option<ReplAction>,
+}
+
+#[derive(clap::Parser, Debug)]
+#[clap(no_binary_name = true, name = "")]
+struct ReplLine {
+    #[command(subcommand)]
+    action: ReplAction,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ReplAction {
+    /// Send a single command
+    Command {
+        #[command(subcommand)]
+        command: vlc_http::clap::Command,
+    },
+    /// Query the current state
+    Query {
+        #[command(subcommand)]
+        query: Query,
+    },
+    /// Apply a high-level change (multiple commands, depending on the current state)
+    Action {
+        #[command(subcommand)]
+        action: vlc_http::clap::Change,
+    },
+    /// Stream the playback status (stop with Ctrl-C)
+    Watch {
+        /// Delay between status requests, in milliseconds
+        #[clap(long, default_value_t = 1000)]
+        interval_ms: u64,
+        /// Number of statuses to print (unlimited if not specified)
+        #[clap(long)]
+        count: Option<u64>,
+    },
+    /// Run the lines in the file (blank lines and `#` comments are ignored)
+    Script { path: PathBuf },
+    /// Exit the prompt
+    #[clap(alias = "exit", alias = "q")]
+    Quit,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Query {
+    /// Playlist items (current item marked with `>`)
+    Playlist,
+    /// Playback status
+    Playback,
+    /// Set the playlist, and print the matched items after the current item
+    PlaylistSet(vlc_http::clap::PlaylistSetQueryMatched),
+    /// Number of commands for `action playlist-set` to reach the target (without changes)
+    PlaylistSetCost(vlc_http::clap::PlaylistSetQueryMatched),
+}
+
+struct Shutdown;
+
+/// Ctrl-C handling: stops a running `watch`, otherwise exits the process
+#[derive(Clone, Default)]
+struct Interrupt {
+    watching: Arc<AtomicBool>,
+    interrupted: Arc<AtomicBool>,
+}
+impl Interrupt {
+    fn install() -> eyre::Result<Self> {
+        let this = Self::default();
+        let handler = this.clone();
+        ctrlc::set_handler(move || {
+            if handler.watching.load(Ordering::SeqCst) {
+                handler.interrupted.store(true, Ordering::SeqCst);
+            } else {
+                // default SIGINT behavior (the prompt handles Ctrl-C while reading a line)
+                std::process::exit(130);
+            }
+        })?;
+        Ok(this)
+    }
+    /// Marks the start of a `watch`, clearing any previous interrupt
+    fn start_watch(&self) {
+        self.interrupted.store(false, Ordering::SeqCst);
+        self.watching.store(true, Ordering::SeqCst);
+    }
+    fn stop_watch(&self) {
+        self.watching.store(false, Ordering::SeqCst);
+    }
+    fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+}
+
+fn main() -> eyre::Result<()> {
+    let Args {
+        auth,
+        json,
+        oneshot_action,
+    } = Args::parse();
+
+    let auth = vlc_http::Auth::new(auth.into())?;
+    let mut client = Client {
+        runner: HttpRunner::new(auth),
+        state: ClientState::new(),
+        interrupt: Interrupt::install()?,
+        format: if json {
+            output::Format::Json
+        } else {
+            output::Format::Text
+        },
+    };
+
+    if let Some(action) = oneshot_action {
+        client.run_action(action, 0)?;
+        return Ok(());
+    }
+
+    let config = rustyline::Config::builder().auto_add_history(true).build();
+    let mut editor = rustyline::Editor::with_config(config)?;
+    editor.set_helper(Some(complete::Helper::new(ReplLine::command())));
+    loop {
+        let line = match editor.readline("vlc> ") {
+            Ok(line) => line,
+            Err(rustyline::error::ReadlineError::Interrupted) => continue,
+            Err(rustyline::error::ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        match client.run_line(&line, 0) {
+            Ok(Some(Shutdown)) => break,
+            Ok(None) => {}
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+    Ok(())
+}
+
+struct Client {
+    runner: HttpRunner,
+    state: ClientState,
+    interrupt: Interrupt,
+    format: output::Format,
+}
+impl Client {
+    fn run_line(&mut self, line: &str, depth: usize) -> eyre::Result<Option<Shutdown>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        // NOTE: simplistic whitespace splitting should suffice,
+        // as any path/string arguments should be in URL form (percent-encoded)
+        let ReplLine { action } = ReplLine::try_parse_from(line.split_whitespace())?;
+        self.run_action(action, depth)
+    }
+    fn run_action(&mut self, action: ReplAction, depth: usize) -> eyre::Result<Option<Shutdown>> {
+        match action {
+            ReplAction::Command { command } => {
+                let command = vlc_http::Command::try_from(command)?;
+                self.state.notify_command(&command, Instant::now());
+                let response = self.runner.request(command.into_endpoint())?;
+                self.state.update(response);
+            }
+            ReplAction::Query { query } => self.run_query(query)?,
+            ReplAction::Action { action } => {
+                let plan = self
+                    .state
+                    .build_plan()
+                    .apply(vlc_http::Change::from(action));
+                self.complete_plan(plan)?;
+            }
+            ReplAction::Watch { interval_ms, count } => self.watch(interval_ms, count)?,
+            ReplAction::Script { path } => return self.run_script(&path, depth),
+            ReplAction::Quit => return Ok(Some(Shutdown)),
+        }
+        Ok(None)
+    }
+    fn run_query(&mut self, query: Query) -> eyre::Result<()> {
+        let format = self.format;
+        match query {
+            Query::Playlist => {
+                let status = self.complete_plan(self.state.build_plan().query_playback())?;
+                let current_item_id = status
+                    .information
+                    .as_ref()
+                    .and_then(|info| info.playlist_item_id);
+                let items = self.complete_plan(self.state.build_plan().query_playlist())?;
+                format.print_items(items, current_item_id)
+            }
+            Query::Playback => {
+                let status = self.complete_plan(self.state.build_plan().query_playback())?;
+                format.print_status(status)
+            }
+            Query::PlaylistSet(target) => {
+                let plan = self
+                    .state
+                    .build_plan()
+                    .set_playlist_and_query_matched(target.into());
+                let items = self.complete_plan(plan)?;
+                format.print_items(items, None)
+            }
+            Query::PlaylistSetCost(target) => {
+                let plan = self
+                    .state
+                    .build_plan()
+                    .query_playlist_set_cost(target.into());
+                let cost = self.complete_plan(plan)?;
+                format.print_cost(cost)
+            }
+        }
+    }
+    fn watch(&mut self, interval_ms: u64, count: Option<u64>) -> eyre::Result<()> {
+        self.interrupt.start_watch();
+        let result = self.watch_until_interrupted(interval_ms, count);
+        self.interrupt.stop_watch();
+        result
+    }
+    fn watch_until_interrupted(
+        &mut self,
+        interval_ms: u64,
+        count: Option<u64>,
+    ) -> eyre::Result<()> {
+        let format = self.format;
+        let interval = Duration::from_millis(interval_ms);
+        let mut printed = 0;
+        while count.is_none_or(|count| printed < count) {
+            if printed > 0 {
+                let wake = Instant::now() + interval;
+                while !self.interrupt.is_interrupted() {
+                    let remaining = wake.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+                    std::thread::sleep(remaining.min(WATCH_INTERRUPT_POLL));
+                }
+            }
+            if self.interrupt.is_interrupted() {
+                break;
+            }
+            let status = self.complete_plan(self.state.build_plan().query_playback())?;
+            format.print_status(status)?;
+            printed += 1;
+        }
+        Ok(())
+    }
+    fn run_script(&mut self, path: &PathBuf, depth: usize) -> eyre::Result<Option<Shutdown>> {
+        if depth >= MAX_SCRIPT_DEPTH {
+            eyre::bail!(
+                "script nesting exceeds {MAX_SCRIPT_DEPTH} levels at {}",
+                path.display()
+            );
+        }
+        let contents = std::fs::read_to_string(path)?;
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            match self.run_line(line, depth + 1) {
+                Ok(Some(Shutdown)) => return Ok(Some(Shutdown)),
+                Ok(None) => {}
+                Err(err) => {
+                    return Err(err.wrap_err(format!("{}:{line_number}", path.display())));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn complete_plan<T>(&mut self, plan: T) -> eyre::Result<T::Output<'_>>
+    where
+        T: vlc_http::Plan,
+        eyre::Report: From<vlc_http::sync::Error<T, vlc_http::http_runner::ureq::Error>>,
+    {
+        let output =
+            vlc_http::sync::complete_plan(plan, &mut self.state, &mut self.runner, MAX_ITER_COUNT)?;
+        Ok(output)
+    }
+}
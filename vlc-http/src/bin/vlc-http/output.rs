@@ -0,0 +1,133 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Human-readable and JSON output
+
+use vlc_http::{
+    goal::PlaylistSetCost,
+    response::{playback::Mode, playlist::Item, PlaybackStatus},
+};
+
+/// Format for printing query results
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    /// Tables and summary lines
+    Text,
+    /// One JSON value per line
+    Json,
+}
+impl Format {
+    pub fn print_status(self, status: &PlaybackStatus) -> eyre::Result<()> {
+        match self {
+            Self::Text => println!("{}", status_line(status)),
+            Self::Json => println!("{}", serde_json::to_string(status)?),
+        }
+        Ok(())
+    }
+    pub fn print_items(self, items: &[Item], current_item_id: Option<u64>) -> eyre::Result<()> {
+        match self {
+            Self::Text => print!("{}", items_table(items, current_item_id)),
+            Self::Json => println!("{}", serde_json::to_string(items)?),
+        }
+        Ok(())
+    }
+    pub fn print_cost(self, cost: PlaylistSetCost) -> eyre::Result<()> {
+        match self {
+            Self::Text => {
+                let PlaylistSetCost {
+                    keep, delete, add, ..
+                } = cost;
+                let total = cost.total();
+                println!("keep {keep}, delete {delete}, add {add} ({total} commands)");
+            }
+            Self::Json => println!("{}", serde_json::to_string(&cost)?),
+        }
+        Ok(())
+    }
+}
+
+fn status_line(status: &PlaybackStatus) -> String {
+    let mode = match status.mode {
+        Mode::Playing => "playing",
+        Mode::Paused => "paused",
+        Mode::Stopped => "stopped",
+    };
+    let position = format_secs(status.position_secs);
+    let duration = format_secs(status.duration_secs);
+    let volume = status.volume_percent;
+    let flags: String = [
+        (status.is_loop_all, " [loop]"),
+        (status.is_repeat_one, " [repeat-one]"),
+        (status.is_random, " [random]"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, label)| enabled.then_some(label))
+    .collect();
+    let title = status
+        .information
+        .as_ref()
+        .map(|info| match (info.artist.as_str(), info.title.as_str()) {
+            (_, "") => String::new(),
+            ("", title) => format!(" {title}"),
+            (artist, title) => format!(" {artist} - {title}"),
+        })
+        .unwrap_or_default();
+    format!("{mode} {position}/{duration} vol {volume}%{flags}{title}")
+}
+
+fn items_table(items: &[Item], current_item_id: Option<u64>) -> String {
+    use std::fmt::Write as _;
+
+    let rows: Vec<[String; 3]> = items
+        .iter()
+        .map(|item| {
+            [
+                item.get_id().to_string(),
+                item.get_duration_secs()
+                    .map(format_secs)
+                    .unwrap_or_default(),
+                item.get_name().to_owned(),
+            ]
+        })
+        .collect();
+    let id_width = rows.iter().map(|[id, ..]| id.len()).fold(2, usize::max);
+    let length_width = rows
+        .iter()
+        .map(|[_, length, _]| length.len())
+        .fold(6, usize::max);
+
+    let mut table = format!("  {:>id_width$}  {:>length_width$}  NAME\n", "ID", "LENGTH");
+    for (item, [id, length, name]) in items.iter().zip(&rows) {
+        let marker = if Some(item.get_id()) == current_item_id {
+            '>'
+        } else {
+            ' '
+        };
+        // NOTE: writing to a String is infallible
+        let _ = writeln!(
+            table,
+            "{marker} {id:>id_width$}  {length:>length_width$}  {name}"
+        );
+    }
+    table
+}
+
+fn format_secs(secs: u64) -> String {
+    let (hours, minutes, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes}:{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_secs;
+
+    #[test]
+    fn formats_secs() {
+        assert_eq!(format_secs(0), "0:00");
+        assert_eq!(format_secs(65), "1:05");
+        assert_eq!(format_secs(3600 + 62), "1:01:02");
+    }
+}
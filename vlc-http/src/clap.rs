@@ -1,7 +1,7 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! [`clap`] compatible versions of types
 
-use crate::command::VolumeBoundsError;
+use crate::command::{DelayBoundsError, EqualizerBoundsError, VolumeBoundsError};
 // avoid local name conflicts
 use crate::request::AuthInput as CrateAuthInput;
 use crate::Command as CrateCommand;
@@ -71,10 +71,58 @@ pub enum Command {
         /// Speed on unit scale (1.0 = normal speed)
         speed: f64,
     },
+    // ========================================
+    /// Enable or disable the equalizer
+    EqualizerEnable {
+        /// True to enable the equalizer
+        #[clap(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Set the equalizer preamp gain
+    EqualizerPreamp {
+        /// Gain for the preamp in dB (clamped at +/- 20)
+        #[clap(allow_negative_numbers = true)]
+        gain: f64,
+    },
+    /// Set the gain of one equalizer band
+    EqualizerBand {
+        /// Band index (0 - 9, lowest to highest frequency)
+        band: u8,
+        /// Gain for the band in dB (clamped at +/- 20)
+        #[clap(allow_negative_numbers = true)]
+        gain: f64,
+    },
+    /// Apply an equalizer preset
+    EqualizerPreset {
+        /// Identifier of the preset
+        preset_id: u32,
+    },
+    /// Set the audio delay, relative to the video
+    AudioDelay {
+        /// Delay in seconds (negative for earlier, clamped at +/- 3600)
+        #[clap(allow_negative_numbers = true)]
+        seconds: f64,
+    },
+    /// Set the subtitle delay, relative to the video
+    SubtitleDelay {
+        /// Delay in seconds (negative for earlier, clamped at +/- 3600)
+        #[clap(allow_negative_numbers = true)]
+        seconds: f64,
+    },
+    /// Select the audio track of the current item
+    AudioTrack {
+        /// Identifier of the track (elementary stream)
+        track_id: u64,
+    },
+    /// Select the subtitle track of the current item
+    SubtitleTrack {
+        /// Identifier of the track (elementary stream)
+        track_id: u64,
+    },
 }
 impl TryFrom<Command> for CrateCommand {
-    type Error = VolumeBoundsError;
-    fn try_from(value: Command) -> Result<Self, VolumeBoundsError> {
+    type Error = BoundsError;
+    fn try_from(value: Command) -> Result<Self, BoundsError> {
         use CrateCommand as Dest;
         Ok(match value {
             Command::PlaylistAdd { url } => Dest::PlaylistAdd { url },
@@ -99,10 +147,64 @@ impl TryFrom<Command> for CrateCommand {
                 percent_delta: percent_delta.try_into()?,
             },
             Command::PlaybackSpeed { speed } => Dest::PlaybackSpeed { speed },
+            Command::EqualizerEnable { enabled } => Dest::EqualizerEnable { enabled },
+            Command::EqualizerPreamp { gain } => Dest::EqualizerPreamp {
+                gain: gain.try_into()?,
+            },
+            Command::EqualizerBand { band, gain } => Dest::EqualizerBand {
+                band: band.try_into()?,
+                gain: gain.try_into()?,
+            },
+            Command::EqualizerPreset { preset_id } => Dest::EqualizerPreset { preset_id },
+            Command::AudioDelay { seconds } => Dest::AudioDelay {
+                seconds: seconds.try_into()?,
+            },
+            Command::SubtitleDelay { seconds } => Dest::SubtitleDelay {
+                seconds: seconds.try_into()?,
+            },
+            Command::AudioTrack { track_id } => Dest::AudioTrack { track_id },
+            Command::SubtitleTrack { track_id } => Dest::SubtitleTrack { track_id },
         })
     }
 }
 
+/// Error converting a [`Command`] with out-of-range values
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum BoundsError {
+    /// Volume out of range
+    Volume(VolumeBoundsError),
+    /// Equalizer gain or band out of range
+    Equalizer(EqualizerBoundsError),
+    /// Audio or subtitle delay out of range
+    Delay(DelayBoundsError),
+}
+impl From<VolumeBoundsError> for BoundsError {
+    fn from(value: VolumeBoundsError) -> Self {
+        Self::Volume(value)
+    }
+}
+impl From<EqualizerBoundsError> for BoundsError {
+    fn from(value: EqualizerBoundsError) -> Self {
+        Self::Equalizer(value)
+    }
+}
+impl From<DelayBoundsError> for BoundsError {
+    fn from(value: DelayBoundsError) -> Self {
+        Self::Delay(value)
+    }
+}
+impl std::fmt::Display for BoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Volume(error) => write!(f, "{error}"),
+            Self::Equalizer(error) => write!(f, "{error}"),
+            Self::Delay(error) => write!(f, "{error}"),
+        }
+    }
+}
+impl std::error::Error for BoundsError {}
+
 /// Input authentication parameters to the VLC instance
 #[derive(Clone, clap::Args, Debug)]
 pub struct AuthInput {
@@ -115,6 +217,21 @@ pub struct AuthInput {
     /// Port number
     #[clap(long, env = "VLC_PORT")]
     pub port: u16,
+    /// URI scheme
+    #[clap(long, env = "VLC_SCHEME", value_enum, default_value_t = Scheme::Http)]
+    pub scheme: Scheme,
+    /// Path prefix for all endpoints (e.g. `/vlc` when behind a reverse proxy)
+    #[clap(long, env = "VLC_PATH_PREFIX")]
+    pub path_prefix: Option<String>,
+    /// Additional HTTP header for all requests, as `NAME: VALUE` (newline-separated in the
+    /// environment variable)
+    #[clap(
+        long = "header",
+        env = "VLC_EXTRA_HEADERS",
+        value_delimiter = '\n',
+        value_parser = parse_header
+    )]
+    pub extra_headers: Vec<(String, String)>,
 }
 impl From<AuthInput> for CrateAuthInput {
     fn from(value: AuthInput) -> Self {
@@ -122,11 +239,41 @@ impl From<AuthInput> for CrateAuthInput {
             password,
             host,
             port,
+            scheme,
+            path_prefix,
+            extra_headers,
         } = value;
         Self {
             password,
             host,
             port,
+            scheme: scheme.into(),
+            path_prefix,
+            extra_headers,
+        }
+    }
+}
+fn parse_header(header: &str) -> Result<(String, String), String> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("expected NAME: VALUE, found {header:?}"))?;
+    Ok((name.trim().to_owned(), value.trim().to_owned()))
+}
+
+/// URI scheme for reaching the VLC instance
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+#[must_use]
+pub enum Scheme {
+    /// Plain HTTP (VLC default)
+    Http,
+    /// HTTPS, e.g. for VLC behind a TLS reverse proxy
+    Https,
+}
+impl From<Scheme> for crate::request::Scheme {
+    fn from(value: Scheme) -> Self {
+        match value {
+            Scheme::Http => Self::Http,
+            Scheme::Https => Self::Https,
         }
     }
 }
@@ -193,13 +340,104 @@ pub struct PlaylistSetQueryMatched {
     /// Minimum number of history (past-played) items to retain
     #[clap(long, default_value_t = 10)]
     keep_history: u16,
+    /// Choose the match with the fewest commands (instead of the first match)
+    #[clap(long)]
+    minimize_edits: bool,
 }
 impl From<PlaylistSetQueryMatched> for crate::goal::TargetPlaylistItems {
     fn from(value: PlaylistSetQueryMatched) -> Self {
-        let PlaylistSetQueryMatched { urls, keep_history } = value;
+        let PlaylistSetQueryMatched {
+            urls,
+            keep_history,
+            minimize_edits,
+        } = value;
         Self::new()
             .set_urls(urls) //
             .set_keep_history(keep_history)
+            .set_minimize_edits(minimize_edits)
+    }
+}
+
+/// Full desired state for VLC (only the specified fields are changed)
+#[derive(clap::Args, Clone, Debug)]
+pub struct DesiredState {
+    /// Path to the file(s) for the playlist, starting with the current/past item
+    #[clap(long = "playlist-url")]
+    playlist_urls: Vec<url::Url>,
+    /// Minimum number of history (past-played) items to retain (sets the playlist, even if empty)
+    #[clap(long)]
+    keep_history: Option<u16>,
+    /// Rule for repeating items
+    #[clap(long)]
+    repeat_mode: Option<RepeatMode>,
+    /// Randomize the VLC playback order
+    #[clap(long)]
+    random: Option<bool>,
+    /// Percentage for the volume
+    #[clap(long)]
+    volume: Option<u16>,
+    /// URL of the item to play
+    #[clap(long)]
+    playing_url: Option<url::Url>,
+    /// Playing/paused/stopped state
+    #[clap(long)]
+    playback: Option<DesiredPlayback>,
+}
+/// Desired playback state
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+#[must_use]
+pub enum DesiredPlayback {
+    /// Current item is playing
+    Playing,
+    /// Current item is paused
+    Paused,
+    /// No item is selected for playback
+    Stopped,
+}
+impl From<DesiredPlayback> for crate::goal::DesiredPlayback {
+    fn from(value: DesiredPlayback) -> Self {
+        match value {
+            DesiredPlayback::Playing => Self::Playing,
+            DesiredPlayback::Paused => Self::Paused,
+            DesiredPlayback::Stopped => Self::Stopped,
+        }
+    }
+}
+impl TryFrom<DesiredState> for crate::goal::DesiredState {
+    type Error = VolumeBoundsError;
+    fn try_from(value: DesiredState) -> Result<Self, VolumeBoundsError> {
+        let DesiredState {
+            playlist_urls,
+            keep_history,
+            repeat_mode,
+            random,
+            volume,
+            playing_url,
+            playback,
+        } = value;
+        let mut target = Self::new();
+        if !playlist_urls.is_empty() || keep_history.is_some() {
+            let playlist = crate::goal::TargetPlaylistItems::new()
+                .set_urls(playlist_urls)
+                .set_keep_history(keep_history.unwrap_or_default());
+            target = target.set_playlist(playlist);
+        }
+        if repeat_mode.is_some() || random.is_some() {
+            let mode = crate::goal::PlaybackMode::new()
+                .set_repeat(repeat_mode.map_or(crate::goal::RepeatMode::Off, Into::into))
+                .set_random(random.unwrap_or_default());
+            target = target.set_playback_mode(mode);
+        }
+        if let Some(volume) = volume {
+            target = target.set_volume(volume.try_into()?);
+        }
+        if let Some(playing_url) = playing_url {
+            target = target.set_playing_url(playing_url);
+        }
+        if let Some(playback) = playback {
+            target = target.set_playback(playback.into());
+        }
+        Ok(target)
     }
 }
 
@@ -2,17 +2,34 @@
 //! Types to track the state of a specific VLC instance
 
 use self::sequenced::Sequenced;
-use crate::{response, Response};
+use crate::{response, Command, Endpoint, Response};
+use std::time::Instant;
 
 pub(crate) use sequenced::Sequence;
 mod sequenced;
 
+pub use events::ChangeEvent;
+mod events;
+
+pub use estimate::PlaybackEstimate;
+mod estimate;
+
 /// Tracks the state of a specific VLC instance
 #[derive(Clone, Debug)]
 #[must_use]
 pub struct ClientState {
     playlist_info: Sequenced<response::PlaylistInfo>,
     playback_status: Sequenced<Option<response::PlaybackStatus>>,
+    /// Art for a single item, kept until replaced (art is not expected to change for an item)
+    ///
+    /// Cleared on VLC restart, as playlist IDs may be reused for different items
+    art: Option<response::Art>,
+    /// Most recent directory listing
+    browse_listing: Sequenced<Option<response::BrowseListing>>,
+    /// Highest playlist item ID reported, for detecting VLC restarts
+    max_item_id_seen: Option<u64>,
+    /// Receive time of the playback status, for [`ClientState::estimate_playback`]
+    playback_timing: estimate::Timing,
 }
 
 impl ClientState {
@@ -22,24 +39,103 @@ impl ClientState {
         Self {
             playlist_info: builder.next_default(),
             playback_status: builder.next_default(),
+            art: None,
+            browse_listing: builder.next_default(),
+            max_item_id_seen: None,
+            playback_timing: estimate::Timing::default(),
         }
     }
 
-    /// Updates the state for the specified [`Response`]
+    /// Updates the state for the specified [`Response`], returning the detected changes
     ///
     /// This allows [`Plan`](`crate::Plan`)s to progress to return a result, or a new
     /// [`Endpoint`](`crate::Endpoint`)
-    pub fn update(&mut self, response: Response) {
+    ///
+    /// The receive time is taken as [`Instant::now`], see [`ClientState::update_at`] to specify
+    /// the time explicitly.
+    pub fn update(&mut self, response: Response) -> Vec<ChangeEvent> {
+        self.update_at(response, Instant::now())
+    }
+    /// Updates the state for the specified [`Response`] received at time `received`, returning the
+    /// detected changes
+    ///
+    /// See [`ClientState::update`]
+    pub fn update_at(&mut self, response: Response, received: Instant) -> Vec<ChangeEvent> {
+        let events = self.update_inner(response, received);
+        if events.contains(&ChangeEvent::VlcRestarted) {
+            self.art = None;
+        }
+        events
+    }
+    fn update_inner(&mut self, response: Response, received: Instant) -> Vec<ChangeEvent> {
         match response.inner {
             crate::response::ResponseInner::PlaylistInfo(new) => {
-                let _ = self.playlist_info.replace(new);
+                let is_first = self.playlist_info.is_initial();
+                let previous = self.playlist_info.replace(new);
+                let events = events::diff_playlist(
+                    &previous,
+                    &self.playlist_info,
+                    &mut self.max_item_id_seen,
+                );
+                if is_first {
+                    vec![]
+                } else {
+                    events
+                }
             }
             crate::response::ResponseInner::PlaybackStatus(new) => {
-                let _ = self.playback_status.replace(Some(new));
+                self.playback_timing.notify_status(received);
+                let previous = self.playback_status.replace(Some(*new));
+                match (previous, &*self.playback_status) {
+                    (Some(previous), Some(current)) => events::diff_status(&previous, current),
+                    _ => vec![],
+                }
+            }
+            crate::response::ResponseInner::Art(new) => {
+                self.art = Some(new);
+                vec![]
+            }
+            crate::response::ResponseInner::BrowseListing(new) => {
+                let _ = self.browse_listing.replace(Some(new));
+                vec![]
             }
         }
     }
 
+    /// Records the [`Command`] sent to VLC at time `sent`, marking later estimates as stale until
+    /// a new playback status is received
+    ///
+    /// Only commands that may change the playback position are recorded (e.g. seek, pause, or
+    /// speed change).
+    ///
+    /// NOTE: [`complete_plan`](`crate::sync::complete_plan`) and the
+    /// [`Coordinator`](`crate::multi::Coordinator`) record their commands, see
+    /// [`Self::notify_endpoint`] for requests sent directly.
+    pub fn notify_command(&mut self, command: &Command, sent: Instant) {
+        self.playback_timing.notify_command(command.effect(), sent);
+    }
+    /// Records the [`Endpoint`] requested from VLC at time `sent`, if the endpoint is a command
+    /// (see [`Self::notify_command`])
+    pub fn notify_endpoint(&mut self, endpoint: &Endpoint, sent: Instant) {
+        if let Some(effect) = endpoint.get_command_effect() {
+            self.playback_timing.notify_command(effect, sent);
+        }
+    }
+    /// Returns `true` if the playback status was received long enough after `sent` to reflect a
+    /// command sent at that time
+    pub(crate) fn is_status_settled_after(&self, sent: Instant) -> bool {
+        self.playback_timing.is_settled_after(sent)
+    }
+    /// Returns the predicted playback position at time `now`, extrapolated from the most recent
+    /// playback status according to the playback mode and rate
+    ///
+    /// Returns `None` if no playback status was received.
+    #[must_use]
+    pub fn estimate_playback(&self, now: Instant) -> Option<PlaybackEstimate> {
+        let status = self.playback_status.as_ref()?;
+        self.playback_timing.estimate(status, now)
+    }
+
     /// Returns a short-lived builder referencing the current [`ClientState`]
     ///
     /// The reference is needed to ensure any cached data used in building the
@@ -71,10 +167,15 @@ impl ClientState {
         let Self {
             playlist_info,
             playback_status,
+            art: _,
+            browse_listing,
+            max_item_id_seen: _,
+            playback_timing: _,
         } = self;
         ClientStateSequence {
             playlist_info: playlist_info.get_sequence(),
             playback_status: playback_status.get_sequence(),
+            browse_listing: browse_listing.get_sequence(),
         }
     }
 
@@ -88,6 +189,16 @@ impl ClientState {
     pub(crate) fn playback_status(&self) -> &Sequenced<Option<response::PlaybackStatus>> {
         &self.playback_status
     }
+    /// NOTE: All access to state must flow through [`Action`](crate::Action) to ensure the user
+    /// considered the cache invalidation cases
+    pub(crate) fn art(&self) -> Option<&response::Art> {
+        self.art.as_ref()
+    }
+    /// NOTE: All access to state must flow through [`Action`](crate::Action) to ensure the user
+    /// considered the cache invalidation cases
+    pub(crate) fn browse_listing(&self) -> &Sequenced<Option<response::BrowseListing>> {
+        &self.browse_listing
+    }
 }
 impl Default for ClientState {
     fn default() -> Self {
@@ -121,6 +232,7 @@ impl PlanBuilder<'_> {
 pub(crate) struct ClientStateSequence {
     playlist_info: Sequence,
     playback_status: Sequence,
+    browse_listing: Sequence,
 }
 impl ClientStateSequence {
     pub(crate) fn playlist_info(self) -> Sequence {
@@ -129,6 +241,9 @@ impl ClientStateSequence {
     pub(crate) fn playback_status(self) -> Sequence {
         self.playback_status
     }
+    pub(crate) fn browse_listing(self) -> Sequence {
+        self.browse_listing
+    }
     // fn try_min(self, other: Self) -> Result<Self, InvalidClientInstance> {
     //     let Self {
     //         playlist_info,
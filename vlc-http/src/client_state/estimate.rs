@@ -0,0 +1,229 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use crate::{
+    request::CommandEffect,
+    response::{playback::Mode, PlaybackStatus},
+};
+use std::time::{Duration, Instant};
+
+/// Predicted playback position at a specific instant, from
+/// [`ClientState::estimate_playback`](`super::ClientState::estimate_playback`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct PlaybackEstimate {
+    position: Duration,
+    remaining: Option<Duration>,
+    track_end: Option<Instant>,
+    is_stale: bool,
+}
+impl PlaybackEstimate {
+    /// Returns the predicted position within the current item
+    #[must_use]
+    pub fn get_position(&self) -> Duration {
+        self.position
+    }
+    /// Returns the predicted media time remaining in the current item (if the duration is known)
+    #[must_use]
+    pub fn get_remaining(&self) -> Option<Duration> {
+        self.remaining
+    }
+    /// Returns the predicted instant the current item ends (only while playing, with a known
+    /// duration)
+    #[must_use]
+    pub fn get_track_end(&self) -> Option<Instant> {
+        self.track_end
+    }
+    /// Returns `true` if a command sent since the status was received may have changed the
+    /// playback (e.g. seek, pause, or speed change)
+    ///
+    /// Stale estimates are still extrapolated from the last status, but should be confirmed by
+    /// fetching a new status.
+    #[must_use]
+    pub fn is_stale(&self) -> bool {
+        self.is_stale
+    }
+}
+
+/// Receive times of the playback status, and of commands affecting the timing
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Timing {
+    status_received: Option<Instant>,
+    command_sent: Option<Instant>,
+}
+impl Timing {
+    /// Delay after a command before a status is assumed to reflect the command
+    const COMMAND_SETTLE: Duration = Duration::from_millis(50);
+
+    pub fn notify_status(&mut self, received: Instant) {
+        self.status_received = Some(received);
+    }
+    /// Returns `true` if the status was received long enough after `sent` to reflect a command
+    pub fn is_settled_after(self, sent: Instant) -> bool {
+        self.status_received
+            .is_some_and(|received| received >= sent + Self::COMMAND_SETTLE)
+    }
+    pub fn notify_command(&mut self, effect: CommandEffect, sent: Instant) {
+        if effect.affects_timing() {
+            self.command_sent = Some(sent);
+        }
+    }
+    pub fn estimate(self, status: &PlaybackStatus, now: Instant) -> Option<PlaybackEstimate> {
+        let received = self.status_received?;
+        // NOTE: VLC responds to commands before applying them, so wait for a settled status
+        let is_stale = self
+            .command_sent
+            .is_some_and(|sent| received < sent + Self::COMMAND_SETTLE);
+
+        let duration =
+            (status.duration_secs > 0).then(|| Duration::from_secs(status.duration_secs));
+        let reported = Duration::from_secs(status.position_secs);
+        let rate = status.rate_ratio;
+        let is_advancing = status.mode == Mode::Playing && rate > 0.0 && rate.is_finite();
+        let position = if is_advancing {
+            let elapsed = now.saturating_duration_since(received);
+            let position = reported.saturating_add(scale_secs(elapsed, rate));
+            duration.map_or(position, |duration| position.min(duration))
+        } else {
+            reported
+        };
+        let remaining = duration.map(|duration| duration.saturating_sub(position));
+        let track_end = remaining
+            .filter(|_| is_advancing)
+            .and_then(|remaining| now.checked_add(scale_secs(remaining, rate.recip())));
+
+        Some(PlaybackEstimate {
+            position,
+            remaining,
+            track_end,
+            is_stale,
+        })
+    }
+}
+
+/// Multiplies the duration by the (positive, finite) factor, saturating on overflow
+fn scale_secs(duration: Duration, factor: f64) -> Duration {
+    Duration::try_from_secs_f64(duration.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use crate::{
+        test_fixtures::{status, Clock},
+        ClientState, Command, Endpoint,
+    };
+    use std::time::Instant;
+
+    /// Returns the position, remaining (millis), track end (millis since the clock start) and
+    /// stale flag
+    fn estimate(
+        state: &ClientState,
+        clock: &Clock,
+        now_millis: u64,
+    ) -> (u128, Option<u128>, Option<u128>, bool) {
+        let estimate = state
+            .estimate_playback(clock.at_millis(now_millis))
+            .unwrap();
+        (
+            estimate.get_position().as_millis(),
+            estimate
+                .get_remaining()
+                .map(|remaining| remaining.as_millis()),
+            estimate
+                .get_track_end()
+                .map(|track_end| track_end.duration_since(clock.at_millis(0)).as_millis()),
+            estimate.is_stale(),
+        )
+    }
+
+    #[test]
+    fn empty() {
+        let state = ClientState::new();
+        assert_eq!(state.estimate_playback(Instant::now()), None);
+    }
+
+    #[test]
+    fn playing() {
+        let clock = Clock::new();
+        let mut state = ClientState::new();
+        state.update_at(status("playing", 100, 20, 1.0, 256), clock.at_millis(1_000));
+
+        assert_eq!(
+            estimate(&state, &clock, 1_000),
+            (20_000, Some(80_000), Some(81_000), false)
+        );
+        assert_eq!(
+            estimate(&state, &clock, 11_500),
+            (30_500, Some(69_500), Some(81_000), false)
+        );
+        // limited to the duration
+        assert_eq!(
+            estimate(&state, &clock, 200_000),
+            (100_000, Some(0), Some(200_000), false)
+        );
+    }
+
+    #[test]
+    fn rate() {
+        for (rate, expected) in [
+            (2.0, (60_000, Some(40_000), Some(30_000), false)),
+            (0.5, (45_000, Some(55_000), Some(120_000), false)),
+            (0.0, (40_000, Some(60_000), None, false)),
+        ] {
+            let clock = Clock::new();
+            let mut state = ClientState::new();
+            state.update_at(status("playing", 100, 40, rate, 256), clock.at_millis(0));
+            assert_eq!(estimate(&state, &clock, 10_000), expected, "rate {rate}");
+        }
+    }
+
+    #[test]
+    fn not_playing() {
+        for mode in ["paused", "stopped"] {
+            let clock = Clock::new();
+            let mut state = ClientState::new();
+            state.update_at(status(mode, 100, 20, 1.0, 256), clock.at_millis(0));
+            assert_eq!(
+                estimate(&state, &clock, 10_000),
+                (20_000, Some(80_000), None, false),
+                "mode {mode}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_duration() {
+        let clock = Clock::new();
+        let mut state = ClientState::new();
+        state.update_at(status("playing", 0, 20, 1.0, 256), clock.at_millis(0));
+        assert_eq!(estimate(&state, &clock, 5_000), (25_000, None, None, false));
+    }
+
+    #[test]
+    fn stale_after_command() {
+        let clock = Clock::new();
+        let mut state = ClientState::new();
+        state.update_at(status("playing", 100, 20, 1.0, 256), clock.at_millis(0));
+
+        // ignores commands not affecting the position, and queries
+        state.notify_command(&Command::ToggleRandom, clock.at_millis(1_000));
+        state.notify_endpoint(&Endpoint::query_status(), clock.at_millis(1_000));
+        assert!(!estimate(&state, &clock, 1_000).3);
+
+        state.notify_command(&Command::SeekTo { seconds: 50 }, clock.at_millis(2_000));
+        assert_eq!(
+            estimate(&state, &clock, 2_000),
+            (22_000, Some(78_000), Some(80_000), true)
+        );
+
+        // response to the command is not yet settled
+        state.update_at(status("playing", 100, 22, 1.0, 256), clock.at_millis(2_010));
+        assert!(estimate(&state, &clock, 2_010).3);
+
+        state.update_at(status("playing", 100, 50, 1.0, 256), clock.at_millis(2_100));
+        assert_eq!(
+            estimate(&state, &clock, 3_100),
+            (51_000, Some(49_000), Some(52_100), false)
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use crate::{
+    goal::{PlaybackMode, RepeatMode},
+    response::{playback::Mode, playlist, PlaybackStatus, PlaylistInfo},
+};
+use std::collections::BTreeSet;
+
+/// Change in VLC state, detected by [`ClientState::update`](`super::ClientState::update`)
+///
+/// No events are reported for the first response of each kind (no previous state to compare)
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[non_exhaustive]
+pub enum ChangeEvent {
+    /// Current item changed (by playlist ID)
+    TrackChanged {
+        /// Previous current item
+        previous: Option<u64>,
+        /// New current item
+        current: Option<u64>,
+    },
+    /// Repeat/random mode changed
+    PlaybackModeChanged {
+        /// Previous mode
+        previous: PlaybackMode,
+        /// New mode
+        current: PlaybackMode,
+    },
+    /// Volume changed
+    VolumeChanged {
+        /// Previous volume percentage
+        previous_percent: u16,
+        /// New volume percentage
+        current_percent: u16,
+    },
+    /// Position within the current item jumped
+    ///
+    /// NOTE: Without a clock, only jumps that cannot be explained by forward playback are
+    /// detected: moving backward, or moving at all while paused. The same item restarting
+    /// between updates (e.g. repeat) is also reported.
+    Seeked {
+        /// Playlist ID of the current item
+        item_id: u64,
+        /// Previous position (in seconds)
+        previous_secs: u64,
+        /// New position (in seconds)
+        current_secs: u64,
+    },
+    /// Items added to the playlist
+    PlaylistItemsAdded {
+        /// Playlist IDs of the new items
+        item_ids: Vec<u64>,
+    },
+    /// Items removed from the playlist
+    PlaylistItemsRemoved {
+        /// Playlist IDs of the removed items
+        item_ids: Vec<u64>,
+    },
+    /// VLC instance restarted, detected by a changed version or reused playlist IDs
+    ///
+    /// Other events in the same update compare against the state before the restart.
+    VlcRestarted,
+}
+
+impl PlaybackMode {
+    fn from_status(status: &PlaybackStatus) -> Self {
+        let repeat = if status.is_repeat_one {
+            RepeatMode::One
+        } else if status.is_loop_all {
+            RepeatMode::All
+        } else {
+            RepeatMode::Off
+        };
+        Self::new().set_repeat(repeat).set_random(status.is_random)
+    }
+}
+
+fn current_item_id(status: &PlaybackStatus) -> Option<u64> {
+    status
+        .information
+        .as_ref()
+        .and_then(|info| info.playlist_item_id)
+}
+
+/// Returns the events for a new [`PlaybackStatus`]
+pub(super) fn diff_status(previous: &PlaybackStatus, current: &PlaybackStatus) -> Vec<ChangeEvent> {
+    let mut events = vec![];
+
+    if previous.apiversion != current.apiversion || previous.version != current.version {
+        events.push(ChangeEvent::VlcRestarted);
+    }
+
+    let previous_item = current_item_id(previous);
+    let current_item = current_item_id(current);
+    if previous_item == current_item {
+        let jumped = if current.mode == Mode::Paused && previous.mode == Mode::Paused {
+            current.position_secs != previous.position_secs
+        } else {
+            current.position_secs < previous.position_secs
+        };
+        if let (Some(item_id), true) = (current_item, jumped) {
+            events.push(ChangeEvent::Seeked {
+                item_id,
+                previous_secs: previous.position_secs,
+                current_secs: current.position_secs,
+            });
+        }
+    } else {
+        events.push(ChangeEvent::TrackChanged {
+            previous: previous_item,
+            current: current_item,
+        });
+    }
+
+    let previous_mode = PlaybackMode::from_status(previous);
+    let current_mode = PlaybackMode::from_status(current);
+    if previous_mode != current_mode {
+        events.push(ChangeEvent::PlaybackModeChanged {
+            previous: previous_mode,
+            current: current_mode,
+        });
+    }
+
+    if previous.volume_percent != current.volume_percent {
+        events.push(ChangeEvent::VolumeChanged {
+            previous_percent: previous.volume_percent,
+            current_percent: current.volume_percent,
+        });
+    }
+
+    events
+}
+
+/// Returns the events for a new [`PlaylistInfo`], updating the maximum item ID seen
+///
+/// Items are compared by ID and URL, so an ID reused for a different URL is both removed and added.
+///
+/// VLC assigns increasing playlist IDs, so a new ID at or below the maximum indicates a restart.
+pub(super) fn diff_playlist(
+    previous: &PlaylistInfo,
+    current: &PlaylistInfo,
+    max_item_id_seen: &mut Option<u64>,
+) -> Vec<ChangeEvent> {
+    fn id_and_url(item: &playlist::Item) -> (u64, &url::Url) {
+        (item.get_id(), item.get_url())
+    }
+    let mut events = vec![];
+
+    let previous_items: BTreeSet<_> = previous.items.iter().map(id_and_url).collect();
+    let current_items: BTreeSet<_> = current.items.iter().map(id_and_url).collect();
+
+    let added: Vec<u64> = current_items
+        .difference(&previous_items)
+        .map(|&(id, _)| id)
+        .collect();
+    let removed: Vec<u64> = previous_items
+        .difference(&current_items)
+        .map(|&(id, _)| id)
+        .collect();
+
+    let ids_reused = match (*max_item_id_seen, added.first()) {
+        (Some(max_seen), Some(&min_added)) => min_added <= max_seen,
+        _ => false,
+    };
+    if ids_reused {
+        events.push(ChangeEvent::VlcRestarted);
+        *max_item_id_seen = None;
+    }
+    *max_item_id_seen = (*max_item_id_seen).max(current_items.last().map(|&(id, _)| id));
+
+    if !added.is_empty() {
+        events.push(ChangeEvent::PlaylistItemsAdded { item_ids: added });
+    }
+    if !removed.is_empty() {
+        events.push(ChangeEvent::PlaylistItemsRemoved { item_ids: removed });
+    }
+
+    events
+}
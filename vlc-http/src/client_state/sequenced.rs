@@ -18,6 +18,10 @@ impl<T> Sequenced<T> {
     pub fn get_sequence(&self) -> Sequence {
         self.sequence
     }
+    /// Returns `true` if the value was never replaced
+    pub fn is_initial(&self) -> bool {
+        self.sequence.is_initial()
+    }
     fn increment(&mut self) {
         self.sequence = self.sequence.next();
     }
@@ -67,6 +71,9 @@ mod sequence {
         pub fn new(instance: Instance) -> Self {
             Self { instance, count: 0 }
         }
+        pub fn is_initial(self) -> bool {
+            self.count == 0
+        }
         pub fn next(self) -> Self {
             let Self { instance, count } = self;
             Self {
@@ -73,6 +73,49 @@ pub enum Command {
         /// Speed on unit scale (1.0 = normal speed)
         speed: f64,
     },
+    // ========================================
+    /// Enable or disable the equalizer
+    EqualizerEnable {
+        /// True to enable the equalizer
+        enabled: bool,
+    },
+    /// Set the equalizer preamp gain
+    EqualizerPreamp {
+        /// Gain for the preamp
+        gain: EqualizerGain,
+    },
+    /// Set the gain of one equalizer band
+    EqualizerBand {
+        /// Band to adjust
+        band: EqualizerBand,
+        /// Gain for the band
+        gain: EqualizerGain,
+    },
+    /// Apply an equalizer preset (see [`Equalizer::presets`](`crate::response::playback::Equalizer::presets`))
+    EqualizerPreset {
+        /// Identifier of the preset
+        preset_id: u32,
+    },
+    /// Set the audio delay, relative to the video
+    AudioDelay {
+        /// Delay (negative for earlier)
+        seconds: DelaySeconds,
+    },
+    /// Set the subtitle delay, relative to the video
+    SubtitleDelay {
+        /// Delay (negative for earlier)
+        seconds: DelaySeconds,
+    },
+    /// Select the audio track of the current item
+    AudioTrack {
+        /// Identifier of the track (elementary stream)
+        track_id: u64,
+    },
+    /// Select the subtitle track of the current item
+    SubtitleTrack {
+        /// Identifier of the track (elementary stream)
+        track_id: u64,
+    },
 }
 
 pub use volume::Percent as VolumePercent;
@@ -88,6 +131,8 @@ mod volume {
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct Percent(u16);
     impl Percent {
+        /// Maximum volume percentage (300%)
+        pub const MAX: Self = Self(MAX_INCLUSIVE);
         /// Constructor for volume percentage
         ///
         /// # Errors
@@ -118,6 +163,11 @@ mod volume {
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct PercentDelta(i16);
     impl PercentDelta {
+        /// Maximum volume percentage delta (+300%)
+        #[expect(clippy::cast_possible_wrap)] // bound comfortably fits in i16
+        pub const MAX: Self = Self(MAX_INCLUSIVE as i16);
+        /// Minimum volume percentage delta (-300%)
+        pub const MIN: Self = Self(-Self::MAX.0);
         /// Constructor for volume percentage delta
         ///
         /// # Errors
@@ -172,6 +222,153 @@ mod volume {
     }
 }
 
+pub use equalizer::Band as EqualizerBand;
+pub use equalizer::Gain as EqualizerGain;
+mod equalizer {
+    //! Encapsulation boundary for the numeric limits on the equalizer types
+
+    use super::EqualizerBoundsError;
+
+    pub(crate) const GAIN_MAX_DB: f64 = 20.0;
+    pub(crate) const BAND_COUNT: u8 = 10;
+
+    /// Equalizer gain in decibels, clamped to +/- 20 dB (inclusive)
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Gain(f64);
+    impl Gain {
+        /// Constructor for equalizer gain (in dB)
+        ///
+        /// # Errors
+        /// Returns an error if the gain is out of bounds (or not a number)
+        ///
+        /// ```
+        /// use vlc_http::command::EqualizerGain;
+        /// assert!(EqualizerGain::new(-20.0).is_ok());
+        /// assert!(EqualizerGain::new(20.0).is_ok());
+        ///
+        /// assert!(EqualizerGain::new(20.1).is_err());
+        /// assert!(EqualizerGain::new(f64::NAN).is_err());
+        /// ```
+        pub fn new(gain_db: f64) -> Result<Self, EqualizerBoundsError> {
+            (gain_db.abs() <= GAIN_MAX_DB)
+                .then_some(Self(gain_db))
+                .ok_or(EqualizerBoundsError::Gain(gain_db))
+        }
+        /// Returns the gain value (in dB)
+        #[must_use]
+        pub fn value(self) -> f64 {
+            self.0
+        }
+    }
+
+    /// Index of an equalizer band, 0 - 9 (inclusive) from lowest to highest frequency
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Band(u8);
+    impl Band {
+        /// Constructor for equalizer band index
+        ///
+        /// # Errors
+        /// Returns an error if the band is out of bounds
+        ///
+        /// ```
+        /// use vlc_http::command::EqualizerBand;
+        /// assert!(EqualizerBand::new(9).is_ok());
+        ///
+        /// assert!(EqualizerBand::new(10).is_err());
+        /// ```
+        pub fn new(band: u8) -> Result<Self, EqualizerBoundsError> {
+            (band < BAND_COUNT)
+                .then_some(Self(band))
+                .ok_or(EqualizerBoundsError::Band(band))
+        }
+        /// Returns the band index
+        #[must_use]
+        pub fn value(self) -> u8 {
+            self.0
+        }
+    }
+}
+
+pub use delay::Seconds as DelaySeconds;
+mod delay {
+    //! Encapsulation boundary for the numeric limits on the delay type
+
+    use super::DelayBoundsError;
+
+    pub(crate) const MAX_SECONDS: f64 = 3600.0;
+
+    /// Audio or subtitle delay in seconds, clamped to +/- 1 hour (inclusive)
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Seconds(f64);
+    impl Seconds {
+        /// Constructor for delay (in seconds)
+        ///
+        /// # Errors
+        /// Returns an error if the delay is out of bounds (or not a number)
+        ///
+        /// ```
+        /// use vlc_http::command::DelaySeconds;
+        /// assert!(DelaySeconds::new(-3600.0).is_ok());
+        /// assert!(DelaySeconds::new(1.5).is_ok());
+        ///
+        /// assert!(DelaySeconds::new(3600.5).is_err());
+        /// assert!(DelaySeconds::new(f64::INFINITY).is_err());
+        /// assert!(DelaySeconds::new(f64::NAN).is_err());
+        /// ```
+        pub fn new(seconds: f64) -> Result<Self, DelayBoundsError> {
+            (seconds.abs() <= MAX_SECONDS)
+                .then_some(Self(seconds))
+                .ok_or(DelayBoundsError(seconds))
+        }
+        /// Returns the delay value (in seconds)
+        #[must_use]
+        pub fn value(self) -> f64 {
+            self.0
+        }
+    }
+}
+
+/// Error in constructing a [`DelaySeconds`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DelayBoundsError(f64);
+impl std::fmt::Display for DelayBoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(value) = *self;
+        write!(
+            f,
+            "delay {value} seconds out of range (+/-{})",
+            delay::MAX_SECONDS
+        )
+    }
+}
+impl std::error::Error for DelayBoundsError {}
+
+/// Error in constructing an equalizer type
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EqualizerBoundsError {
+    /// Gain (dB) out of range
+    Gain(f64),
+    /// Band index out of range
+    Band(u8),
+}
+impl std::fmt::Display for EqualizerBoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Gain(value) => write!(
+                f,
+                "equalizer gain {value} dB out of range (+/-{} dB)",
+                equalizer::GAIN_MAX_DB
+            ),
+            Self::Band(value) => write!(
+                f,
+                "equalizer band {value} out of range (0-{})",
+                equalizer::BAND_COUNT - 1
+            ),
+        }
+    }
+}
+impl std::error::Error for EqualizerBoundsError {}
+
 /// Error in constructing a volume type
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct VolumeBoundsError {
@@ -262,6 +459,25 @@ impl From<i32> for SecondsDelta {
         Self(value)
     }
 }
+impl TryFrom<f64> for EqualizerGain {
+    type Error = EqualizerBoundsError;
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+impl TryFrom<f64> for DelaySeconds {
+    type Error = DelayBoundsError;
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+impl TryFrom<u8> for EqualizerBand {
+    type Error = EqualizerBoundsError;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
 impl std::fmt::Display for SecondsDelta {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Self(seconds_delta) = *self;
@@ -307,6 +523,39 @@ impl std::fmt::Debug for Command {
                 .debug_struct("PlaybackSpeed")
                 .field("speed", speed)
                 .finish(),
+            Self::EqualizerEnable { enabled } => f
+                .debug_struct("EqualizerEnable")
+                .field("enabled", enabled)
+                .finish(),
+            Self::EqualizerPreamp { gain } => f
+                .debug_struct("EqualizerPreamp")
+                .field("gain", gain)
+                .finish(),
+            Self::EqualizerBand { band, gain } => f
+                .debug_struct("EqualizerBand")
+                .field("band", band)
+                .field("gain", gain)
+                .finish(),
+            Self::EqualizerPreset { preset_id } => f
+                .debug_struct("EqualizerPreset")
+                .field("preset_id", preset_id)
+                .finish(),
+            Self::AudioDelay { seconds } => f
+                .debug_struct("AudioDelay")
+                .field("seconds", seconds)
+                .finish(),
+            Self::SubtitleDelay { seconds } => f
+                .debug_struct("SubtitleDelay")
+                .field("seconds", seconds)
+                .finish(),
+            Self::AudioTrack { track_id } => f
+                .debug_struct("AudioTrack")
+                .field("track_id", track_id)
+                .finish(),
+            Self::SubtitleTrack { track_id } => f
+                .debug_struct("SubtitleTrack")
+                .field("track_id", track_id)
+                .finish(),
         }
     }
 }
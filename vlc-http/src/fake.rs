@@ -0,0 +1,11 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Fake VLC instance, for testing without a real VLC
+//!
+//! The [`Model`] responds to the same HTTP endpoints as VLC (in-process), and the [`Server`]
+//! serves it over HTTP for end-to-end tests.
+
+pub use model::{Model, ModelResponse};
+mod model;
+
+pub use server::Server;
+mod server;
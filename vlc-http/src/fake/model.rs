@@ -0,0 +1,496 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Simulated playlist and playback state, responding to VLC HTTP requests
+
+use crate::Endpoint;
+use std::time::Duration;
+
+/// Volume reported by VLC for 100%
+const VOLUME_DEFAULT: u16 = 256;
+/// Maximum volume accepted by VLC (300%)
+const VOLUME_MAX: u16 = 768;
+
+/// Simulated VLC instance (playlist items, playback modes and playback timing)
+///
+/// Item lengths are arbitrary but deterministic, based on the item id (the first item has zero
+/// length, so it ends as soon as time advances).
+///
+/// NOTE: Random mode is reported, but playback order is always sequential (deterministic).
+#[derive(Clone, Default, Debug, PartialEq, Eq, serde::Serialize)]
+#[must_use]
+pub struct Model {
+    #[serde(skip)]
+    items_created: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(serialize_with = "serialize_items_vec")]
+    items: Vec<Item>,
+    #[serde(skip_serializing_if = "bool_is_false")]
+    is_loop_all: bool,
+    #[serde(skip_serializing_if = "bool_is_false")]
+    is_repeat_one: bool,
+    #[serde(skip_serializing_if = "bool_is_false")]
+    is_random: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unknown_endpoints: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_item_id: Option<(u32, PlayState)>,
+    #[serde(skip_serializing_if = "Duration::is_zero")]
+    position: Duration,
+    /// Volume on the VLC scale (256 = 100%), `None` for the default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume: Option<u16>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+enum PlayState {
+    Playing,
+    Paused,
+}
+#[derive(Clone, PartialEq, Eq)]
+struct Item {
+    id: u32,
+    uri: String,
+}
+/// Body of a response from the [`Model`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelResponse {
+    /// JSON text (status or playlist)
+    Json(String),
+    /// Fake image bytes (not a valid image)
+    Art(String),
+    /// No content (`404 Not Found`), for unknown endpoints or items without art
+    NotFound,
+}
+impl ModelResponse {
+    /// Content type for the fake image bytes
+    pub const ART_CONTENT_TYPE: &'static str = "image/x-fake";
+    /// Returns the HTTP content type for the response
+    #[must_use]
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json(_) => "application/json",
+            Self::Art(_) => Self::ART_CONTENT_TYPE,
+            Self::NotFound => "text/plain",
+        }
+    }
+    /// Returns the HTTP status code for the response
+    #[must_use]
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::Json(_) | Self::Art(_) => 200,
+            Self::NotFound => 404,
+        }
+    }
+    /// Returns the HTTP response body
+    #[must_use]
+    pub fn into_body(self) -> String {
+        match self {
+            Self::Json(body) | Self::Art(body) => body,
+            Self::NotFound => String::new(),
+        }
+    }
+}
+
+impl Model {
+    /// Adds the items to the playlist, as if added by `in_enqueue` commands
+    ///
+    /// # Panics
+    /// Panics if any items were previously added (one-time initialization)
+    pub fn initialize_items(&mut self, items: Vec<impl ToString>) {
+        assert!(
+            self.items_created == 0,
+            "cannot intialize_items, already processed {} items",
+            self.items_created
+        );
+        for item in items {
+            self.push_uri(item.to_string());
+        }
+    }
+    /// Advances the playback clock, moving to the following items when the current item ends
+    pub fn advance(&mut self, elapsed: Duration) {
+        let mut remaining = elapsed;
+        // NOTE: limit iterations, in case of zero-length items on repeat
+        for _ in 0..=self.items.len() {
+            let Some((id, PlayState::Playing)) = self.current_item_id else {
+                return;
+            };
+            let Some(length) = self.item_length(id) else {
+                // item deleted while playing, continue forever
+                self.position += remaining;
+                return;
+            };
+            let until_end = length.saturating_sub(self.position);
+            if remaining <= until_end {
+                self.position += remaining;
+                return;
+            }
+            remaining -= until_end;
+            self.end_current(id, self.is_repeat_one);
+        }
+    }
+    /// Returns the response for the specified endpoint (path and query)
+    ///
+    /// Unknown endpoints are recorded, and return [`ModelResponse::NotFound`].
+    pub fn request(&mut self, endpoint: &str) -> ModelResponse {
+        let playlist = Endpoint::query_playlist();
+        let playlist = playlist.get_path_and_query();
+
+        let playback = Endpoint::query_status();
+        let playback = playback.get_path_and_query();
+
+        // FIXME improve parsing strategy
+        let (path, args) =
+            endpoint
+                .split_once('?')
+                .map_or((endpoint, Vec::new()), |(base, args)| {
+                    (
+                        base,
+                        args.split('&')
+                            .map(|arg| {
+                                arg.split_once('=')
+                                    .map_or((arg, None), |(key, val)| (key, Some(val)))
+                            })
+                            .collect(),
+                    )
+                });
+
+        let command = args
+            .iter()
+            .find_map(|&(key, val)| (key == "command").then_some(val).flatten());
+        let args: Vec<_> = args
+            .into_iter()
+            .filter(|&(key, _val)| key != "command")
+            .collect();
+
+        let response = if path == playlist {
+            match command {
+                Some("in_enqueue") => self.enqueue(&args),
+                Some("pl_delete") => self.delete(&args),
+                Some(_) => None, // unknown
+                None => Some(self.get_playlist_info()),
+            }
+        } else if path == playback {
+            let handled = if args.is_empty() {
+                self.apply_status_command(command)
+            } else {
+                match command {
+                    Some("pl_play") => self.play(&args),
+                    Some("seek") => self.seek(&args),
+                    Some("volume") => self.set_volume(&args),
+                    None | Some(_) => None, // unknown
+                }
+            };
+            handled.map(|()| self.get_playback_status())
+        } else if path == "/art" {
+            if let Some(response) = self.art(&args) {
+                return response;
+            }
+            None // unknown
+        } else {
+            None
+        };
+
+        if let Some(response) = response {
+            ModelResponse::Json(response)
+        } else {
+            self.unknown_endpoints.push(endpoint.to_owned());
+            ModelResponse::NotFound
+        }
+    }
+    fn apply_status_command(&mut self, command: Option<&str>) -> Option<()> {
+        match command {
+            None => {}
+            Some("pl_random") => toggle(&mut self.is_random),
+            Some("pl_loop") => toggle(&mut self.is_loop_all),
+            Some("pl_repeat") => toggle(&mut self.is_repeat_one),
+            Some("pl_play") => self.resume_or_play_first(),
+            Some("pl_pause") => self.toggle_pause(),
+            Some("pl_forceresume") => self.set_paused(false),
+            Some("pl_forcepause") => self.set_paused(true),
+            Some("pl_stop") => self.stop(),
+            Some("pl_next") => self.next(),
+            Some("pl_previous") => self.previous(),
+            Some(_) => return None, // unknown
+        }
+        Some(())
+    }
+    /// Returns the art (or [`ModelResponse::NotFound`] if there is no such item), or `None` for
+    /// invalid args
+    fn art(&self, args: &[(&str, Option<&str>)]) -> Option<ModelResponse> {
+        let id = match *args {
+            [] => self.current_item_id.map(|(id, _)| id),
+            [("item", Some(id))] => Some(id.parse().ok()?),
+            _ => return None,
+        };
+        let response = id
+            .filter(|&id| self.items.iter().any(|item| item.id == id))
+            .map_or(ModelResponse::NotFound, |id| {
+                ModelResponse::Art(format!("art{id}"))
+            });
+        Some(response)
+    }
+    fn enqueue(&mut self, args: &[(&str, Option<&str>)]) -> Option<String> {
+        let [("input", Some(val))] = *args else {
+            return None;
+        };
+
+        let uri = urlencoding::decode(val).ok()?.to_string();
+
+        self.push_uri(uri);
+
+        Some(self.get_playlist_info())
+    }
+    fn push_uri(&mut self, uri: String) {
+        let id = self.items_created;
+        self.items_created += 1;
+
+        self.items.push(Item { id, uri });
+    }
+    fn delete(&mut self, args: &[(&str, Option<&str>)]) -> Option<String> {
+        let [("id", Some(val))] = *args else {
+            return None;
+        };
+
+        let id: u32 = val.parse::<u32>().ok()?;
+
+        self.items.retain(|item| item.id != id);
+
+        Some(self.get_playlist_info())
+    }
+    fn play(&mut self, args: &[(&str, Option<&str>)]) -> Option<()> {
+        let [("id", Some(val))] = *args else {
+            return None;
+        };
+
+        let id: u32 = val.parse::<u32>().ok()?;
+
+        self.start(id);
+        Some(())
+    }
+    fn start(&mut self, id: u32) {
+        self.current_item_id = Some((id, PlayState::Playing));
+        self.position = Duration::ZERO;
+    }
+    fn resume_or_play_first(&mut self) {
+        match self.current_item_id {
+            Some(_) => self.set_paused(false),
+            None => {
+                if let Some(first) = self.items.first() {
+                    self.start(first.id);
+                }
+            }
+        }
+    }
+    fn toggle_pause(&mut self) {
+        if let Some((_, state)) = self.current_item_id {
+            self.set_paused(state == PlayState::Playing);
+        }
+    }
+    fn set_paused(&mut self, paused: bool) {
+        if let Some((_, state)) = &mut self.current_item_id {
+            *state = if paused {
+                PlayState::Paused
+            } else {
+                PlayState::Playing
+            };
+        }
+    }
+    fn stop(&mut self) {
+        self.current_item_id = None;
+        self.position = Duration::ZERO;
+    }
+    fn next(&mut self) {
+        if let Some((id, _)) = self.current_item_id {
+            self.end_current(id, false);
+        }
+    }
+    fn previous(&mut self) {
+        let Some((id, _)) = self.current_item_id else {
+            return;
+        };
+        let index = self.items.iter().position(|item| item.id == id);
+        let previous = match index {
+            Some(0) if self.is_loop_all => self.items.last(),
+            Some(0) | None => self.items.iter().find(|item| item.id == id),
+            Some(index) => self.items.get(index - 1),
+        };
+        match previous {
+            Some(previous) => self.start(previous.id),
+            None => self.stop(),
+        }
+    }
+    /// Moves to the item following `id` (or the same, if `repeat`)
+    fn end_current(&mut self, id: u32, repeat: bool) {
+        if repeat {
+            self.start(id);
+            return;
+        }
+        let index = self.items.iter().position(|item| item.id == id);
+        let next = index
+            .and_then(|index| self.items.get(index + 1))
+            .or_else(|| self.is_loop_all.then(|| self.items.first()).flatten());
+        match next {
+            Some(next) => self.start(next.id),
+            None => self.stop(),
+        }
+    }
+    fn seek(&mut self, args: &[(&str, Option<&str>)]) -> Option<()> {
+        let [("val", Some(val))] = *args else {
+            return None;
+        };
+        let val = urlencoding::decode(val).ok()?;
+        let (id, _) = self.current_item_id?;
+
+        let position_secs = self.position.as_secs();
+        let target_secs = match parse_signed(&val)? {
+            Value::Absolute(secs) => secs,
+            Value::Relative { is_negative, delta } if is_negative => {
+                position_secs.saturating_sub(delta)
+            }
+            Value::Relative { delta, .. } => position_secs.saturating_add(delta),
+        };
+
+        let length = self.item_length(id).unwrap_or(Duration::MAX);
+        self.position = Duration::from_secs(target_secs).min(length);
+        Some(())
+    }
+    fn set_volume(&mut self, args: &[(&str, Option<&str>)]) -> Option<()> {
+        let [("val", Some(val))] = *args else {
+            return None;
+        };
+        let val = urlencoding::decode(val).ok()?;
+
+        let volume = u64::from(self.volume.unwrap_or(VOLUME_DEFAULT));
+        let new_volume = match parse_signed(&val)? {
+            Value::Absolute(volume) => volume,
+            Value::Relative { is_negative, delta } if is_negative => volume.saturating_sub(delta),
+            Value::Relative { delta, .. } => volume.saturating_add(delta),
+        };
+        let new_volume = u16::try_from(new_volume)
+            .unwrap_or(VOLUME_MAX)
+            .min(VOLUME_MAX);
+        self.volume = (new_volume != VOLUME_DEFAULT).then_some(new_volume);
+        Some(())
+    }
+    fn item_length(&self, id: u32) -> Option<Duration> {
+        self.items
+            .iter()
+            .any(|item| item.id == id)
+            .then(|| Duration::from_secs(item_length_secs(id).into()))
+    }
+
+    fn get_playlist_info(&self) -> String {
+        let items = self
+            .items
+            .iter()
+            .map(|Item { id, uri }| {
+                serde_json::json!({
+                    "duration": item_length_secs(*id),
+                    "uri": uri,
+                    "type": "leaf",
+                    "id": id.to_string(),
+                    "ro": "rw",
+                    "name": format!("Item {id}"),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "children":[{
+                "children": items,
+                "name":"Playlist",
+            }]
+        })
+        .to_string()
+    }
+
+    fn get_playback_status(&self) -> String {
+        let (current_id, state, length) = match self.current_item_id {
+            Some((id, state)) => {
+                let state = match state {
+                    PlayState::Playing => "playing",
+                    PlayState::Paused => "paused",
+                };
+                let length = self.item_length(id).unwrap_or_default();
+                (i64::from(id), state, length)
+            }
+            None => (-1, "stopped", Duration::ZERO),
+        };
+        let position_fraction = if length.is_zero() {
+            0.0
+        } else {
+            self.position.as_secs_f64() / length.as_secs_f64()
+        };
+        serde_json::json!({
+            "rate":1,
+            "time":self.position.as_secs(),
+            "repeat": self.is_repeat_one,
+            "loop": self.is_loop_all,
+            "length":length.as_secs(),
+            "random": self.is_random,
+            "apiversion":3,
+            "version":"3.0.20 Vetinari",
+            "currentplid":current_id,
+            "position":position_fraction,
+            "volume":self.volume.unwrap_or(VOLUME_DEFAULT),
+            "state":state,
+            "information":{"category":{"meta":{}}},
+        })
+        .to_string()
+    }
+}
+
+fn toggle(value: &mut bool) {
+    *value = !*value;
+}
+
+/// Arbitrary (deterministic) length for the item
+fn item_length_secs(id: u32) -> u32 {
+    id * 100 + (7 * (id % 3))
+}
+
+enum Value {
+    Absolute(u64),
+    Relative { is_negative: bool, delta: u64 },
+}
+fn parse_signed(val: &str) -> Option<Value> {
+    if let Some(delta) = val.strip_prefix('+') {
+        Some(Value::Relative {
+            is_negative: false,
+            delta: delta.parse().ok()?,
+        })
+    } else if let Some(delta) = val.strip_prefix('-') {
+        Some(Value::Relative {
+            is_negative: true,
+            delta: delta.parse().ok()?,
+        })
+    } else {
+        val.parse().ok().map(Value::Absolute)
+    }
+}
+
+#[expect(clippy::trivially_copy_pass_by_ref)] // signature required by serde
+fn bool_is_false(value: &bool) -> bool {
+    !(*value)
+}
+
+impl std::fmt::Debug for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // force single-line for cleanliness
+        let Self { id, uri } = self;
+        write!(f, "{id}: {uri}")
+    }
+}
+fn serialize_items_vec<S>(items: &Vec<Item>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::ser::SerializeMap as _;
+
+    // NOTE: assumes all IDs are unique (which they *should* be)
+    let len = Some(items.len());
+    let mut seq = serializer.serialize_map(len)?;
+    for item in items {
+        let Item { id, uri } = item;
+        seq.serialize_key(&id)?;
+        seq.serialize_value(&uri)?;
+    }
+    seq.end()
+}
@@ -0,0 +1,108 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! HTTP server for the [`Model`], using [`hyper`]
+
+use super::Model;
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Request, StatusCode,
+};
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex, PoisonError},
+    time::Instant,
+};
+
+/// Fake VLC HTTP server, serving a [`Model`] behind basic auth (blank username)
+///
+/// Playback time advances in real time, checked on each request.
+pub struct Server {
+    model: Model,
+    password: String,
+}
+impl Server {
+    /// Creates a server for the model, requiring the specified password
+    pub fn new(model: Model, password: impl Into<String>) -> Self {
+        Self {
+            model,
+            password: password.into(),
+        }
+    }
+    /// Serves requests on the listener until an error occurs
+    ///
+    /// Requires a [`tokio`] runtime.
+    ///
+    /// # Errors
+    /// Returns an error if the listener cannot be used, or the server fails
+    pub async fn serve(self, listener: std::net::TcpListener) -> Result<(), hyper::Error> {
+        let Self { model, password } = self;
+        let expected_authorization =
+            format!("Basic {}", BASE64_STANDARD.encode(format!(":{password}")));
+        let shared = Arc::new(Shared {
+            expected_authorization,
+            clocked: Mutex::new(Clocked {
+                model,
+                last_instant: Instant::now(),
+            }),
+        });
+
+        let make_service = make_service_fn(move |_| {
+            let shared = Arc::clone(&shared);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let response = shared.respond(&request);
+                    async move { Ok::<_, Infallible>(response) }
+                }))
+            }
+        });
+        hyper::Server::from_tcp(listener)?.serve(make_service).await
+    }
+}
+
+struct Shared {
+    expected_authorization: String,
+    clocked: Mutex<Clocked>,
+}
+struct Clocked {
+    model: Model,
+    last_instant: Instant,
+}
+impl Shared {
+    fn respond(&self, request: &Request<Body>) -> hyper::Response<Body> {
+        let authorized = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .is_some_and(|value| value == self.expected_authorization.as_str());
+        if !authorized {
+            tracing::debug!(uri = %request.uri(), "unauthorized");
+            return hyper::Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .header(header::WWW_AUTHENTICATE, r#"Basic realm="VLC stream""#)
+                .body(Body::empty())
+                .expect("static response is valid");
+        }
+
+        let path_and_query = request
+            .uri()
+            .path_and_query()
+            .map_or("/", hyper::http::uri::PathAndQuery::as_str);
+
+        let response = {
+            let mut clocked = self.clocked.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let elapsed = now.duration_since(clocked.last_instant);
+            clocked.last_instant = now;
+            clocked.model.advance(elapsed);
+            clocked.model.request(path_and_query)
+        };
+        tracing::debug!(path_and_query, ?response, "request");
+
+        let status = StatusCode::from_u16(response.status_code()).expect("status code is valid");
+        hyper::Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, response.content_type())
+            .body(Body::from(response.into_body()))
+            .expect("content type is valid")
+    }
+}
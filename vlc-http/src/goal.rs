@@ -7,16 +7,24 @@ use crate::{
     response, ClientState, Endpoint,
 };
 
+pub use desired_state::{DesiredPlayback, DesiredState, DesiredStateReport, FieldStatus};
+mod desired_state;
 mod playback_mode;
 mod playlist_items;
 
+pub use query_art::QueryArt;
+mod query_art;
+pub use query_browse::QueryBrowse;
+mod query_browse;
 mod query_playback;
 mod query_playlist;
 
 mod builders {
     use super::{
-        playlist_items, query_playback::QueryPlayback, query_playlist::QueryPlaylist, ActionPlan,
-        ActionQuerySetItems, Change, PlanConstructor as _, TargetPlaylistItems,
+        desired_state, playlist_items, query_art::QueryArt, query_browse::QueryBrowse,
+        query_playback::QueryPlayback, query_playlist::QueryPlaylist, ActionDesiredState,
+        ActionPlan, ActionQuerySetItems, Change, DesiredState, PlanConstructor as _,
+        QueryPlaylistSetCost, TargetPlaylistItems,
     };
     use crate::{client_state::PlanBuilder, goal::playback_mode};
 
@@ -29,6 +37,20 @@ mod builders {
         pub fn query_playback(self) -> QueryPlayback {
             QueryPlayback::new((), self.get_sequence())
         }
+        /// Creates a [`Plan`](`super::Plan`) to query the album art for the current item
+        ///
+        /// The art is cached in the [`ClientState`](`crate::ClientState`) by playlist item id, so
+        /// only the playback status is re-queried while the current item is unchanged.
+        pub fn query_art(self) -> QueryArt {
+            QueryArt::new((), self.get_sequence())
+        }
+        /// Creates a [`Plan`](`super::Plan`) to query the directory listing for the `uri` on the
+        /// VLC host (e.g. `file:///path/to/Music`)
+        ///
+        /// Items in the listing have URIs for browsing subdirectories, or adding to the playlist.
+        pub fn query_browse(self, uri: url::Url) -> QueryBrowse {
+            QueryBrowse::new(uri, self.get_sequence())
+        }
         /// Returns an endpoint source for setting the `playlist_items` and querying matched items after
         /// the current playing item.
         ///
@@ -41,6 +63,23 @@ mod builders {
             let inner = playlist_items::Update::new(target, self.get_sequence());
             ActionQuerySetItems(inner)
         }
+        /// Creates a [`Plan`](`super::Plan`) to query the number of commands for
+        /// [`Change::PlaylistSet`] to reach the `target`, without changing the playlist
+        ///
+        /// Outputs a [`PlaylistSetCost`](`super::PlaylistSetCost`) for the current playlist.
+        pub fn query_playlist_set_cost(self, target: TargetPlaylistItems) -> QueryPlaylistSetCost {
+            let inner = playlist_items::Cost::new(target, self.get_sequence());
+            QueryPlaylistSetCost(inner)
+        }
+        /// Creates a [`Plan`](`super::Plan`) to converge on the [`DesiredState`], changing only
+        /// the specified fields
+        ///
+        /// Outputs a [`DesiredStateReport`](`super::DesiredStateReport`) of which fields were
+        /// already satisfied.
+        pub fn apply_desired_state(self, target: DesiredState) -> ActionDesiredState {
+            let inner = desired_state::Converge::new(target, self.get_sequence());
+            ActionDesiredState(inner)
+        }
         /// Creates a [`Plan`](`super::Plan`) to apply the desired change
         pub fn apply(self, change: Change) -> ActionPlan {
             use super::ActionPlanInner as Inner;
@@ -81,7 +120,7 @@ pub enum Change {
     PlaylistSet(TargetPlaylistItems),
 }
 /// Rule for selecting the next playback item in the VLC queue
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
 #[must_use]
 pub struct PlaybackMode {
     repeat: RepeatMode,
@@ -128,7 +167,7 @@ impl PlaybackMode {
 }
 
 /// Rule for repeating items
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
 #[must_use]
 pub enum RepeatMode {
     /// Stop the VLC queue after playing all items
@@ -146,6 +185,7 @@ pub enum RepeatMode {
 pub struct TargetPlaylistItems {
     urls: Vec<url::Url>,
     max_history_count: u16,
+    minimize_edits: bool,
 }
 impl TargetPlaylistItems {
     /// Constructs the default target, no items and removing all history items from the playlist
@@ -165,6 +205,39 @@ impl TargetPlaylistItems {
         self.max_history_count = keep_items;
         self
     }
+    /// Set to choose the match with the fewest commands, rather than the first match
+    ///
+    /// VLC can only append and delete items, so kept items must match a prefix of the `urls`.
+    /// When nothing is playing, items before the match are kept as history (up to the
+    /// [`Self::set_keep_history`] count). A later match may then need fewer deletes.
+    ///
+    /// See [`PlanBuilder::query_playlist_set_cost`](`crate::client_state::PlanBuilder::query_playlist_set_cost`)
+    /// to check the cost before applying.
+    pub fn set_minimize_edits(mut self, minimize_edits: bool) -> Self {
+        self.minimize_edits = minimize_edits;
+        self
+    }
+}
+
+/// Number of playlist commands needed for [`Change::PlaylistSet`], from
+/// [`PlanBuilder::query_playlist_set_cost`](`crate::client_state::PlanBuilder::query_playlist_set_cost`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+#[must_use]
+#[non_exhaustive]
+pub struct PlaylistSetCost {
+    /// Existing items matched to the target
+    pub keep: usize,
+    /// Items to delete (history beyond the limit, and unmatched items)
+    pub delete: usize,
+    /// Target items to add
+    pub add: usize,
+}
+impl PlaylistSetCost {
+    /// Returns the total number of commands (deletes and adds)
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.delete + self.add
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -184,6 +257,18 @@ pub struct ActionPlan(ActionPlanInner);
 #[derive(Clone, Debug)]
 pub struct ActionQuerySetItems(playlist_items::Update);
 
+/// [`Plan`] container for
+/// [`PlanBuilder::query_playlist_set_cost`](`crate::client_state::PlanBuilder::query_playlist_set_cost`)
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct QueryPlaylistSetCost(playlist_items::Cost);
+
+/// [`Plan`] container for
+/// [`PlanBuilder::apply_desired_state`](`crate::client_state::PlanBuilder::apply_desired_state`)
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct ActionDesiredState(desired_state::Converge);
+
 /// Result for one part in reaching a goal
 #[derive(Debug, serde::Serialize, PartialEq, Eq)]
 pub enum Step<T> {
@@ -267,6 +352,18 @@ impl Plan for ActionPlan {
         }
     }
 }
+impl Plan for ActionDesiredState {
+    type Output<'a> = DesiredStateReport;
+    fn next<'a>(&mut self, state: &'a ClientState) -> Result<Step<Self::Output<'a>>, Error> {
+        self.0.next(state)
+    }
+}
+impl Plan for QueryPlaylistSetCost {
+    type Output<'a> = PlaylistSetCost;
+    fn next<'a>(&mut self, state: &'a ClientState) -> Result<Step<Self::Output<'a>>, Error> {
+        self.0.next(state)
+    }
+}
 impl Plan for ActionQuerySetItems {
     type Output<'a> = &'a [response::playlist::Item];
     fn next<'a>(&mut self, state: &'a ClientState) -> Result<Step<Self::Output<'a>>, Error> {
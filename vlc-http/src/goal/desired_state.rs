@@ -0,0 +1,318 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Converges on the full desired VLC state in a single [`Plan`]
+//!
+//! ### Implementation notes
+//!
+//! Fields are converged in order, re-checking from the start after each command:
+//!
+//! 1. Playlist items - forces linear playback while changes are needed (the playlist order must
+//!    match the playback order), see [`playlist_items`](`super::playlist_items`)
+//! 2. Playback mode (repeat/random)
+//! 3. Volume
+//! 4. Playing item - plays the first item matching the URL (enqueued if missing)
+//! 5. Playback state (playing, paused, stopped)
+//!
+//! Fields that were never changed by the plan are reported as
+//! [`FieldStatus::AlreadySatisfied`].
+
+use super::{
+    playback_mode, playlist_items, query_playback::QueryPlayback, query_playlist::QueryPlaylist,
+    response, ClientState, Error, Plan, PlanConstructor, PlaybackMode, Step, TargetPlaylistItems,
+};
+use crate::{
+    client_state::ClientStateSequence, fmt::DebugUrl, response::playback::Mode, Command,
+    VolumePercent,
+};
+use tracing::debug;
+
+/// Full desired state for VLC, for use in
+/// [`PlanBuilder::apply_desired_state`](`crate::client_state::PlanBuilder::apply_desired_state`)
+///
+/// Only the specified fields are changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[must_use]
+pub struct DesiredState {
+    playlist: Option<TargetPlaylistItems>,
+    playback_mode: Option<PlaybackMode>,
+    volume: Option<VolumePercent>,
+    playing_url: Option<url::Url>,
+    playback: Option<DesiredPlayback>,
+}
+impl DesiredState {
+    /// Constructs the default target, with no fields specified (no changes)
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the playlist items
+    pub fn set_playlist(mut self, playlist: TargetPlaylistItems) -> Self {
+        self.playlist = Some(playlist);
+        self
+    }
+    /// Set the item selection mode
+    pub fn set_playback_mode(mut self, playback_mode: PlaybackMode) -> Self {
+        self.playback_mode = Some(playback_mode);
+        self
+    }
+    /// Set the playback volume
+    pub fn set_volume(mut self, volume: VolumePercent) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+    /// Set the current item, by URL
+    ///
+    /// NOTE: Plays the first playlist item with the URL, or enqueues the URL if not found.
+    /// When combined with [`Self::set_playlist`], the URL should be the first target URL (it is
+    /// never enqueued, to avoid conflicting with the playlist target)
+    pub fn set_playing_url(mut self, url: url::Url) -> Self {
+        self.playing_url = Some(url);
+        self
+    }
+    /// Set the playing/paused/stopped state
+    pub fn set_playback(mut self, playback: DesiredPlayback) -> Self {
+        self.playback = Some(playback);
+        self
+    }
+}
+
+/// Desired playback state for [`DesiredState`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DesiredPlayback {
+    /// Current item is playing
+    Playing,
+    /// Current item is paused
+    Paused,
+    /// No item is selected for playback
+    Stopped,
+}
+
+/// Outcome of each field in [`DesiredState`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+#[must_use]
+#[non_exhaustive]
+pub struct DesiredStateReport {
+    /// Outcome of [`DesiredState::set_playlist`]
+    pub playlist: FieldStatus,
+    /// Outcome of [`DesiredState::set_playback_mode`]
+    pub playback_mode: FieldStatus,
+    /// Outcome of [`DesiredState::set_volume`]
+    pub volume: FieldStatus,
+    /// Outcome of [`DesiredState::set_playing_url`]
+    pub playing_url: FieldStatus,
+    /// Outcome of [`DesiredState::set_playback`]
+    pub playback: FieldStatus,
+}
+/// Outcome of a single field in [`DesiredStateReport`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub enum FieldStatus {
+    /// Field was not specified
+    #[default]
+    Unspecified,
+    /// Field already matched, no commands were needed
+    AlreadySatisfied,
+    /// Field was changed by one or more commands
+    Changed,
+    /// Field cannot be reached without conflicting with another field
+    Unsatisfiable,
+}
+impl FieldStatus {
+    fn new<T>(target: Option<&T>) -> Self {
+        if target.is_some() {
+            Self::AlreadySatisfied
+        } else {
+            Self::Unspecified
+        }
+    }
+    fn mark_changed(&mut self) {
+        *self = Self::Changed;
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Converge {
+    playlist: Option<playlist_items::Target<DebugUrl>>,
+    playback_mode: Option<PlaybackMode>,
+    volume: Option<VolumePercent>,
+    playing_url: Option<DebugUrl>,
+    playback: Option<DesiredPlayback>,
+    report: DesiredStateReport,
+    query_playback: QueryPlayback,
+    query_playlist: QueryPlaylist,
+}
+impl Plan for Converge {
+    type Output<'a> = DesiredStateReport;
+
+    fn next(&mut self, state: &ClientState) -> Result<Step<DesiredStateReport>, Error> {
+        let status = match self.query_playback.next(state)? {
+            Step::Done(status) => status,
+            Step::Need(endpoint) => return Ok(Step::Need(endpoint)),
+        };
+
+        let needs_playlist = self.playlist.is_some() || self.playing_url.is_some();
+        let playlist = if needs_playlist {
+            match self.query_playlist.next(state)? {
+                Step::Done(playlist) => playlist,
+                Step::Need(endpoint) => return Ok(Step::Need(endpoint)),
+            }
+        } else {
+            &[]
+        };
+
+        let command = self.next_command(status, playlist);
+        let step = match command {
+            Some(command) => Step::Need(command.into()),
+            None => Step::Done(self.report),
+        };
+        Ok(step)
+    }
+}
+impl Converge {
+    /// Returns the next command for the first unsatisfied field (if any), updating the report
+    fn next_command(
+        &mut self,
+        status: &response::PlaybackStatus,
+        playlist: &[response::playlist::Item],
+    ) -> Option<Command> {
+        let report = &mut self.report;
+
+        if let Some(target) = &self.playlist {
+            let (command, _) = target.next_playlist_command(status, playlist);
+            if let Some(command) = command {
+                report.playlist.mark_changed();
+                // linear playback required before modifying the playlist
+                if let Some(toggle) =
+                    playback_mode::next_toggle(status, playlist_items::LINEAR_PLAYBACK)
+                {
+                    if self.playback_mode.is_some() {
+                        report.playback_mode.mark_changed();
+                    }
+                    return Some(toggle);
+                }
+                return Some(command);
+            }
+        }
+
+        if let Some(target) = self.playback_mode {
+            if let Some(toggle) = playback_mode::next_toggle(status, target) {
+                report.playback_mode.mark_changed();
+                return Some(toggle);
+            }
+        }
+
+        if let Some(target) = self.volume {
+            if status.volume_percent != target.value() {
+                debug!(
+                    volume_percent = status.volume_percent,
+                    target = target.value(),
+                    "want to set volume"
+                );
+                report.volume.mark_changed();
+                return Some(Command::Volume { percent: target });
+            }
+        }
+
+        if let Some(DebugUrl(target)) = &self.playing_url {
+            let playing_item_id = status
+                .information
+                .as_ref()
+                .and_then(|info| info.playlist_item_id);
+            let playing_url = playing_item_id.and_then(|playing_item_id| {
+                playlist
+                    .iter()
+                    .find(|item| item.get_id() == playing_item_id)
+                    .map(response::playlist::Item::get_url)
+            });
+            if playing_url != Some(target) {
+                debug!(?playing_item_id, %target, "want to play url");
+                let command = match playlist.iter().find(|item| item.get_url() == target) {
+                    Some(item) => Some(Command::PlaylistPlay {
+                        item_id: Some(item.get_id()),
+                    }),
+                    None if self.playlist.is_some() => None,
+                    None => Some(Command::PlaylistAdd {
+                        url: target.clone(),
+                    }),
+                };
+                if command.is_some() {
+                    report.playing_url.mark_changed();
+                    return command;
+                }
+                report.playing_url = FieldStatus::Unsatisfiable;
+            }
+        }
+
+        if let Some(target) = self.playback {
+            let command = match (status.mode, target) {
+                (Mode::Playing, DesiredPlayback::Playing)
+                | (Mode::Paused, DesiredPlayback::Paused)
+                | (Mode::Stopped, DesiredPlayback::Stopped) => None,
+                (Mode::Paused, DesiredPlayback::Playing) => Some(Command::PlaybackResume),
+                // start playback before pausing
+                (Mode::Stopped, DesiredPlayback::Playing | DesiredPlayback::Paused) => {
+                    Some(Command::PlaylistPlay { item_id: None })
+                }
+                (Mode::Playing, DesiredPlayback::Paused) => Some(Command::PlaybackPause),
+                (Mode::Playing | Mode::Paused, DesiredPlayback::Stopped) => {
+                    Some(Command::PlaybackStop)
+                }
+            };
+            if let Some(command) = command {
+                debug!(mode = ?status.mode, ?target, "want to change playback");
+                report.playback.mark_changed();
+                return Some(command);
+            }
+        }
+
+        None
+    }
+}
+impl PlanConstructor for Converge {
+    type Args = DesiredState;
+    fn new(target: Self::Args, state: ClientStateSequence) -> Self {
+        let DesiredState {
+            playlist,
+            playback_mode,
+            volume,
+            playing_url,
+            playback,
+        } = target;
+        let report = DesiredStateReport {
+            playlist: FieldStatus::new(playlist.as_ref()),
+            playback_mode: FieldStatus::new(playback_mode.as_ref()),
+            volume: FieldStatus::new(volume.as_ref()),
+            playing_url: FieldStatus::new(playing_url.as_ref()),
+            playback: FieldStatus::new(playback.as_ref()),
+        };
+        Self {
+            playlist: playlist.map(playlist_items::Target::new),
+            playback_mode,
+            volume,
+            playing_url: playing_url.map(DebugUrl),
+            playback,
+            report,
+            query_playback: QueryPlayback::new((), state),
+            query_playlist: QueryPlaylist::new((), state),
+        }
+    }
+}
+impl std::fmt::Debug for Converge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            playlist,
+            playback_mode,
+            volume,
+            playing_url,
+            playback,
+            report: _,
+            query_playback: _,
+            query_playlist: _,
+        } = self;
+        f.debug_struct("Converge")
+            .field("playlist", playlist)
+            .field("playback_mode", playback_mode)
+            .field("volume", volume)
+            .field("playing_url", playing_url)
+            .field("playback", playback)
+            .finish()
+    }
+}
@@ -3,7 +3,7 @@
 use super::{
     query_playback::QueryPlayback, ClientState, Error, Plan, PlanConstructor, PlaybackMode, Step,
 };
-use crate::{client_state::ClientStateSequence, Command};
+use crate::{client_state::ClientStateSequence, response::PlaybackStatus, Command};
 use tracing::{debug, trace};
 
 #[derive(Clone)]
@@ -21,37 +21,45 @@ impl Plan for Set {
             Step::Need(endpoint) => return Ok(Step::Need(endpoint)),
         };
 
-        if status.is_random != self.target.is_random() {
-            debug!(
-                is_random = status.is_random,
-                target = self.target.is_random(),
-                "want to toggle random",
-            );
-            return Ok(Step::Need(Command::ToggleRandom.into()));
-        }
-
-        if status.is_loop_all != self.target.is_loop_all() {
-            debug!(
-                is_loop_all = status.is_loop_all,
-                target = self.target.is_loop_all(),
-                "want to toggle loop-all",
-            );
-            return Ok(Step::Need(Command::ToggleLoopAll.into()));
-        }
-
-        if status.is_repeat_one != self.target.is_repeat_one() {
-            debug!(
-                is_loop_all = status.is_repeat_one,
-                target = self.target.is_repeat_one(),
-                "want to toggle repeat-one",
-            );
-            return Ok(Step::Need(Command::ToggleRepeatOne.into()));
-        }
+        let step = if let Some(command) = next_toggle(status, self.target) {
+            Step::Need(command.into())
+        } else {
+            trace!("no change for playback_mode");
+            Step::Done(())
+        };
+        Ok(step)
+    }
+}
+/// Returns the next toggle [`Command`] to reach the `target` (if any)
+pub(super) fn next_toggle(status: &PlaybackStatus, target: PlaybackMode) -> Option<Command> {
+    if status.is_random != target.is_random() {
+        debug!(
+            is_random = status.is_random,
+            target = target.is_random(),
+            "want to toggle random",
+        );
+        return Some(Command::ToggleRandom);
+    }
 
-        trace!("no change for playback_mode");
+    if status.is_loop_all != target.is_loop_all() {
+        debug!(
+            is_loop_all = status.is_loop_all,
+            target = target.is_loop_all(),
+            "want to toggle loop-all",
+        );
+        return Some(Command::ToggleLoopAll);
+    }
 
-        Ok(Step::Done(()))
+    if status.is_repeat_one != target.is_repeat_one() {
+        debug!(
+            is_loop_all = status.is_repeat_one,
+            target = target.is_repeat_one(),
+            "want to toggle repeat-one",
+        );
+        return Some(Command::ToggleRepeatOne);
     }
+
+    None
 }
 impl PlanConstructor for Set {
     type Args = PlaybackMode;
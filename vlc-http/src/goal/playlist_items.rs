@@ -29,6 +29,10 @@
 //!     - "Remove(3)" comes after "Add(4)" for seamless playback progression to desired items
 //!     - (minor) "Remove (1)" can go anywhere, but place first as a "pre-step" before material
 //!       changes to the playback order
+//! - Match start:
+//!     - By default, the first item matching the first target URL (at/after the playing item)
+//!     - With `minimize_edits` and nothing playing, the match start with the fewest commands
+//!       (see `edit_cost` for the reasoning)
 //!
 //! Examples:
 //!
@@ -65,11 +69,19 @@ use super::{
     playback_mode, query_playback::QueryPlayback, query_playlist::QueryPlaylist, response, Error,
     PlanConstructor, PlaybackMode, Step,
 };
-use crate::{client_state::ClientStateSequence, fmt::DebugUrl, Command, Plan};
+use crate::{
+    client_state::ClientStateSequence, fmt::DebugUrl, goal::PlaylistSetCost, Command, Plan,
+};
 
+mod edit_cost;
 mod insert_match;
 mod next_command;
 
+/// Playback mode required for the playlist order to match the playback order
+pub(super) const LINEAR_PLAYBACK: PlaybackMode = PlaybackMode::new()
+    .set_repeat(crate::goal::RepeatMode::Off)
+    .set_random(false);
+
 /// Sets the specified target and outputs matched items after the current playing item
 ///
 /// Output items will be items from a subset of the original target if playing desired items.
@@ -82,9 +94,10 @@ pub(super) struct Update {
     query_playlist: QueryPlaylist,
 }
 #[derive(Clone, Debug)]
-struct Target<T> {
+pub(super) struct Target<T> {
     pub urls: Vec<T>,
     pub max_history_count: u16,
+    pub minimize_edits: bool,
 }
 
 impl Plan for Update {
@@ -106,53 +119,68 @@ impl Plan for Update {
             Step::Need(endpoint) => return Ok(Step::Need(endpoint)),
         };
 
-        let playing_item_id = playback
-            .information
-            .as_ref()
-            .and_then(|info| info.playlist_item_id);
-
-        let playing_item_index = playing_item_id.and_then(|playing_item_id| {
-            playlist
-                .iter()
-                .position(|item| playing_item_id == item.get_id())
-        });
-
-        let (command, matched_items) = self.target.next_command(playlist, playing_item_index);
-
+        let (command, matched_items) = self.target.next_playlist_command(playback, playlist);
         if let Some(command) = command {
-            let command = match command {
-                next_command::NextCommand::PlaylistAdd(url) => {
-                    Command::PlaylistAdd { url: url.0.clone() }
-                }
-                next_command::NextCommand::PlaylistDelete(item) => Command::PlaylistDelete {
-                    item_id: item.get_id(),
-                },
-            };
             Ok(Step::Need(command.into()))
         } else {
             Ok(Step::Done(matched_items))
         }
     }
 }
+impl Target<DebugUrl> {
+    pub(super) fn new(target: super::TargetPlaylistItems) -> Self {
+        let super::TargetPlaylistItems {
+            urls,
+            max_history_count,
+            minimize_edits,
+        } = target;
+        Target {
+            urls: urls.into_iter().map(DebugUrl).collect(),
+            max_history_count,
+            minimize_edits,
+        }
+    }
+    /// Returns the next command to reach the target (if any), and the matched items after the
+    /// current playing item
+    pub(super) fn next_playlist_command<'a>(
+        &self,
+        playback: &response::PlaybackStatus,
+        playlist: &'a [response::playlist::Item],
+    ) -> (Option<Command>, &'a [response::playlist::Item]) {
+        let playing_item_index = playing_item_index(playback, playlist);
+
+        let (command, matched_items) = self.next_command(playlist, playing_item_index);
+
+        let command = command.map(|command| match command {
+            next_command::NextCommand::PlaylistAdd(url) => {
+                Command::PlaylistAdd { url: url.0.clone() }
+            }
+            next_command::NextCommand::PlaylistDelete(item) => Command::PlaylistDelete {
+                item_id: item.get_id(),
+            },
+        });
+        (command, matched_items)
+    }
+}
+
+fn playing_item_index(
+    playback: &response::PlaybackStatus,
+    playlist: &[response::playlist::Item],
+) -> Option<usize> {
+    let playing_item_id = playback
+        .information
+        .as_ref()
+        .and_then(|info| info.playlist_item_id)?;
+    playlist
+        .iter()
+        .position(|item| playing_item_id == item.get_id())
+}
 
 impl PlanConstructor for Update {
     type Args = super::TargetPlaylistItems;
     fn new(target: Self::Args, state: ClientStateSequence) -> Self {
-        const LINEAR_PLAYBACK: PlaybackMode = PlaybackMode::new()
-            .set_repeat(crate::goal::RepeatMode::Off)
-            .set_random(false);
-        let target = {
-            let super::TargetPlaylistItems {
-                urls,
-                max_history_count,
-            } = target;
-            Target {
-                urls: urls.into_iter().map(DebugUrl).collect(),
-                max_history_count,
-            }
-        };
         Self {
-            target,
+            target: Target::new(target),
             playback_mode: playback_mode::Set::new(LINEAR_PLAYBACK, state),
             query_playback: QueryPlayback::new((), state),
             query_playlist: QueryPlaylist::new((), state),
@@ -190,3 +218,46 @@ impl PlanConstructor for Set {
         Self { update }
     }
 }
+
+/// Queries the number of commands to reach the target, without changing the playlist
+///
+/// NOTE: Excludes commands to set the linear [`PlaybackMode`]
+#[derive(Clone)]
+pub(super) struct Cost {
+    target: Target<crate::fmt::DebugUrl>,
+    query_playback: QueryPlayback,
+    query_playlist: QueryPlaylist,
+}
+impl Plan for Cost {
+    type Output<'a> = PlaylistSetCost;
+    fn next(&mut self, state: &crate::ClientState) -> Result<Step<PlaylistSetCost>, Error> {
+        let playback = match self.query_playback.next(state)? {
+            Step::Done(playback) => playback,
+            Step::Need(endpoint) => return Ok(Step::Need(endpoint)),
+        };
+
+        let playlist = match self.query_playlist.next(state)? {
+            Step::Done(playlist) => playlist,
+            Step::Need(endpoint) => return Ok(Step::Need(endpoint)),
+        };
+
+        let playing_item_index = playing_item_index(playback, playlist);
+        let edit_plan = self.target.edit_plan(playlist, playing_item_index);
+        Ok(Step::Done(edit_plan.cost))
+    }
+}
+impl PlanConstructor for Cost {
+    type Args = super::TargetPlaylistItems;
+    fn new(target: Self::Args, state: ClientStateSequence) -> Self {
+        Self {
+            target: Target::new(target),
+            query_playback: QueryPlayback::new((), state),
+            query_playlist: QueryPlaylist::new((), state),
+        }
+    }
+}
+impl std::fmt::Debug for Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Cost").field(&self.target).finish()
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Minimum edit script for reaching the target, given VLC can only append and delete items
+//!
+//! Without insert or move commands, every kept item must precede every added item. So the kept
+//! items are the longest common subsequence of the queue and a *prefix* of the target. For a
+//! fixed match start, the greedy in-order scan finds that subsequence.
+//!
+//! The only choice is the match start. While playing, items between the playing item and the
+//! match start are deleted, so the first match is the cheapest. When nothing is playing, items
+//! before the match start are history, and history within `max_history_count` is kept for free,
+//! so a later match start may need fewer commands.
+
+use super::Target;
+use crate::goal::PlaylistSetCost;
+
+/// Match start and cost of reaching the target
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct EditPlan {
+    /// Index in the playlist to begin searching for the first target item
+    pub search_start: usize,
+    pub cost: PlaylistSetCost,
+}
+
+impl<T> Target<T> {
+    /// Returns the edit plan for the current playlist, minimizing the cost when
+    /// `minimize_edits` is set (otherwise using the first match, same as the default planner)
+    pub(super) fn edit_plan<U>(&self, playlist: &[U], playing_item_index: Option<usize>) -> EditPlan
+    where
+        U: AsRef<T>,
+        T: Eq,
+    {
+        let max_history_count = usize::from(self.max_history_count);
+        let first = self.urls.first();
+        let match_starts = |from: usize| {
+            (from..playlist.len()).filter(move |&index| Some(playlist[index].as_ref()) == first)
+        };
+
+        if let Some(playing) = playing_item_index {
+            let history = playing.saturating_sub(max_history_count);
+            let search_start = playing;
+            let cost = match match_starts(playing).next() {
+                Some(match_start) => {
+                    let between = match_start.saturating_sub(playing + 1);
+                    self.matched_cost(playlist, match_start)
+                        .with_delete(history + between)
+                }
+                None => PlaylistSetCost {
+                    keep: 0,
+                    delete: history + (playlist.len() - playing - 1),
+                    add: self.urls.len(),
+                },
+            };
+            return EditPlan { search_start, cost };
+        }
+
+        let no_match = EditPlan {
+            search_start: playlist.len(),
+            cost: PlaylistSetCost {
+                keep: 0,
+                delete: playlist.len().saturating_sub(max_history_count),
+                add: self.urls.len(),
+            },
+        };
+        let mut candidates = match_starts(0)
+            .map(|match_start| EditPlan {
+                search_start: match_start,
+                cost: self
+                    .matched_cost(playlist, match_start)
+                    .with_delete(match_start.saturating_sub(max_history_count)),
+            })
+            .chain(std::iter::once(no_match));
+
+        if self.minimize_edits {
+            // earliest on ties, for consistency with the default
+            candidates
+                .reduce(|best, candidate| {
+                    if candidate.cost.total() < best.cost.total() {
+                        candidate
+                    } else {
+                        best
+                    }
+                })
+                .unwrap_or(no_match)
+        } else {
+            candidates.next().unwrap_or(no_match)
+        }
+    }
+    /// Cost for the items at and after `match_start` (the first target item)
+    fn matched_cost<U>(&self, playlist: &[U], match_start: usize) -> PlaylistSetCost
+    where
+        U: AsRef<T>,
+        T: Eq,
+    {
+        let queue = &playlist[match_start..];
+        let keep = matched_prefix_len(&self.urls, queue);
+        PlaylistSetCost {
+            keep,
+            delete: queue.len() - keep,
+            add: self.urls.len() - keep,
+        }
+    }
+}
+
+/// Returns the length of the longest prefix of `target` that is a subsequence of `queue`
+fn matched_prefix_len<T, U>(target: &[T], queue: &[U]) -> usize
+where
+    U: AsRef<T>,
+    T: Eq,
+{
+    let mut target_iter = target.iter().peekable();
+    let mut matched = 0;
+    for item in queue {
+        if target_iter.next_if(|&url| url == item.as_ref()).is_some() {
+            matched += 1;
+        }
+    }
+    matched
+}
+
+impl PlaylistSetCost {
+    fn with_delete(self, extra: usize) -> Self {
+        Self {
+            delete: self.delete + extra,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug)]
+    struct TestItem(&'static str);
+    impl AsRef<&'static str> for TestItem {
+        fn as_ref(&self) -> &&'static str {
+            &self.0
+        }
+    }
+
+    fn uut(
+        minimize_edits: bool,
+        max_history_count: u16,
+        target: &[&'static str],
+        playlist: &[&'static str],
+        playing_item_index: Option<usize>,
+    ) -> (usize, [usize; 3]) {
+        let target = Target {
+            urls: target.to_owned(),
+            max_history_count,
+            minimize_edits,
+        };
+        let playlist: Vec<_> = playlist.iter().copied().map(TestItem).collect();
+        let EditPlan { search_start, cost } = target.edit_plan(&playlist, playing_item_index);
+        let PlaylistSetCost { keep, delete, add } = cost;
+        (search_start, [keep, delete, add])
+    }
+
+    #[test]
+    fn prefix_subsequence() {
+        assert_eq!(matched_prefix_len(&["a", "b", "c"], &[TestItem("a")]), 1);
+        let queue = ["a", "x", "b", "a", "c"].map(TestItem);
+        assert_eq!(matched_prefix_len(&["a", "b", "c"], &queue), 3);
+        // "c" cannot be kept without inserting "b" before it
+        let queue = ["a", "c", "x"].map(TestItem);
+        assert_eq!(matched_prefix_len(&["a", "b", "c"], &queue), 1);
+    }
+
+    #[test]
+    fn default_uses_first_match() {
+        let target = &["a", "b", "c"];
+        let playlist = &["a", "x", "a", "b"];
+        //                              keep delete add
+        assert_eq!(uut(false, 10, target, playlist, None), (0, [2, 2, 1]));
+        assert_eq!(uut(true, 10, target, playlist, None), (2, [2, 0, 1]));
+        // later match no longer free without history
+        assert_eq!(uut(true, 0, target, playlist, None), (0, [2, 2, 1]));
+    }
+
+    #[test]
+    fn no_match() {
+        let target = &["a", "b"];
+        let playlist = &["x", "y", "z"];
+        assert_eq!(uut(true, 1, target, playlist, None), (3, [0, 2, 2]));
+        assert_eq!(uut(true, 1, &[], playlist, None), (3, [0, 2, 0]));
+        assert_eq!(uut(true, 1, target, &[], None), (0, [0, 0, 2]));
+    }
+
+    #[test]
+    fn playing_uses_first_match() {
+        let target = &["a", "b"];
+        let playlist = &["h1", "h2", "p", "x", "a", "a", "b", "y"];
+        for minimize_edits in [false, true] {
+            //                                                   keep delete add
+            assert_eq!(
+                uut(minimize_edits, 1, target, playlist, Some(2)),
+                (2, [2, 1 + 1 + 2, 0])
+            );
+        }
+        assert_eq!(uut(true, 1, target, &["p", "x"], Some(0)), (0, [0, 1, 2]));
+        assert_eq!(uut(true, 1, target, &["a", "x"], Some(0)), (0, [1, 1, 1]));
+    }
+}
@@ -21,6 +21,9 @@ impl<T> Target<T> {
                 "playing_item_index out of bounds of playlist"
             );
             playing
+        } else if self.minimize_edits {
+            // items before the cheapest match start are history
+            self.edit_plan(playlist, None).search_start
         } else {
             0
         };
@@ -39,8 +42,7 @@ impl<T> Target<T> {
                 // none playing, count before match_start (adjusted to global)
                 (None, ItemsBeforeMatchStart::Absolute(absolute)) => absolute,
                 (None, ItemsBeforeMatchStart::Relative(relative)) => {
-                    // NOTE: Relative == Absolute when playing_item_index is None (e.g. trim_offset == 0)
-                    relative.assume_absolute()
+                    relative.with_offset(trim_offset)
                 }
             };
             let max_history_count = usize::from(self.max_history_count);
@@ -139,9 +141,6 @@ mod relative {
         pub fn with_offset(self, offset: usize) -> usize {
             self.0 + offset
         }
-        pub fn assume_absolute(self) -> usize {
-            self.0
-        }
         pub fn assume_relative_context_ok(self) -> usize {
             self.0
         }
@@ -181,9 +180,15 @@ mod tests {
             target: Target {
                 urls: target_urls.to_owned(),
                 max_history_count,
+                minimize_edits: false,
             },
         }
     }
+    fn target_minimize(max_history_count: u16, target_urls: &[&'static str]) -> Uut {
+        let mut uut = target_history(max_history_count, target_urls);
+        uut.target.minimize_edits = true;
+        uut
+    }
     struct Uut {
         target: Target<&'static str>,
     }
@@ -390,6 +395,26 @@ mod tests {
         check!(&uut3 => Some(3), &["_", "M1", "M2", "M3"], (None, MATCH_EMPTY));
     }
 
+    #[test]
+    fn minimize_keeps_history_before_later_match() {
+        let existing = test_items!["M1", "X1", "M1", "M2"];
+        let matched_offset2 = &existing[2..];
+
+        // default deletes between the first match
+        let uut = target_history(3, &["M1", "M2", "M3"]);
+        check!(&uut => &["M1", "X1", "M1", "M2"], delete("X1", MATCH1));
+
+        // later match keeps leading items as history
+        let uut = target_minimize(3, &["M1", "M2", "M3"]);
+        assert_eq!(
+            uut.check(existing),
+            (Some(Cmd::PlaylistAdd(&"M3")), matched_offset2)
+        );
+        // ...unless the history deletes cost the same (first match on ties)
+        let uut = target_minimize(0, &["M1", "M2", "M3"]);
+        check!(&uut => &["M1", "X1", "M1", "M2"], delete("X1", MATCH1));
+    }
+
     #[test]
     fn deletes_first_when_no_match() {
         let uut = target_history(1, &[]);
@@ -0,0 +1,230 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use super::{response, ClientState, Endpoint, Error, Plan, PlanConstructor, Sequence, Step};
+use crate::client_state::ClientStateSequence;
+
+/// Query the album art for the current item
+///
+/// Outputs `None` when there is no current item, and [`Art`](`response::Art`) that is absent when
+/// the current item has no art
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct QueryArt {
+    start_sequence: Sequence,
+}
+impl Plan for QueryArt {
+    type Output<'a> = Option<&'a response::Art>;
+
+    fn next<'a>(&mut self, state: &'a ClientState) -> Result<Step<Self::Output<'a>>, Error> {
+        let playback_status = state.playback_status();
+        let status_updated = playback_status
+            .get_sequence()
+            .is_after(self.start_sequence)?;
+        let playback = match &**playback_status {
+            Some(playback) if status_updated => playback,
+            _ => return Ok(Step::Need(Endpoint::query_status())),
+        };
+        let Some(current_id) = playback
+            .information
+            .as_ref()
+            .and_then(|info| info.playlist_item_id)
+        else {
+            return Ok(Step::Done(None));
+        };
+        let step = match state.art() {
+            Some(art) if art.playlist_item_id() == current_id => Step::Done(Some(art)),
+            _ => Step::Need(Endpoint::query_art(current_id)),
+        };
+        Ok(step)
+    }
+}
+impl PlanConstructor for QueryArt {
+    type Args = ();
+
+    fn new((): Self::Args, state: ClientStateSequence) -> Self {
+        let start_sequence = state.playback_status();
+        Self { start_sequence }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::Response;
+    use std::str::FromStr as _;
+    use test_log::test;
+
+    fn status_for_item(id: i64) -> Response {
+        status_for_item_version(id, "3.0.20 Vetinari")
+    }
+    fn status_for_item_version(id: i64, version: &str) -> Response {
+        let status = serde_json::json!({
+            "apiversion":3,
+            "version":version,
+            "currentplid":id,
+            "state":"playing",
+            "volume":256,
+            "length":100,
+            "time":10,
+            "position":0.1,
+            "rate":1,
+            "loop":false,
+            "random":false,
+            "repeat":false,
+            "information":{"category":{"meta":{}}},
+        });
+        Response::from_str(&status.to_string()).expect("valid response")
+    }
+    fn art_for(endpoint: &Endpoint, data: &[u8]) -> Response {
+        Response::from_endpoint_body(endpoint, "image/png", data).expect("valid art response")
+    }
+
+    #[test]
+    fn caches_by_item() {
+        let mut state = ClientState::new();
+
+        let mut query = state.build_plan().query_art();
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Need(Endpoint(
+          path_and_query: "/requests/status.json",
+        ))
+        "###);
+        state.update(status_for_item(5));
+
+        let Step::Need(endpoint) = query.next(&state).unwrap() else {
+            unreachable!("art not yet cached")
+        };
+        insta::assert_ron_snapshot!(endpoint, @r###"
+        Endpoint(
+          path_and_query: "/art?item=5",
+        )
+        "###);
+        state.update(art_for(&endpoint, b"five"));
+
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Done(Some(Art(
+          playlist_item_id: 5,
+          image: Some(Image(
+            content_type: "image/png",
+            data: [
+              102,
+              105,
+              118,
+              101,
+            ],
+          )),
+        )))
+        "###);
+
+        // same item, only the status is queried
+        let mut query = state.build_plan().query_art();
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Need(Endpoint(
+          path_and_query: "/requests/status.json",
+        ))
+        "###);
+        state.update(status_for_item(5));
+        let output = query.next(&state).unwrap();
+        assert!(matches!(output, Step::Done(Some(art)) if art.data() == Some(b"five")));
+
+        // new item, art is queried again
+        let mut query = state.build_plan().query_art();
+        state.update(status_for_item(6));
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Need(Endpoint(
+          path_and_query: "/art?item=6",
+        ))
+        "###);
+    }
+
+    #[test]
+    fn restart_clears_cache() {
+        let mut state = ClientState::new();
+
+        let mut query = state.build_plan().query_art();
+        state.update(status_for_item(5));
+        let Step::Need(endpoint) = query.next(&state).unwrap() else {
+            unreachable!("art not yet cached")
+        };
+        state.update(art_for(&endpoint, b"five"));
+        assert!(matches!(query.next(&state).unwrap(), Step::Done(Some(_))));
+
+        // restarted VLC reuses ID 5 for a different item
+        let mut query = state.build_plan().query_art();
+        let events = state.update(status_for_item_version(5, "3.0.21 Vetinari"));
+        assert!(events.contains(&crate::client_state::ChangeEvent::VlcRestarted));
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Need(Endpoint(
+          path_and_query: "/art?item=5",
+        ))
+        "###);
+    }
+
+    #[test]
+    fn absent_art_cached() {
+        let mut state = ClientState::new();
+
+        let mut query = state.build_plan().query_art();
+        state.update(status_for_item(7));
+        let Step::Need(endpoint) = query.next(&state).unwrap() else {
+            unreachable!("art not yet cached")
+        };
+        let not_found = Response::from_endpoint_not_found(&endpoint)
+            .unwrap()
+            .expect("art endpoint allows not found");
+        state.update(not_found);
+
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Done(Some(Art(
+          playlist_item_id: 7,
+          image: None,
+        )))
+        "###);
+
+        // same item, absence is cached
+        let mut query = state.build_plan().query_art();
+        state.update(status_for_item(7));
+        let output = query.next(&state).unwrap();
+        assert!(matches!(output, Step::Done(Some(art)) if art.is_absent()));
+    }
+
+    #[test]
+    fn not_found_only_for_art() {
+        let not_found = Response::from_endpoint_not_found(&Endpoint::query_status()).unwrap();
+        assert!(not_found.is_none());
+    }
+
+    #[test]
+    fn no_current_item() {
+        let mut state = ClientState::new();
+
+        let mut query = state.build_plan().query_art();
+        state.update(status_for_item(-1));
+
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @"Done(None)");
+    }
+
+    #[test]
+    fn invalid_art_item() {
+        let endpoint = crate::Command::art_endpoint("not_a_number");
+        let error = Response::from_endpoint_body(&endpoint, "image/png", b"data")
+            .expect_err("non-numeric art item");
+        assert_eq!(
+            error.to_string(),
+            "invalid item id \"not_a_number\" for art endpoint"
+        );
+    }
+
+    #[test]
+    fn bare_art_endpoint() {
+        let endpoint = crate::Command::art_endpoint("");
+        assert_eq!(endpoint.get_path_and_query(), "/art?item=");
+        let bare: Endpoint = serde_json::from_str(r#"{"path_and_query":"/art"}"#).unwrap();
+        let error = Response::from_endpoint_body(&bare, "image/png", b"data")
+            .expect_err("bare art endpoint");
+        assert_eq!(error.to_string(), "missing item id for art endpoint");
+        let error = Response::from_endpoint_not_found(&bare).expect_err("bare art endpoint");
+        assert_eq!(error.to_string(), "missing item id for art endpoint");
+    }
+}
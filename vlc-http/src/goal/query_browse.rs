@@ -0,0 +1,186 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+use super::{response, ClientState, Endpoint, Error, Plan, PlanConstructor, Sequence, Step};
+use crate::client_state::ClientStateSequence;
+
+/// Query the directory listing for a URI on the VLC host
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct QueryBrowse {
+    uri: url::Url,
+    start_sequence: Sequence,
+}
+impl Plan for QueryBrowse {
+    type Output<'a> = &'a response::BrowseListing;
+
+    fn next<'a>(&mut self, state: &'a ClientState) -> Result<Step<Self::Output<'a>>, Error> {
+        let browse_listing = state.browse_listing();
+        let listing_updated = browse_listing
+            .get_sequence()
+            .is_after(self.start_sequence)?;
+        let step = match &**browse_listing {
+            Some(listing) if listing_updated && *listing.get_uri() == self.uri => {
+                Step::Done(listing)
+            }
+            _ => Step::Need(Endpoint::query_browse(&self.uri)),
+        };
+        Ok(step)
+    }
+}
+impl PlanConstructor for QueryBrowse {
+    type Args = url::Url;
+
+    fn new(uri: Self::Args, state: ClientStateSequence) -> Self {
+        let start_sequence = state.browse_listing();
+        Self {
+            uri,
+            start_sequence,
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::Response;
+    use test_log::test;
+
+    const RESPONSE_BROWSE: &str = r#"{"element":[
+        {
+          "type":"dir",
+          "path":"/path/to/Music/..",
+          "name":"..",
+          "access_time":1700000300,
+          "uid":1000,
+          "creation_time":1700000000,
+          "gid":100,
+          "modification_time":1700000000,
+          "mode":16877,
+          "uri":"file:///path/to/Music/..",
+          "size":4096
+        },
+        {
+          "type":"dir",
+          "path":"/path/to/Music/Jimmy Fontanez",
+          "name":"Jimmy Fontanez",
+          "access_time":1700000300,
+          "uid":1000,
+          "creation_time":1700000100,
+          "gid":100,
+          "modification_time":1700000100,
+          "mode":16877,
+          "uri":"file:///path/to/Music/Jimmy%20Fontanez",
+          "size":4096
+        },
+        {
+          "type":"file",
+          "path":"/path/to/Music/Floaters.mp3",
+          "name":"Floaters.mp3",
+          "access_time":1700000300,
+          "uid":1000,
+          "creation_time":1700000200,
+          "gid":100,
+          "modification_time":1700000200,
+          "mode":33188,
+          "uri":"file:///path/to/Music/Floaters.mp3",
+          "size":3145728
+        }
+    ]}"#;
+
+    fn music_uri() -> url::Url {
+        url::Url::parse("file:///path/to/Music").unwrap()
+    }
+
+    #[test]
+    fn query_listing() {
+        let mut state = ClientState::new();
+
+        let mut query = state.build_plan().query_browse(music_uri());
+        let Step::Need(endpoint) = query.next(&state).unwrap() else {
+            unreachable!("listing not yet queried")
+        };
+        insta::assert_ron_snapshot!(endpoint, @r###"
+        Endpoint(
+          path_and_query: "/requests/browse.json?uri=file%3A%2F%2F%2Fpath%2Fto%2FMusic",
+        )
+        "###);
+
+        let response =
+            Response::from_endpoint_body(&endpoint, "application/json", RESPONSE_BROWSE.as_bytes())
+                .unwrap();
+        state.update(response);
+
+        let Step::Done(listing) = query.next(&state).unwrap() else {
+            unreachable!("listing received")
+        };
+        insta::assert_ron_snapshot!(listing, @r###"
+        Listing(
+          uri: "file:///path/to/Music",
+          items: [
+            Item(
+              name: "..",
+              file_type: Directory,
+              size_bytes: Some(4096),
+              uri: "file:///path/to/",
+              path: "/path/to/Music/..",
+              modification_time_secs: Some(1700000000),
+            ),
+            Item(
+              name: "Jimmy Fontanez",
+              file_type: Directory,
+              size_bytes: Some(4096),
+              uri: "file:///path/to/Music/Jimmy%20Fontanez",
+              path: "/path/to/Music/Jimmy Fontanez",
+              modification_time_secs: Some(1700000100),
+            ),
+            Item(
+              name: "Floaters.mp3",
+              file_type: File,
+              size_bytes: Some(3145728),
+              uri: "file:///path/to/Music/Floaters.mp3",
+              path: "/path/to/Music/Floaters.mp3",
+              modification_time_secs: Some(1700000200),
+            ),
+          ],
+        )
+        "###);
+    }
+
+    #[test]
+    fn requery_for_new_plan_or_uri() {
+        let mut state = ClientState::new();
+
+        let endpoint = Endpoint::query_browse(&music_uri());
+        let response =
+            Response::from_endpoint_body(&endpoint, "application/json", br#"{"element":[]}"#)
+                .unwrap();
+        state.update(response);
+
+        // new plan, listing may be stale
+        let mut query = state.build_plan().query_browse(music_uri());
+        assert_eq!(query.next(&state).unwrap(), Step::Need(endpoint.clone()));
+
+        // different URI
+        let mut query = state
+            .assume_cache_valid_for_later_building()
+            .query_browse(url::Url::parse("file:///path/to").unwrap());
+        insta::assert_ron_snapshot!(query.next(&state).unwrap(), @r###"
+        Need(Endpoint(
+          path_and_query: "/requests/browse.json?uri=file%3A%2F%2F%2Fpath%2Fto",
+        ))
+        "###);
+    }
+
+    #[test]
+    fn special_characters_round_trip() {
+        let uri = url::Url::parse("file:///path/to/Music/A%26B%3Fc%3D1%23x").unwrap();
+        let endpoint = Endpoint::query_browse(&uri);
+        insta::assert_ron_snapshot!(endpoint, @r###"
+        Endpoint(
+          path_and_query: "/requests/browse.json?uri=file%3A%2F%2F%2Fpath%2Fto%2FMusic%2FA%2526B%253Fc%253D1%2523x",
+        )
+        "###);
+        assert_eq!(endpoint.get_browse_uri(), Some(uri.to_string()));
+    }
+}
@@ -101,7 +101,13 @@ mod tests {
             date: "",
             track_number: "0",
             track_total: "0",
+            genre: None,
+            album_artist: None,
+            disc_number: None,
+            artwork_url: None,
+            encoded_by: None,
             extra: {},
+            streams: [],
             playlist_item_id: Some(438),
           )),
           is_loop_all: true,
@@ -126,7 +132,13 @@ mod tests {
             date: "",
             track_number: "0",
             track_total: "0",
+            genre: None,
+            album_artist: None,
+            disc_number: None,
+            artwork_url: None,
+            encoded_by: None,
             extra: {},
+            streams: [],
             playlist_item_id: Some(438),
           )),
           is_loop_all: true,
@@ -172,7 +184,13 @@ mod tests {
             date: "",
             track_number: "0",
             track_total: "0",
+            genre: None,
+            album_artist: None,
+            disc_number: None,
+            artwork_url: None,
+            encoded_by: None,
             extra: {},
+            streams: [],
             playlist_item_id: Some(438),
           )),
           is_loop_all: true,
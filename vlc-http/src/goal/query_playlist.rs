@@ -79,6 +79,10 @@ mod tests {
             id: 123,
             name: "Floaters.mp3",
             url: "file:///path/to/Music/Jimmy%20Fontanez/Floaters.mp3",
+            extra: {
+              "ro": "rw",
+              "type": "leaf",
+            },
           ),
         ])
         "###);
@@ -89,6 +93,10 @@ mod tests {
             id: 123,
             name: "Floaters.mp3",
             url: "file:///path/to/Music/Jimmy%20Fontanez/Floaters.mp3",
+            extra: {
+              "ro": "rw",
+              "type": "leaf",
+            },
           ),
         ])
         "###);
@@ -120,6 +128,10 @@ mod tests {
             id: 123,
             name: "Floaters.mp3",
             url: "file:///path/to/Music/Jimmy%20Fontanez/Floaters.mp3",
+            extra: {
+              "ro": "rw",
+              "type": "leaf",
+            },
           ),
         ])
         "###);
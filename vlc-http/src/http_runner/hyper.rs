@@ -0,0 +1,187 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! HTTP runner using [`hyper`] (`async`)
+use crate::{asynchronous::EndpointRequestor, Auth, Endpoint, Response};
+use hyper::{client::HttpConnector, Body, Client, StatusCode};
+
+pub use super::ErrorCategory;
+
+type ResponseStrObserver = dyn FnMut(&str) + Send;
+type ResponseObserver = dyn FnMut(&Response) + Send;
+
+/// Fallback when VLC omits the `Content-Type` header
+const CONTENT_TYPE_UNKNOWN: &str = "application/octet-stream";
+
+/// Fulfills [`Endpoint`]s using the [`hyper`] HTTP client library
+///
+/// Requires a [`tokio`](https://docs.rs/tokio) runtime.
+///
+/// NOTE: Only supports [`Scheme::Http`](`crate::request::Scheme::Http`), requests for
+/// [`Scheme::Https`](`crate::request::Scheme::Https`) fail with [`ErrorCategory::Unsupported`].
+/// Use the [`ureq`](`super::ureq`) runner (with the `tls` feature) for HTTPS.
+pub struct HttpRunner {
+    auth: Auth,
+    client: Client<HttpConnector>,
+    observe_fn_responses_str: Option<Box<ResponseStrObserver>>,
+    observe_fn_responses: Option<Box<ResponseObserver>>,
+}
+impl HttpRunner {
+    /// Creates a default with the specified [`Auth`]
+    pub fn new(auth: Auth) -> Self {
+        Self {
+            auth,
+            client: Client::new(),
+            observe_fn_responses: None,
+            observe_fn_responses_str: None,
+        }
+    }
+    /// Allows custom logging of the raw HTTP response string, called for each endpoint
+    ///
+    /// NOTE: Not called for non-UTF-8 responses (e.g. album art)
+    ///
+    /// NOTE: Replaces the previous "responses str" observer function (if any)
+    pub fn set_observe_responses_str(&mut self, f: Box<ResponseStrObserver>) -> &mut Self {
+        self.observe_fn_responses_str = Some(f);
+        self
+    }
+    /// Allows custom logging of the parsed VLC [`Response`], called for each endpoint
+    ///
+    /// NOTE: Replaces the previous "responses" observer function (if any)
+    pub fn set_observe_responses(&mut self, f: Box<ResponseObserver>) -> &mut Self {
+        self.observe_fn_responses = Some(f);
+        self
+    }
+}
+impl EndpointRequestor for HttpRunner {
+    type Error = Error;
+    async fn request(&mut self, endpoint: Endpoint) -> Result<Response, Self::Error> {
+        let make_error = |kind| Error { kind };
+
+        if self.auth.is_https() {
+            return Err(make_error(ErrorKind::TlsUnsupported));
+        }
+
+        let request = endpoint.with_auth(&self.auth).build_http_request();
+
+        // NOTE: `hyper` 0.14 uses `http` 0.2, so rebuild the request
+        let request = {
+            let (parts, ()) = request.into_parts();
+            let mut builder = hyper::Request::builder()
+                .method(parts.method.as_str())
+                .uri(parts.uri.to_string());
+            for (name, value) in &parts.headers {
+                builder = builder.header(name.as_str(), value.as_bytes());
+            }
+            builder
+                .body(Body::empty())
+                .expect("internally-generated request is valid for http 0.2")
+        };
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(ErrorKind::RequestCall)
+            .map_err(make_error)?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            let not_found = Response::from_endpoint_not_found(&endpoint)
+                .map_err(ErrorKind::ResponseParse)
+                .map_err(make_error)?;
+            if let Some(response) = not_found {
+                if let Some(observe_fn) = &mut self.observe_fn_responses {
+                    observe_fn(&response);
+                }
+                return Ok(response);
+            }
+        }
+        if !status.is_success() {
+            return Err(make_error(ErrorKind::Status(status)));
+        }
+
+        let content_type = response
+            .headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or(CONTENT_TYPE_UNKNOWN)
+            .to_owned();
+        let response_body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(ErrorKind::ResponseBody)
+            .map_err(make_error)?;
+
+        if let Some(observe_fn) = &mut self.observe_fn_responses_str {
+            if let Ok(response_body) = std::str::from_utf8(&response_body) {
+                observe_fn(response_body);
+            }
+        }
+
+        let response = Response::from_endpoint_body(&endpoint, &content_type, &response_body)
+            .map_err(ErrorKind::ResponseParse)
+            .map_err(make_error)?;
+
+        if let Some(observe_fn) = &mut self.observe_fn_responses {
+            observe_fn(&response);
+        }
+
+        Ok(response)
+    }
+}
+
+/// Error calling an HTTP endpoint using `hyper` and parsing the result
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+}
+impl Error {
+    /// Returns the category of the failure, for deciding how to recover
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match &self.kind {
+            ErrorKind::TlsUnsupported => ErrorCategory::Unsupported,
+            ErrorKind::RequestCall(error) => {
+                if error.is_connect() || error.is_closed() || error.is_incomplete_message() {
+                    ErrorCategory::Unreachable
+                } else {
+                    ErrorCategory::BadResponse
+                }
+            }
+            ErrorKind::Status(StatusCode::UNAUTHORIZED) => ErrorCategory::Unauthorized,
+            ErrorKind::Status(_) | ErrorKind::ResponseParse(_) => ErrorCategory::BadResponse,
+            // connection dropped while reading
+            ErrorKind::ResponseBody(_) => ErrorCategory::Unreachable,
+        }
+    }
+}
+#[derive(Debug)]
+enum ErrorKind {
+    TlsUnsupported,
+    RequestCall(hyper::Error),
+    Status(StatusCode),
+    ResponseBody(hyper::Error),
+    ResponseParse(crate::response::ParseError),
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::RequestCall(error) | ErrorKind::ResponseBody(error) => Some(error),
+            ErrorKind::TlsUnsupported | ErrorKind::Status(_) => None,
+            ErrorKind::ResponseParse(error) => Some(error),
+        }
+    }
+}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { kind } = self;
+        match kind {
+            ErrorKind::TlsUnsupported => write!(
+                f,
+                "HTTPS is not supported by the hyper runner, use the ureq runner"
+            ),
+            ErrorKind::RequestCall(_) => write!(f, "request call failed"),
+            ErrorKind::Status(status) => write!(f, "unexpected status {status}"),
+            ErrorKind::ResponseBody(_) => write!(f, "response body failed"),
+            ErrorKind::ResponseParse(_) => write!(f, "invalid response"),
+        }
+    }
+}
@@ -1,12 +1,25 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! HTTP runner using [`ureq`]
-use crate::{sync::EndpointRequestor, Auth, Endpoint, Response};
-use std::str::FromStr as _;
+use crate::{
+    record::{RawEndpointRequestor, RawResponse},
+    sync::EndpointRequestor,
+    Auth, Endpoint, Response,
+};
+use std::io::Read as _;
+
+pub use super::ErrorCategory;
 
 type ResponseStrObserver = dyn FnMut(&str);
 type ResponseObserver = dyn FnMut(&Response);
 
 /// Fulfills [`Endpoint`]s using the [`ureq`] HTTP client library
+///
+/// The scheme, path prefix and extra headers from the [`Auth`] apply to every request.
+///
+/// For saving exchanges to a file, see [`Recorder`](`crate::record::Recorder`).
+///
+/// NOTE: [`Scheme::Https`](`crate::request::Scheme::Https`) requires the `tls` feature, otherwise
+/// each request fails with [`ErrorCategory::Unsupported`].
 pub struct HttpRunner {
     auth: Auth,
     observe_fn_responses_str: Option<Box<ResponseStrObserver>>,
@@ -23,6 +36,8 @@ impl HttpRunner {
     }
     /// Allows custom logging of the raw HTTP response string, called for each endpoint
     ///
+    /// NOTE: Not called for non-UTF-8 responses (e.g. album art)
+    ///
     /// NOTE: Replaces the previous "responses str" observer function (if any)
     pub fn set_observe_responses_str(&mut self, f: Box<ResponseStrObserver>) -> &mut Self {
         self.observe_fn_responses_str = Some(f);
@@ -36,11 +51,15 @@ impl HttpRunner {
         self
     }
 }
-impl EndpointRequestor for HttpRunner {
+impl RawEndpointRequestor for HttpRunner {
     type Error = Error;
-    fn request(&mut self, endpoint: Endpoint) -> Result<Response, Self::Error> {
+    fn request_raw(&mut self, endpoint: &Endpoint) -> Result<RawResponse, Self::Error> {
         let make_error = |kind| Error { kind };
 
+        if self.auth.is_https() && !cfg!(feature = "tls") {
+            return Err(make_error(ErrorKind::TlsUnsupported));
+        }
+
         let request = endpoint.with_auth(&self.auth).build_http_request();
 
         let request = {
@@ -53,18 +72,54 @@ impl EndpointRequestor for HttpRunner {
             .map_err(Box::new)
             .map_err(ErrorKind::RequestCall)
             .map_err(make_error)?;
-        let response_body = response
-            .into_string()
+        let content_type = response.content_type().to_owned();
+        let mut body = vec![];
+        response
+            .into_reader()
+            .read_to_end(&mut body)
             .map_err(ErrorKind::ResponseBody)
             .map_err(make_error)?;
 
         if let Some(observe_fn) = &mut self.observe_fn_responses_str {
-            observe_fn(&response_body);
+            if let Ok(body) = std::str::from_utf8(&body) {
+                observe_fn(body);
+            }
         }
 
-        let response = Response::from_str(&response_body)
-            .map_err(ErrorKind::ResponseParse)
-            .map_err(make_error)?;
+        Ok(RawResponse { content_type, body })
+    }
+    fn error_status(error: &Self::Error) -> Option<u16> {
+        match &error.kind {
+            ErrorKind::RequestCall(error) => match **error {
+                ureq::Error::Status(status, _) => Some(status),
+                ureq::Error::Transport(_) => None,
+            },
+            ErrorKind::TlsUnsupported
+            | ErrorKind::ResponseBody(_)
+            | ErrorKind::ResponseParse(_) => None,
+        }
+    }
+}
+impl EndpointRequestor for HttpRunner {
+    type Error = Error;
+    fn request(&mut self, endpoint: Endpoint) -> Result<Response, Self::Error> {
+        let make_error = |kind| Error { kind };
+
+        let response = match self.request_raw(&endpoint) {
+            Ok(RawResponse { content_type, body }) => {
+                Response::from_endpoint_body(&endpoint, &content_type, &body)
+            }
+            Err(error) if error.is_not_found() => {
+                match Response::from_endpoint_not_found(&endpoint) {
+                    Ok(Some(response)) => Ok(response),
+                    Ok(None) => return Err(error),
+                    Err(parse_error) => Err(parse_error),
+                }
+            }
+            Err(error) => return Err(error),
+        }
+        .map_err(ErrorKind::ResponseParse)
+        .map_err(make_error)?;
 
         if let Some(observe_fn) = &mut self.observe_fn_responses {
             observe_fn(&response);
@@ -79,8 +134,35 @@ impl EndpointRequestor for HttpRunner {
 pub struct Error {
     kind: ErrorKind,
 }
+impl Error {
+    /// Returns the category of the failure, for deciding how to recover
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match &self.kind {
+            ErrorKind::TlsUnsupported => ErrorCategory::Unsupported,
+            ErrorKind::RequestCall(error) => match &**error {
+                ureq::Error::Status(401, _) => ErrorCategory::Unauthorized,
+                ureq::Error::Status(..) => ErrorCategory::BadResponse,
+                ureq::Error::Transport(transport) => match transport.kind() {
+                    ureq::ErrorKind::Dns
+                    | ureq::ErrorKind::ConnectionFailed
+                    | ureq::ErrorKind::Io
+                    | ureq::ErrorKind::ProxyConnect => ErrorCategory::Unreachable,
+                    _ => ErrorCategory::BadResponse,
+                },
+            },
+            // connection dropped while reading
+            ErrorKind::ResponseBody(_) => ErrorCategory::Unreachable,
+            ErrorKind::ResponseParse(_) => ErrorCategory::BadResponse,
+        }
+    }
+    fn is_not_found(&self) -> bool {
+        matches!(&self.kind, ErrorKind::RequestCall(error) if matches!(**error, ureq::Error::Status(404, _)))
+    }
+}
 #[derive(Debug)]
 enum ErrorKind {
+    TlsUnsupported,
     RequestCall(Box<ureq::Error>),
     ResponseBody(std::io::Error),
     ResponseParse(crate::response::ParseError),
@@ -88,6 +170,7 @@ enum ErrorKind {
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.kind {
+            ErrorKind::TlsUnsupported => None,
             ErrorKind::RequestCall(error) => Some(error),
             ErrorKind::ResponseBody(error) => Some(error),
             ErrorKind::ResponseParse(error) => Some(error),
@@ -98,6 +181,7 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Self { kind } = self;
         let description = match kind {
+            ErrorKind::TlsUnsupported => "HTTPS requires the `tls` feature of vlc-http",
             ErrorKind::RequestCall(_) => "request call failed",
             ErrorKind::ResponseBody(_) => "response body failed",
             ErrorKind::ResponseParse(_) => "invalid response",
@@ -105,3 +189,25 @@ impl std::fmt::Display for Error {
         write!(f, "{description}")
     }
 }
+
+#[cfg(all(test, not(feature = "tls")))]
+mod tests {
+    use super::*;
+    use crate::request::{AuthInput, Scheme};
+
+    #[test]
+    fn https_requires_tls() {
+        let mut input = AuthInput::new(String::new(), "localhost".to_owned(), 8080);
+        input.scheme = Scheme::Https;
+        let mut runner = HttpRunner::new(Auth::new(input).expect("valid auth"));
+
+        let error = runner
+            .request_raw(&crate::Command::PlaybackPause.into_endpoint())
+            .expect_err("no TLS support");
+        assert_eq!(error.category(), ErrorCategory::Unsupported);
+        assert_eq!(
+            error.to_string(),
+            "HTTPS requires the `tls` feature of vlc-http"
+        );
+    }
+}
@@ -131,6 +131,16 @@ pub mod response;
 pub use client_state::ClientState;
 pub mod client_state;
 
+pub use poll::PollAdvisor;
+pub mod poll;
+
+pub use rate::CommandLimiter;
+pub mod rate;
+
+pub mod multi;
+
+pub mod record;
+
 // --------------------------------------------------
 // Utilities
 // --------------------------------------------------
@@ -138,10 +148,33 @@ pub mod client_state;
 #[cfg(feature = "clap")]
 pub mod clap;
 
+#[cfg(feature = "fake")]
+pub mod fake;
+
 /// Helpers for specific HTTP client implementations
 pub mod http_runner {
+    #[cfg(feature = "hyper")]
+    pub mod hyper;
     #[cfg(feature = "ureq")]
     pub mod ureq;
+
+    /// Category of a runner error, for deciding how to recover
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum ErrorCategory {
+        /// The runner cannot send requests as configured (e.g. HTTPS without TLS support)
+        Unsupported,
+        /// VLC rejected the credentials (e.g. wrong password)
+        Unauthorized,
+        /// VLC could not be reached, or the connection dropped (e.g. VLC is not running)
+        Unreachable,
+        /// VLC responded, but the response was unexpected or invalid
+        BadResponse,
+    }
 }
 
+pub mod asynchronous;
 pub mod sync;
+
+#[cfg(test)]
+mod test_fixtures;
@@ -0,0 +1,368 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//
+//! Coordinates several VLC instances to play the same queue (e.g. multi-room playback)
+//!
+//! The [`Coordinator`] owns one [`ClientState`] per instance, and fans out each playlist target
+//! and [`Command`] to every instance (except commands for a playlist item ID, since the IDs differ
+//! between instances). Each instance progresses independently: the application
+//! requests the [`Endpoint`] for each instance, and returns the [`Response`] to the coordinator.
+//!
+//! The playlist target is kept, and applied again to instances added later, and to instances
+//! whose playlist changed outside the coordinator (e.g. VLC restarted, or items added/removed).
+//!
+//! ### Drift
+//!
+//! The first instance added is the leader. [`Coordinator::drift`] compares the current item (by
+//! URL, since playlist IDs differ between instances) and position of each instance against the
+//! leader. Positions are extrapolated from the time each status was received (see
+//! [`ClientState::estimate_playback`]), so statuses fetched at different times are comparable.
+
+use crate::{
+    client_state::ChangeEvent,
+    goal::{self, ActionPlan, TargetPlaylistItems},
+    Change, ClientState, Command, Endpoint, Plan as _, Response,
+};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Sans-io controller for several VLC instances, identified by keys of type `K`
+#[derive(Debug)]
+#[must_use]
+pub struct Coordinator<K> {
+    instances: Vec<Instance<K>>,
+    /// Most recent playlist target, for new instances and instances that drift from the target
+    playlist_target: Option<TargetPlaylistItems>,
+}
+#[derive(Debug)]
+struct Instance<K> {
+    key: K,
+    state: ClientState,
+    tasks: VecDeque<Task>,
+}
+#[derive(Debug)]
+enum Task {
+    Command(Command),
+    Query(Endpoint),
+    Playlist(ActionPlan),
+}
+
+/// Difference between an instance and the leader, from [`Coordinator::drift`]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[non_exhaustive]
+pub struct Drift {
+    /// URL of the current item (if known)
+    pub current_url: Option<url::Url>,
+    /// `true` if the current item URL matches the leader
+    pub same_track: bool,
+    /// Position relative to the leader, in seconds (if playing the same track)
+    pub position_delta_secs: Option<i64>,
+}
+
+impl<K> Coordinator<K>
+where
+    K: Clone + PartialEq + std::fmt::Debug,
+{
+    /// Creates a coordinator with no instances
+    pub fn new() -> Self {
+        Self {
+            instances: vec![],
+            playlist_target: None,
+        }
+    }
+    /// Adds an instance with an empty [`ClientState`] (the first instance is the leader)
+    ///
+    /// The current playlist target (if any) is queued for the new instance.
+    ///
+    /// # Errors
+    /// Returns an error if the key is already used
+    pub fn add_instance(&mut self, key: K) -> Result<(), Error<K>> {
+        if self.find(&key).is_some() {
+            return Err(Error {
+                kind: ErrorKind::DuplicateInstance(key),
+            });
+        }
+        let mut instance = Instance {
+            key,
+            state: ClientState::new(),
+            tasks: VecDeque::new(),
+        };
+        if let Some(target) = &self.playlist_target {
+            instance.queue_playlist(target);
+        }
+        self.instances.push(instance);
+        Ok(())
+    }
+    /// Returns the instance keys, starting with the leader
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.instances.iter().map(|instance| &instance.key)
+    }
+    /// Returns the [`ClientState`] for the instance (if found)
+    pub fn client_state(&self, key: &K) -> Option<&ClientState> {
+        self.find(key).map(|index| &self.instances[index].state)
+    }
+    /// Sets the playlist target for all instances, replacing any previous target
+    pub fn set_playlist(&mut self, target: &TargetPlaylistItems) {
+        for instance in &mut self.instances {
+            instance.queue_playlist(target);
+        }
+        self.playlist_target = Some(target.clone());
+    }
+    /// Queues the [`Command`] for all instances, after any pending playlist target
+    ///
+    /// # Errors
+    /// Returns an error for commands specifying a playlist item ID (e.g. play or delete an item),
+    /// since the IDs differ between instances
+    pub fn push_command(&mut self, command: &Command) -> Result<(), Error<K>> {
+        if let Command::PlaylistDelete { .. } | Command::PlaylistPlay { item_id: Some(_) } = command
+        {
+            return Err(Error {
+                kind: ErrorKind::ItemIdCommand(command.clone()),
+            });
+        }
+        for instance in &mut self.instances {
+            instance.tasks.push_back(Task::Command(command.clone()));
+        }
+        Ok(())
+    }
+    /// Queues a playback status query for all instances (e.g. to refresh [`Self::drift`])
+    pub fn push_status_query(&mut self) {
+        for instance in &mut self.instances {
+            instance
+                .tasks
+                .push_back(Task::Query(Endpoint::query_status()));
+        }
+    }
+    /// Returns `true` if any instance has pending commands or playlist changes
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.instances
+            .iter()
+            .any(|instance| !instance.tasks.is_empty())
+    }
+    /// Returns the next [`Endpoint`] to request for the instance (if any)
+    ///
+    /// NOTE: Commands are removed from the queue when returned, so the endpoint must be requested.
+    ///
+    /// # Errors
+    /// Returns an error if the instance is not found, or the playlist [`Plan`](`crate::Plan`)
+    /// fails
+    pub fn next_endpoint(&mut self, key: &K) -> Result<Option<Endpoint>, Error<K>> {
+        self.next_endpoint_at(key, Instant::now())
+    }
+    /// Returns the next [`Endpoint`] to request for the instance (if any), recording commands as
+    /// sent at the instant `sent` (see [`ClientState::notify_endpoint`])
+    ///
+    /// NOTE: Commands are removed from the queue when returned, so the endpoint must be requested.
+    ///
+    /// # Errors
+    /// Returns an error if the instance is not found, or the playlist [`Plan`](`crate::Plan`)
+    /// fails
+    pub fn next_endpoint_at(
+        &mut self,
+        key: &K,
+        sent: Instant,
+    ) -> Result<Option<Endpoint>, Error<K>> {
+        let instance = self.get_mut(key)?;
+        let endpoint = instance.next_endpoint().map_err(|error| Error {
+            kind: ErrorKind::Plan(key.clone(), error),
+        })?;
+        if let Some(endpoint) = &endpoint {
+            instance.state.notify_endpoint(endpoint, sent);
+        }
+        Ok(endpoint)
+    }
+    /// Updates the [`ClientState`] for the instance, returning the detected changes
+    ///
+    /// # Errors
+    /// Returns an error if the instance is not found
+    pub fn update(&mut self, key: &K, response: Response) -> Result<Vec<ChangeEvent>, Error<K>> {
+        self.update_at(key, response, Instant::now())
+    }
+    /// Updates the [`ClientState`] for the instance with the response received at the specified
+    /// instant, returning the detected changes
+    ///
+    /// If the changes show the playlist changed outside the coordinator (VLC restarted, or items
+    /// added/removed while no playlist target is pending), the playlist target is queued again.
+    ///
+    /// # Errors
+    /// Returns an error if the instance is not found
+    pub fn update_at(
+        &mut self,
+        key: &K,
+        response: Response,
+        received: Instant,
+    ) -> Result<Vec<ChangeEvent>, Error<K>> {
+        let target = self.playlist_target.as_ref();
+        let index = self.find(key).ok_or_else(|| Error {
+            kind: ErrorKind::UnknownInstance(key.clone()),
+        })?;
+        let instance = &mut self.instances[index];
+        let events = instance.state.update_at(response, received);
+        if let Some(target) = target {
+            let playlist_changed = events.iter().any(|event| {
+                matches!(
+                    event,
+                    ChangeEvent::VlcRestarted
+                        | ChangeEvent::PlaylistItemsAdded { .. }
+                        | ChangeEvent::PlaylistItemsRemoved { .. }
+                )
+            });
+            if playlist_changed && !instance.is_playlist_pending() {
+                instance.queue_playlist(target);
+            }
+        }
+        Ok(events)
+    }
+    /// Returns the drift of each instance relative to the leader (including the leader)
+    ///
+    /// NOTE: Uses the latest status of each instance, see [`Self::push_status_query`] to refresh
+    #[must_use]
+    pub fn drift(&self) -> Vec<(K, Drift)> {
+        self.drift_at(Instant::now())
+    }
+    /// Returns the drift of each instance relative to the leader (including the leader), with
+    /// positions extrapolated to the instant `now`
+    #[must_use]
+    pub fn drift_at(&self, now: Instant) -> Vec<(K, Drift)> {
+        let Some(leader) = self.instances.first() else {
+            return vec![];
+        };
+        let leader_url = current_url(&leader.state);
+        let leader_position = estimate_position(&leader.state, now);
+        self.instances
+            .iter()
+            .map(|instance| {
+                let current_url = current_url(&instance.state);
+                let same_track = current_url.is_some() && current_url == leader_url;
+                let position = estimate_position(&instance.state, now);
+                let position_delta_secs = match (same_track, position, leader_position) {
+                    (true, Some(position), Some(leader_position)) => {
+                        Some(delta_secs(position, leader_position))
+                    }
+                    _ => None,
+                };
+                let drift = Drift {
+                    current_url: current_url.cloned(),
+                    same_track,
+                    position_delta_secs,
+                };
+                (instance.key.clone(), drift)
+            })
+            .collect()
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        self.instances
+            .iter()
+            .position(|instance| instance.key == *key)
+    }
+    fn get_mut(&mut self, key: &K) -> Result<&mut Instance<K>, Error<K>> {
+        match self.find(key) {
+            Some(index) => Ok(&mut self.instances[index]),
+            None => Err(Error {
+                kind: ErrorKind::UnknownInstance(key.clone()),
+            }),
+        }
+    }
+}
+impl<K> Instance<K> {
+    /// Queues the playlist target, replacing any pending playlist target
+    fn queue_playlist(&mut self, target: &TargetPlaylistItems) {
+        self.tasks.retain(|task| !matches!(task, Task::Playlist(_)));
+        let plan = self
+            .state
+            .build_plan()
+            .apply(Change::PlaylistSet(target.clone()));
+        self.tasks.push_back(Task::Playlist(plan));
+    }
+    fn is_playlist_pending(&self) -> bool {
+        self.tasks
+            .iter()
+            .any(|task| matches!(task, Task::Playlist(_)))
+    }
+    fn next_endpoint(&mut self) -> Result<Option<Endpoint>, goal::Error> {
+        while let Some(task) = self.tasks.pop_front() {
+            match task {
+                Task::Command(command) => return Ok(Some(command.into())),
+                Task::Query(endpoint) => return Ok(Some(endpoint)),
+                Task::Playlist(mut plan) => match plan.next(&self.state)? {
+                    goal::Step::Need(endpoint) => {
+                        self.tasks.push_front(Task::Playlist(plan));
+                        return Ok(Some(endpoint));
+                    }
+                    goal::Step::Done(()) => {}
+                },
+            }
+        }
+        Ok(None)
+    }
+}
+impl<K> Default for Coordinator<K>
+where
+    K: Clone + PartialEq + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn current_url(state: &ClientState) -> Option<&url::Url> {
+    let status = state.playback_status().as_ref()?;
+    let item_id = status.information.as_ref()?.playlist_item_id?;
+    state
+        .playlist_info()
+        .items
+        .iter()
+        .find(|item| item.get_id() == item_id)
+        .map(crate::response::playlist::Item::get_url)
+}
+fn estimate_position(state: &ClientState, now: Instant) -> Option<Duration> {
+    state
+        .estimate_playback(now)
+        .map(|estimate| estimate.get_position())
+}
+/// Returns the difference in seconds (rounded to nearest)
+fn delta_secs(position: Duration, reference: Duration) -> i64 {
+    let millis = |duration: Duration| i128::try_from(duration.as_millis()).unwrap_or(i128::MAX);
+    let delta_millis = millis(position).saturating_sub(millis(reference));
+    let delta_secs = delta_millis.saturating_add(500).div_euclid(1000);
+    i64::try_from(delta_secs).unwrap_or(if delta_secs < 0 { i64::MIN } else { i64::MAX })
+}
+
+/// Error for a [`Coordinator`] instance
+#[derive(Debug)]
+pub struct Error<K> {
+    kind: ErrorKind<K>,
+}
+#[derive(Debug)]
+enum ErrorKind<K> {
+    DuplicateInstance(K),
+    UnknownInstance(K),
+    ItemIdCommand(Command),
+    Plan(K, goal::Error),
+}
+impl<K: std::fmt::Debug> std::fmt::Display for Error<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::DuplicateInstance(key) => write!(f, "duplicate instance {key:?}"),
+            ErrorKind::UnknownInstance(key) => write!(f, "unknown instance {key:?}"),
+            ErrorKind::ItemIdCommand(command) => write!(
+                f,
+                "playlist item IDs differ between instances, cannot send {command:?} to all"
+            ),
+            ErrorKind::Plan(key, _) => write!(f, "playlist plan failed for instance {key:?}"),
+        }
+    }
+}
+impl<K: std::fmt::Debug> std::error::Error for Error<K> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::DuplicateInstance(_)
+            | ErrorKind::UnknownInstance(_)
+            | ErrorKind::ItemIdCommand(_) => None,
+            ErrorKind::Plan(_, error) => Some(error),
+        }
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//
+//! Advises when to re-query VLC, based on the [`ClientState`] and recent [`Command`]s
+//!
+//! The caller supplies the current time for every call, so the advisor performs no IO and can be
+//! driven by a fake clock in tests.
+//!
+//! ### Rules
+//!
+//! - **Fill** - fetch the playlist and playback status if never received
+//! - **Fetch after seek** - fetch the status shortly after [`Command::SeekNext`] or
+//!   [`Command::SeekPrevious`], if the item did not change yet
+//! - **Fetch after volume** - fetch the status shortly after [`Command::Volume`] or
+//!   [`Command::VolumeRelative`], if the volume did not change yet
+//! - **Fetch after track end** - fetch the status shortly after the current item is predicted to
+//!   end (only while playing)
+//!
+//! The most-immediate need of all rules is returned.
+
+use crate::{
+    client_state::Sequence, request::CommandEffect, response::playback::Mode,
+    response::PlaybackStatus, ClientState, Command, Endpoint,
+};
+use std::time::{Duration, Instant};
+
+/// Decides the next [`Endpoint`] to fetch, and when
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct PollAdvisor {
+    /// Sequence of the last observed playback status, and when it was observed
+    playback_seen: Option<(Sequence, Instant)>,
+    after_seek: FetchAfter<(u64, Option<u64>)>,
+    after_volume: FetchAfter<u16>,
+    track_end: Option<TrackEnd>,
+}
+
+/// Next [`Endpoint`] to fetch, from [`PollAdvisor::next_poll`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct NextPoll {
+    endpoint: Endpoint,
+    deadline: Instant,
+}
+impl NextPoll {
+    /// Returns the endpoint to fetch
+    pub fn get_endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+    /// Returns the endpoint to fetch (consuming the poll)
+    pub fn into_endpoint(self) -> Endpoint {
+        self.endpoint
+    }
+    /// Returns the time to fetch the endpoint
+    #[must_use]
+    pub fn get_deadline(&self) -> Instant {
+        self.deadline
+    }
+    /// Returns the delay from `now` until the deadline (zero if the deadline passed)
+    #[must_use]
+    pub fn delay_from(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+}
+
+impl PollAdvisor {
+    /// Delay after a command before confirming the change
+    const COMMAND_DELAY: Duration = Duration::from_millis(50);
+    /// Delay after the predicted end of the current item
+    const TRACK_END_DELAY: Duration = Duration::from_millis(500);
+
+    /// Creates an advisor with no knowledge of prior commands
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Records the [`Command`] sent to VLC at time `now`
+    pub fn notify_command(&mut self, command: &Command, now: Instant) {
+        match command.effect() {
+            CommandEffect::ChangeItem => self.after_seek.notify_command(now),
+            CommandEffect::Volume => {
+                // volume cannot go below zero, no change to confirm
+                let is_noop = matches!(command, Command::VolumeRelative { percent_delta }
+                    if percent_delta.value() < 0 && self.after_volume.info() == Some(&0));
+                if !is_noop {
+                    self.after_volume.notify_command(now);
+                }
+            }
+            CommandEffect::Timing | CommandEffect::Other => {}
+        }
+    }
+    /// Returns the next [`Endpoint`] to fetch (if any), observing any new responses in the
+    /// [`ClientState`]
+    ///
+    /// NOTE: Responses are timestamped with the `now` of the first call observing them, so call this
+    /// function after each [`ClientState::update`] for accurate predictions.
+    pub fn next_poll(&mut self, state: &ClientState, now: Instant) -> Option<NextPoll> {
+        self.observe(state, now);
+
+        let fill_playlist = state
+            .playlist_info()
+            .is_initial()
+            .then(|| (now, Endpoint::query_playlist()));
+        let fill_playback = state
+            .playback_status()
+            .is_none()
+            .then(|| (now, Endpoint::query_status()));
+        let status_received = self.playback_seen.map(|(_, received)| received);
+        let after_seek = self
+            .after_seek
+            .get_deadline(status_received, Self::COMMAND_DELAY)
+            .map(|deadline| (deadline, Endpoint::query_status()));
+        let after_volume = self
+            .after_volume
+            .get_deadline(status_received, Self::COMMAND_DELAY)
+            .map(|deadline| (deadline, Endpoint::query_status()));
+        let track_end = self
+            .track_end
+            .and_then(|track_end| track_end.get_deadline(Self::TRACK_END_DELAY))
+            .map(|deadline| (deadline, Endpoint::query_status()));
+
+        // first (in rule order) of the earliest deadlines
+        [
+            fill_playlist,
+            fill_playback,
+            after_seek,
+            after_volume,
+            track_end,
+        ]
+        .into_iter()
+        .flatten()
+        .min_by_key(|(deadline, _)| *deadline)
+        .map(|(deadline, endpoint)| NextPoll { endpoint, deadline })
+    }
+    fn observe(&mut self, state: &ClientState, now: Instant) {
+        let playback_status = state.playback_status();
+        let sequence = playback_status.get_sequence();
+        let is_new = self
+            .playback_seen
+            .is_none_or(|(seen_sequence, _)| seen_sequence != sequence);
+        if !is_new {
+            return;
+        }
+        self.playback_seen = Some((sequence, now));
+
+        if let Some(status) = &**playback_status {
+            let item_id = status
+                .information
+                .as_ref()
+                .and_then(|info| info.playlist_item_id);
+            self.after_seek
+                .notify_info((status.duration_secs, item_id), now);
+            self.after_volume.notify_info(status.volume_percent, now);
+            self.track_end = TrackEnd::new(status, now);
+        }
+    }
+}
+
+/// Fetches after a triggering command, until the info changes or a fetch confirms no change
+#[derive(Clone, Debug)]
+struct FetchAfter<T> {
+    /// Latest info, and the time it changed
+    info_time: Option<(T, Instant)>,
+    command_time: Option<Instant>,
+}
+impl<T> Default for FetchAfter<T> {
+    fn default() -> Self {
+        Self {
+            info_time: None,
+            command_time: None,
+        }
+    }
+}
+impl<T: PartialEq> FetchAfter<T> {
+    fn notify_info(&mut self, info: T, now: Instant) {
+        self.info_time = match self.info_time.take() {
+            Some((prev_info, prev_time)) if prev_info == info => Some((prev_info, prev_time)),
+            _ => Some((info, now)),
+        };
+    }
+    fn notify_command(&mut self, now: Instant) {
+        self.command_time = Some(now);
+    }
+    fn info(&self) -> Option<&T> {
+        self.info_time.as_ref().map(|(info, _)| info)
+    }
+    fn get_deadline(&self, status_received: Option<Instant>, delay: Duration) -> Option<Instant> {
+        let command_time = self.command_time?;
+        let deadline = command_time + delay;
+        let changed_after_command = self
+            .info_time
+            .as_ref()
+            .is_some_and(|(_, change_time)| *change_time > command_time);
+        // NOTE: stop after one fetch past the deadline, in case the command changed nothing
+        let confirmed_after_deadline = status_received.is_some_and(|received| received >= deadline);
+        if changed_after_command || confirmed_after_deadline {
+            None
+        } else {
+            Some(deadline)
+        }
+    }
+}
+
+/// Predicted end of the current item
+#[derive(Clone, Copy, Debug)]
+struct TrackEnd {
+    remaining: Duration,
+    received: Instant,
+}
+impl TrackEnd {
+    fn new(status: &PlaybackStatus, received: Instant) -> Option<Self> {
+        if status.mode != Mode::Playing || status.duration_secs == 0 {
+            return None;
+        }
+        let remaining_secs = status.duration_secs.checked_sub(status.position_secs)?;
+        let remaining = if status.rate_ratio > 0.0 {
+            // NOTE: No risk of precision loss for human-lifespan appropriate durations
+            #[expect(clippy::cast_precision_loss)]
+            let remaining_millis = (remaining_secs * 1000) as f64;
+            let adjusted = (remaining_millis / status.rate_ratio).ceil();
+            // NOTE: checked `rate_ratio` is positive, and the delay is padded, so precision is not
+            // critical
+            #[expect(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let adjusted = adjusted as u64;
+            Duration::from_millis(adjusted)
+        } else {
+            Duration::from_secs(remaining_secs)
+        };
+        Some(Self {
+            remaining,
+            received,
+        })
+    }
+    fn get_deadline(self, delay: Duration) -> Option<Instant> {
+        self.received.checked_add(self.remaining + delay)
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_fixtures::{status, Clock},
+        Response, VolumePercent, VolumePercentDelta,
+    };
+    use std::str::FromStr as _;
+
+    fn playlist() -> Response {
+        Response::from_str(
+            r#"{"name":"","id":"0","type":"node","children":[
+              {"name":"Playlist","id":"1","type":"node","children":[]},
+              {"name":"Media Library","id":"2","type":"node","children":[]}
+            ]}"#,
+        )
+        .unwrap()
+    }
+    fn poll(advisor: &mut PollAdvisor, state: &ClientState, now: Instant) -> Option<(u64, String)> {
+        advisor.next_poll(state, now).map(|next| {
+            let delay = next.delay_from(now).as_millis();
+            let delay = u64::try_from(delay).unwrap();
+            (delay, next.into_endpoint().get_path_and_query().to_owned())
+        })
+    }
+    fn filled(clock: &Clock) -> (PollAdvisor, ClientState) {
+        let mut advisor = PollAdvisor::new();
+        let mut state = ClientState::new();
+        state.update(playlist());
+        state.update(status("paused", 100, 20, 1.0, 256));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(0)), None);
+        (advisor, state)
+    }
+
+    const STATUS: &str = "/requests/status.json";
+
+    #[test]
+    fn fills() {
+        let clock = Clock::new();
+        let mut advisor = PollAdvisor::new();
+        let mut state = ClientState::new();
+
+        let now = clock.at_millis(0);
+        assert_eq!(
+            poll(&mut advisor, &state, now),
+            Some((0, "/requests/playlist.json".to_owned()))
+        );
+        state.update(playlist());
+        assert_eq!(
+            poll(&mut advisor, &state, now),
+            Some((0, STATUS.to_owned()))
+        );
+        state.update(status("stopped", 0, 0, 1.0, 256));
+        assert_eq!(poll(&mut advisor, &state, now), None);
+    }
+
+    #[test]
+    fn fetch_after_volume() {
+        let clock = Clock::new();
+        let (mut advisor, mut state) = filled(&clock);
+
+        let command = Command::Volume {
+            percent: VolumePercent::new(50).unwrap(),
+        };
+        advisor.notify_command(&command, clock.at_millis(10));
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(20)),
+            Some((40, STATUS.to_owned()))
+        );
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(100)),
+            Some((0, STATUS.to_owned()))
+        );
+
+        // volume changed
+        state.update(status("paused", 100, 20, 1.0, 128));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(30)), None);
+    }
+
+    #[test]
+    fn fetch_after_volume_unchanged() {
+        let clock = Clock::new();
+        let (mut advisor, mut state) = filled(&clock);
+
+        let command = Command::Volume {
+            percent: VolumePercent::new(100).unwrap(),
+        };
+        advisor.notify_command(&command, clock.at_millis(10));
+
+        // too early to confirm
+        state.update(status("paused", 100, 20, 1.0, 256));
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(20)),
+            Some((40, STATUS.to_owned()))
+        );
+
+        // confirmed no change
+        state.update(status("paused", 100, 20, 1.0, 256));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(60)), None);
+    }
+
+    #[test]
+    fn fetch_after_volume_ignores_below_zero() {
+        let clock = Clock::new();
+        let (mut advisor, mut state) = filled(&clock);
+        state.update(status("paused", 100, 20, 1.0, 0));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(0)), None);
+
+        let command = Command::VolumeRelative {
+            percent_delta: VolumePercentDelta::new(-5).unwrap(),
+        };
+        advisor.notify_command(&command, clock.at_millis(10));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(20)), None);
+    }
+
+    #[test]
+    fn fetch_after_seek() {
+        let clock = Clock::new();
+        let (mut advisor, mut state) = filled(&clock);
+
+        // ignores other commands
+        advisor.notify_command(&Command::PlaybackPause, clock.at_millis(10));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(10)), None);
+
+        advisor.notify_command(&Command::SeekNext, clock.at_millis(10));
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(10)),
+            Some((50, STATUS.to_owned()))
+        );
+
+        // duration changed (new item)
+        state.update(status("paused", 200, 0, 1.0, 256));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(30)), None);
+    }
+
+    #[test]
+    fn fetch_after_track_end() {
+        let clock = Clock::new();
+        let (mut advisor, mut state) = filled(&clock);
+
+        state.update(status("playing", 100, 20, 1.0, 256));
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(1_000)),
+            Some((80_500, STATUS.to_owned()))
+        );
+        // deadline is fixed from the receive time
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(11_000)),
+            Some((70_500, STATUS.to_owned()))
+        );
+
+        // unknown duration
+        state.update(status("playing", 0, 20, 1.0, 256));
+        assert_eq!(poll(&mut advisor, &state, clock.at_millis(12_000)), None);
+    }
+
+    #[test]
+    fn fetch_after_track_end_rate() {
+        for (rate, expected_millis) in [(2.0, 30_500), (0.5, 120_500), (0.0, 60_500)] {
+            let clock = Clock::new();
+            let (mut advisor, mut state) = filled(&clock);
+
+            state.update(status("playing", 100, 40, rate, 256));
+            assert_eq!(
+                poll(&mut advisor, &state, clock.at_millis(0)),
+                Some((expected_millis, STATUS.to_owned())),
+                "rate {rate}"
+            );
+        }
+    }
+
+    #[test]
+    fn picks_earliest() {
+        let clock = Clock::new();
+        let (mut advisor, mut state) = filled(&clock);
+
+        state.update(status("playing", 100, 99, 1.0, 256));
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(0)),
+            Some((1_500, STATUS.to_owned()))
+        );
+
+        advisor.notify_command(&Command::SeekPrevious, clock.at_millis(0));
+        assert_eq!(
+            poll(&mut advisor, &state, clock.at_millis(0)),
+            Some((50, STATUS.to_owned()))
+        );
+    }
+}
@@ -0,0 +1,573 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//
+//! Limits the rate of [`Command`]s sent to VLC, coalescing pending commands where possible
+//!
+//! This protects VLC from misbehaving (or malicious) clients, without dropping the intent of
+//! rapid user input (e.g. holding a volume-up button).
+//!
+//! The caller supplies the current time for every call, and each decision is returned as data
+//! ([`PushDecision`], [`PopDecision`]) for deterministic testing.
+//!
+//! ### Coalescing
+//!
+//! Each pushed command is compared against the most recent pending command:
+//!
+//! - consecutive [`Command::Volume`] / [`Command::VolumeRelative`] merge into one
+//!   [`Command::Volume`]
+//! - consecutive [`Command::SeekTo`] / [`Command::SeekRelative`] merge into one
+//!   [`Command::SeekTo`]
+//! - consecutive identical toggles (e.g. [`Command::ToggleRandom`]) cancel each other
+//!
+//! Merging two relative commands requires a trusted base (volume or position) from the
+//! [`ClientState`], otherwise the deltas are summed into one relative command. The base is trusted
+//! only if the playback estimate is not stale, and the status was received after the last relative
+//! command sent (so the base includes its effect).
+
+use crate::{command::SecondsDelta, ClientState, Command, VolumePercent, VolumePercentDelta};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Rate limiter and coalescer for [`Command`]s
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct CommandLimiter {
+    bucket: TokenBucket,
+    pending: VecDeque<Command>,
+    max_pending: usize,
+    /// Time the last relative command was sent (popped)
+    relative_sent: Option<Instant>,
+}
+
+/// Outcome of [`CommandLimiter::push`]
+#[derive(Clone, Debug, PartialEq)]
+#[must_use]
+#[non_exhaustive]
+pub enum PushDecision {
+    /// Command added to the end of the queue
+    Queued,
+    /// Command merged with the most recent pending command
+    Coalesced {
+        /// Replacement for the most recent pending command
+        merged: Command,
+    },
+    /// Command cancelled the most recent pending command (both removed)
+    Cancelled,
+    /// Command dropped, the queue is full
+    Rejected,
+}
+
+/// Outcome of [`CommandLimiter::pop`]
+#[derive(Clone, Debug, PartialEq)]
+#[must_use]
+pub enum PopDecision {
+    /// Command is allowed to be sent now
+    Send(Command),
+    /// Rate limit reached, retry at the specified time
+    Wait {
+        /// Time when the next token is available
+        until: Instant,
+    },
+    /// No commands are pending
+    Empty,
+}
+
+impl CommandLimiter {
+    /// Default limit on the number of pending commands
+    pub const DEFAULT_MAX_PENDING: usize = 16;
+
+    /// Creates a limiter allowing bursts of `capacity` commands, refilling one token per
+    /// `refill_interval`
+    pub fn new(capacity: u32, refill_interval: Duration) -> Self {
+        Self {
+            bucket: TokenBucket::new(capacity, refill_interval),
+            pending: VecDeque::new(),
+            max_pending: Self::DEFAULT_MAX_PENDING,
+            relative_sent: None,
+        }
+    }
+    /// Sets the limit on the number of pending commands
+    pub fn set_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+    /// Returns the number of pending commands
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+    /// Adds the command to the queue, or coalesces with the most recent pending command
+    ///
+    /// The latest playback status in `state` (if trusted at time `now`) is the base for merging
+    /// relative commands.
+    pub fn push(&mut self, command: Command, state: &ClientState, now: Instant) -> PushDecision {
+        let base = self.trusted_base(state, now);
+        if let Some(previous) = self.pending.back_mut() {
+            match coalesce(previous, &command, base) {
+                Some(Coalesce::Merged(merged)) => {
+                    *previous = merged.clone();
+                    return PushDecision::Coalesced { merged };
+                }
+                Some(Coalesce::Cancelled) => {
+                    self.pending.pop_back();
+                    return PushDecision::Cancelled;
+                }
+                None => {}
+            }
+        }
+        if self.pending.len() >= self.max_pending {
+            PushDecision::Rejected
+        } else {
+            self.pending.push_back(command);
+            PushDecision::Queued
+        }
+    }
+    /// Returns the next command to send (if allowed by the rate limit at time `now`)
+    pub fn pop(&mut self, now: Instant) -> PopDecision {
+        if self.pending.is_empty() {
+            return PopDecision::Empty;
+        }
+        match self.bucket.try_take(now) {
+            Ok(()) => match self.pending.pop_front() {
+                Some(command) => {
+                    if let Command::VolumeRelative { .. } | Command::SeekRelative { .. } = command {
+                        self.relative_sent = Some(now);
+                    }
+                    PopDecision::Send(command)
+                }
+                None => PopDecision::Empty,
+            },
+            Err(until) => PopDecision::Wait { until },
+        }
+    }
+    fn trusted_base(&self, state: &ClientState, now: Instant) -> Option<Base> {
+        let status = state.playback_status().as_ref()?;
+        let estimate = state.estimate_playback(now)?;
+        let relative_reflected = self
+            .relative_sent
+            .is_none_or(|sent| state.is_status_settled_after(sent));
+        (!estimate.is_stale() && relative_reflected).then(|| Base {
+            volume_percent: status.volume_percent,
+            position_secs: u32::try_from(estimate.get_position().as_secs()).unwrap_or(u32::MAX),
+        })
+    }
+}
+
+/// Current volume and position, for merging relative commands
+#[derive(Clone, Copy)]
+struct Base {
+    volume_percent: u16,
+    position_secs: u32,
+}
+
+enum Coalesce {
+    Merged(Command),
+    Cancelled,
+}
+fn coalesce(previous: &Command, command: &Command, base: Option<Base>) -> Option<Coalesce> {
+    let merged = match (previous, command) {
+        (Command::ToggleRandom, Command::ToggleRandom)
+        | (Command::ToggleRepeatOne, Command::ToggleRepeatOne)
+        | (Command::ToggleLoopAll, Command::ToggleLoopAll) => return Some(Coalesce::Cancelled),
+        // absolute replaces any previous
+        (Command::Volume { .. } | Command::VolumeRelative { .. }, Command::Volume { .. })
+        | (Command::SeekTo { .. } | Command::SeekRelative { .. }, Command::SeekTo { .. }) => {
+            command.clone()
+        }
+        // relative adjusts absolute
+        (Command::Volume { percent }, Command::VolumeRelative { percent_delta }) => {
+            Command::Volume {
+                percent: offset_volume(percent.value(), &[*percent_delta]),
+            }
+        }
+        (Command::SeekTo { seconds }, Command::SeekRelative { seconds_delta }) => Command::SeekTo {
+            seconds: offset_seconds(*seconds, &[*seconds_delta]),
+        },
+        // relative pairs need a base
+        (
+            Command::VolumeRelative {
+                percent_delta: first,
+            },
+            Command::VolumeRelative {
+                percent_delta: second,
+            },
+        ) => match base {
+            Some(base) => Command::Volume {
+                percent: offset_volume(base.volume_percent, &[*first, *second]),
+            },
+            None => Command::VolumeRelative {
+                percent_delta: sum_volume_deltas(*first, *second),
+            },
+        },
+        (
+            Command::SeekRelative {
+                seconds_delta: first,
+            },
+            Command::SeekRelative {
+                seconds_delta: second,
+            },
+        ) => match base {
+            Some(base) => Command::SeekTo {
+                seconds: offset_seconds(base.position_secs, &[*first, *second]),
+            },
+            None => Command::SeekRelative {
+                seconds_delta: SecondsDelta(first.0.saturating_add(second.0)),
+            },
+        },
+        _ => return None,
+    };
+    Some(Coalesce::Merged(merged))
+}
+fn offset_volume(base: u16, deltas: &[VolumePercentDelta]) -> VolumePercent {
+    let target = deltas
+        .iter()
+        .fold(i32::from(base), |acc, delta| acc + i32::from(delta.value()));
+    let max = VolumePercent::MAX.value();
+    let target = u16::try_from(target.clamp(0, max.into())).unwrap_or(max);
+    VolumePercent::new(target).unwrap_or(VolumePercent::MAX)
+}
+fn sum_volume_deltas(first: VolumePercentDelta, second: VolumePercentDelta) -> VolumePercentDelta {
+    let sum = first.value().saturating_add(second.value());
+    if sum < 0 {
+        VolumePercentDelta::new(sum).unwrap_or(VolumePercentDelta::MIN)
+    } else {
+        VolumePercentDelta::new(sum).unwrap_or(VolumePercentDelta::MAX)
+    }
+}
+fn offset_seconds(base: u32, deltas: &[SecondsDelta]) -> u32 {
+    let target = deltas
+        .iter()
+        .fold(i64::from(base), |acc, delta| acc + i64::from(delta.0));
+    u32::try_from(target.max(0)).unwrap_or(u32::MAX)
+}
+
+/// Allows bursts up to the capacity, refilling one token per interval
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    capacity: u32,
+    refill_interval: Duration,
+    tokens: u32,
+    /// Time of the last refill (only meaningful when below capacity)
+    last_refill: Option<Instant>,
+}
+impl TokenBucket {
+    fn new(capacity: u32, refill_interval: Duration) -> Self {
+        Self {
+            capacity,
+            refill_interval,
+            tokens: capacity,
+            last_refill: None,
+        }
+    }
+    fn refill(&mut self, now: Instant) {
+        let Some(last_refill) = self.last_refill else {
+            return;
+        };
+        if self.refill_interval.is_zero() {
+            self.tokens = self.capacity;
+            self.last_refill = None;
+            return;
+        }
+        let elapsed = now.saturating_duration_since(last_refill);
+        let new_tokens = elapsed.as_nanos() / self.refill_interval.as_nanos();
+        let new_tokens = u32::try_from(new_tokens).unwrap_or(u32::MAX);
+        self.tokens = self.tokens.saturating_add(new_tokens).min(self.capacity);
+        self.last_refill = if self.tokens == self.capacity {
+            None
+        } else {
+            Some(last_refill + self.refill_interval * new_tokens)
+        };
+    }
+    /// Takes one token, or returns the time the next token is available
+    fn try_take(&mut self, now: Instant) -> Result<(), Instant> {
+        self.refill(now);
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            self.last_refill.get_or_insert(now);
+            Ok(())
+        } else {
+            let last_refill = self.last_refill.unwrap_or(now);
+            Err(last_refill + self.refill_interval)
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{status, Clock};
+
+    fn state_with_status(clock: &Clock, time: u64, volume_256: u16) -> ClientState {
+        let mut state = ClientState::new();
+        state.update_at(
+            status("playing", 300, time, 1.0, volume_256),
+            clock.at_millis(0),
+        );
+        state
+    }
+    fn volume(percent: u16) -> Command {
+        Command::Volume {
+            percent: VolumePercent::new(percent).unwrap(),
+        }
+    }
+    fn volume_relative(percent_delta: i16) -> Command {
+        Command::VolumeRelative {
+            percent_delta: VolumePercentDelta::new(percent_delta).unwrap(),
+        }
+    }
+    fn seek_relative(seconds_delta: i32) -> Command {
+        Command::SeekRelative {
+            seconds_delta: SecondsDelta(seconds_delta),
+        }
+    }
+    fn limiter() -> CommandLimiter {
+        CommandLimiter::new(100, Duration::from_millis(10))
+    }
+
+    #[test]
+    fn coalesces_volume_relative_to_absolute() {
+        let clock = Clock::new();
+        let state = state_with_status(&clock, 0, 128); // 50%
+        let mut limiter = limiter();
+        assert_eq!(
+            limiter.push(volume_relative(5), &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(volume_relative(10), &state, clock.at_millis(0)),
+            PushDecision::Coalesced { merged: volume(65) }
+        );
+        assert_eq!(
+            limiter.push(volume_relative(-80), &state, clock.at_millis(0)),
+            PushDecision::Coalesced { merged: volume(0) }
+        );
+        assert_eq!(limiter.pending_len(), 1);
+    }
+
+    #[test]
+    fn coalesces_volume_relative_without_status() {
+        let clock = Clock::new();
+        let state = ClientState::new();
+        let mut limiter = limiter();
+        assert_eq!(
+            limiter.push(volume_relative(5), &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(volume_relative(10), &state, clock.at_millis(0)),
+            PushDecision::Coalesced {
+                merged: volume_relative(15),
+            }
+        );
+        assert_eq!(
+            limiter.push(volume(20), &state, clock.at_millis(0)),
+            PushDecision::Coalesced { merged: volume(20) }
+        );
+        assert_eq!(
+            limiter.push(volume_relative(300), &state, clock.at_millis(0)),
+            PushDecision::Coalesced {
+                merged: volume(300),
+            }
+        );
+    }
+
+    #[test]
+    fn coalesces_seek_relative_to_absolute() {
+        let clock = Clock::new();
+        let state = state_with_status(&clock, 40, 256);
+        let mut limiter = limiter();
+        assert_eq!(
+            limiter.push(seek_relative(10), &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(seek_relative(5), &state, clock.at_millis(0)),
+            PushDecision::Coalesced {
+                merged: Command::SeekTo { seconds: 55 },
+            }
+        );
+        assert_eq!(
+            limiter.push(seek_relative(-100), &state, clock.at_millis(0)),
+            PushDecision::Coalesced {
+                merged: Command::SeekTo { seconds: 0 },
+            }
+        );
+    }
+
+    #[test]
+    fn cancels_toggles() {
+        let clock = Clock::new();
+        let state = ClientState::new();
+        let mut limiter = limiter();
+        assert_eq!(
+            limiter.push(Command::ToggleRandom, &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(Command::ToggleLoopAll, &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(Command::ToggleLoopAll, &state, clock.at_millis(0)),
+            PushDecision::Cancelled
+        );
+        assert_eq!(
+            limiter.push(Command::ToggleRandom, &state, clock.at_millis(0)),
+            PushDecision::Cancelled
+        );
+        assert_eq!(limiter.pending_len(), 0);
+    }
+
+    #[test]
+    fn keeps_unrelated() {
+        let clock = Clock::new();
+        let state = ClientState::new();
+        let mut limiter = limiter();
+        for command in [
+            volume_relative(5),
+            Command::PlaybackPause,
+            volume_relative(5),
+            seek_relative(5),
+            Command::ToggleRandom,
+            Command::ToggleRepeatOne,
+        ] {
+            assert_eq!(
+                limiter.push(command, &state, clock.at_millis(0)),
+                PushDecision::Queued
+            );
+        }
+        assert_eq!(limiter.pending_len(), 6);
+    }
+
+    #[test]
+    fn rejects_when_full() {
+        let clock = Clock::new();
+        let state = ClientState::new();
+        let mut limiter = limiter().set_max_pending(2);
+        assert_eq!(
+            limiter.push(Command::PlaybackPause, &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(volume(5), &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(Command::PlaybackPause, &state, clock.at_millis(0)),
+            PushDecision::Rejected
+        );
+        // coalescing is still allowed
+        assert_eq!(
+            limiter.push(volume(10), &state, clock.at_millis(0)),
+            PushDecision::Coalesced { merged: volume(10) }
+        );
+        assert_eq!(limiter.pending_len(), 2);
+    }
+
+    #[test]
+    fn token_bucket() {
+        let clock = Clock::new();
+        let state = ClientState::new();
+        let mut limiter = CommandLimiter::new(2, Duration::from_millis(100));
+
+        assert_eq!(limiter.pop(clock.at_millis(0)), PopDecision::Empty);
+        for _ in 0..4 {
+            assert_eq!(
+                limiter.push(Command::ToggleRandom, &state, clock.at_millis(0)),
+                PushDecision::Queued
+            );
+            assert_eq!(
+                limiter.push(Command::PlaybackPause, &state, clock.at_millis(0)),
+                PushDecision::Queued
+            );
+        }
+
+        // burst of 2
+        assert_eq!(
+            limiter.pop(clock.at_millis(0)),
+            PopDecision::Send(Command::ToggleRandom)
+        );
+        assert_eq!(
+            limiter.pop(clock.at_millis(10)),
+            PopDecision::Send(Command::PlaybackPause)
+        );
+        assert_eq!(
+            limiter.pop(clock.at_millis(20)),
+            PopDecision::Wait {
+                until: clock.at_millis(100)
+            }
+        );
+        // refill 1
+        assert_eq!(
+            limiter.pop(clock.at_millis(100)),
+            PopDecision::Send(Command::ToggleRandom)
+        );
+        assert_eq!(
+            limiter.pop(clock.at_millis(150)),
+            PopDecision::Wait {
+                until: clock.at_millis(200)
+            }
+        );
+        // refill to capacity (not beyond)
+        assert_eq!(
+            limiter.pop(clock.at_millis(1_000)),
+            PopDecision::Send(Command::PlaybackPause)
+        );
+        assert_eq!(
+            limiter.pop(clock.at_millis(1_000)),
+            PopDecision::Send(Command::ToggleRandom)
+        );
+        assert_eq!(
+            limiter.pop(clock.at_millis(1_000)),
+            PopDecision::Wait {
+                until: clock.at_millis(1_100)
+            }
+        );
+    }
+
+    #[test]
+    fn coalesces_relative_after_sent_relative() {
+        let clock = Clock::new();
+        let state = state_with_status(&clock, 0, 128); // 50%
+        let mut limiter = limiter();
+        assert_eq!(
+            limiter.push(volume_relative(5), &state, clock.at_millis(0)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.pop(clock.at_millis(10)),
+            PopDecision::Send(volume_relative(5))
+        );
+        // status does not include the sent +5 yet, so keep the deltas relative
+        assert_eq!(
+            limiter.push(volume_relative(5), &state, clock.at_millis(20)),
+            PushDecision::Queued
+        );
+        assert_eq!(
+            limiter.push(volume_relative(5), &state, clock.at_millis(30)),
+            PushDecision::Coalesced {
+                merged: volume_relative(10),
+            }
+        );
+    }
+
+    #[test]
+    fn coalesces_seek_relative_from_estimate() {
+        let clock = Clock::new();
+        let state = state_with_status(&clock, 40, 256);
+        let mut limiter = limiter();
+        assert_eq!(
+            limiter.push(seek_relative(10), &state, clock.at_millis(5_000)),
+            PushDecision::Queued
+        );
+        // position includes the playback time since the status
+        assert_eq!(
+            limiter.push(seek_relative(10), &state, clock.at_millis(5_000)),
+            PushDecision::Coalesced {
+                merged: Command::SeekTo { seconds: 65 },
+            }
+        );
+    }
+}
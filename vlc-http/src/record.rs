@@ -0,0 +1,497 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//
+//! Record and replay raw HTTP exchanges with VLC, for reproducing issues offline
+//!
+//! The [`Recorder`] wraps a [`RawEndpointRequestor`] (e.g. the
+//! [`ureq` runner](`crate::http_runner::ureq::HttpRunner`)) and writes each [`Endpoint`] and its
+//! raw response to a [JSON Lines](https://jsonlines.org/) file. The [`Replayer`] serves the
+//! recorded responses in order, for use with
+//! [`sync::complete_plan`](`crate::sync::complete_plan`) or
+//! [`asynchronous::complete_plan`](`crate::asynchronous::complete_plan`).
+//!
+//! Failed requests are recorded with the HTTP status (if VLC responded) and the error message, so
+//! the [`Replayer`] fails at the same point.
+//!
+//! The recorded endpoints show the sequence of requests, for writing a new test case.
+
+use crate::{http_runner::ErrorCategory, response::ParseError, Endpoint, Response};
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Write},
+};
+
+/// Raw HTTP response from VLC
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawResponse {
+    /// Value of the `Content-Type` header
+    pub content_type: String,
+    /// Response body
+    pub body: Vec<u8>,
+}
+
+/// IO portion that resolves [`Endpoint`]s into the [`RawResponse`]
+pub trait RawEndpointRequestor {
+    /// Error for sending the request
+    type Error;
+    /// Request the specified [`Endpoint`] and return the [`RawResponse`]
+    ///
+    /// # Errors
+    /// Returns an error when requesting the [`Endpoint`] fails
+    fn request_raw(&mut self, endpoint: &Endpoint) -> Result<RawResponse, Self::Error>;
+    /// Returns the HTTP status code of the failed request, if VLC responded (e.g. `401` for a
+    /// wrong password)
+    fn error_status(error: &Self::Error) -> Option<u16> {
+        let _ = error;
+        None
+    }
+}
+impl<F, E> RawEndpointRequestor for F
+where
+    F: FnMut(&Endpoint) -> Result<RawResponse, E>,
+{
+    type Error = E;
+    fn request_raw(&mut self, endpoint: &Endpoint) -> Result<RawResponse, Self::Error> {
+        (self)(endpoint)
+    }
+}
+
+/// One line of the recording
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct Exchange {
+    endpoint: Endpoint,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum Outcome {
+    Response {
+        content_type: String,
+        #[serde(flatten)]
+        body: Body,
+    },
+    Failed {
+        error: Failure,
+    },
+}
+/// Failed request
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct Failure {
+    /// HTTP status code, if VLC responded
+    status: Option<u16>,
+    /// Error message, including the sources
+    message: String,
+}
+impl Failure {
+    fn new<E>(status: Option<u16>, error: &E) -> Self
+    where
+        E: std::error::Error,
+    {
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(error) = source {
+            message = format!("{message}: {error}");
+            source = error.source();
+        }
+        Self { status, message }
+    }
+    fn is_not_found(&self) -> bool {
+        self.status == Some(404)
+    }
+}
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Body {
+    /// UTF-8 body (JSON)
+    Text(String),
+    /// Binary body (e.g. album art)
+    Base64(String),
+}
+impl Body {
+    fn new(body: Vec<u8>) -> Self {
+        match String::from_utf8(body) {
+            Ok(text) => Self::Text(text),
+            Err(error) => Self::Base64(BASE64_STANDARD.encode(error.into_bytes())),
+        }
+    }
+    fn into_bytes(self) -> Result<Vec<u8>, base64::DecodeError> {
+        match self {
+            Self::Text(text) => Ok(text.into_bytes()),
+            Self::Base64(encoded) => BASE64_STANDARD.decode(encoded),
+        }
+    }
+}
+
+/// Records each exchange of the inner [`RawEndpointRequestor`] to the writer, as JSON Lines
+pub struct Recorder<R, W> {
+    inner: R,
+    writer: W,
+}
+impl<R, W> Recorder<R, W>
+where
+    R: RawEndpointRequestor,
+    W: Write,
+{
+    /// Creates a recorder for the requestor, writing to `writer` (e.g. a file)
+    pub fn new(inner: R, writer: W) -> Self {
+        Self { inner, writer }
+    }
+    /// Returns the inner requestor and writer
+    pub fn into_inner(self) -> (R, W) {
+        let Self { inner, writer } = self;
+        (inner, writer)
+    }
+}
+impl<R, W> Recorder<R, W>
+where
+    W: Write,
+{
+    fn write(&mut self, exchange: &Exchange) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, exchange).map_err(std::io::Error::from)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+impl<R, W> crate::sync::EndpointRequestor for Recorder<R, W>
+where
+    R: RawEndpointRequestor,
+    R::Error: std::error::Error,
+    W: Write,
+{
+    type Error = RecordError<R::Error>;
+    fn request(&mut self, endpoint: Endpoint) -> Result<Response, Self::Error> {
+        let make_error = |kind| RecordError { kind };
+
+        let (outcome, result) = match self.inner.request_raw(&endpoint) {
+            Ok(RawResponse { content_type, body }) => {
+                // parse before moving the body
+                let response = Response::from_endpoint_body(&endpoint, &content_type, &body)
+                    .map_err(RecordErrorKind::Parse);
+                let outcome = Outcome::Response {
+                    content_type,
+                    body: Body::new(body),
+                };
+                (outcome, response)
+            }
+            Err(error) => {
+                let failure = Failure::new(R::error_status(&error), &error);
+                let response = not_found_response(&endpoint, &failure)
+                    .map_err(RecordErrorKind::Parse)
+                    .and_then(|response| response.ok_or(RecordErrorKind::Request(error)));
+                (Outcome::Failed { error: failure }, response)
+            }
+        };
+        self.write(&Exchange { endpoint, outcome })
+            .map_err(RecordErrorKind::Write)
+            .map_err(make_error)?;
+
+        result.map_err(make_error)
+    }
+}
+
+/// Returns the response for a `404 Not Found` failure, if expected for the endpoint (e.g. no art)
+fn not_found_response(
+    endpoint: &Endpoint,
+    failure: &Failure,
+) -> Result<Option<Response>, ParseError> {
+    if failure.is_not_found() {
+        Response::from_endpoint_not_found(endpoint)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Serves recorded responses in order, verifying each requested [`Endpoint`] matches the recording
+#[derive(Clone, Debug)]
+pub struct Replayer {
+    exchanges: VecDeque<Exchange>,
+}
+impl Replayer {
+    /// Reads the JSON Lines recording from the reader (blank lines are ignored)
+    ///
+    /// # Errors
+    /// Returns an error if reading fails, or any line is invalid
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, LoadError> {
+        let mut exchanges = VecDeque::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let make_error = |kind| LoadError { line_number, kind };
+            let line = line.map_err(LoadErrorKind::Io).map_err(make_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange = serde_json::from_str(&line)
+                .map_err(LoadErrorKind::Json)
+                .map_err(make_error)?;
+            exchanges.push_back(exchange);
+        }
+        Ok(Self { exchanges })
+    }
+    /// Returns the number of recorded responses not yet served
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.exchanges.len()
+    }
+    fn next_response(&mut self, endpoint: Endpoint) -> Result<Response, ReplayError> {
+        let make_error = |kind| ReplayError { kind };
+
+        let Some(exchange) = self.exchanges.pop_front() else {
+            return Err(make_error(ReplayErrorKind::Exhausted { endpoint }));
+        };
+        let Exchange {
+            endpoint: expected,
+            outcome,
+        } = exchange;
+        if expected != endpoint {
+            return Err(make_error(ReplayErrorKind::Mismatch {
+                expected,
+                found: endpoint,
+            }));
+        }
+        match outcome {
+            Outcome::Response { content_type, body } => {
+                let body = body
+                    .into_bytes()
+                    .map_err(ReplayErrorKind::Base64)
+                    .map_err(make_error)?;
+                Response::from_endpoint_body(&endpoint, &content_type, &body)
+                    .map_err(ReplayErrorKind::Parse)
+                    .map_err(make_error)
+            }
+            Outcome::Failed { error } => match not_found_response(&endpoint, &error) {
+                Ok(Some(response)) => Ok(response),
+                Ok(None) => Err(make_error(ReplayErrorKind::Failed(error))),
+                Err(parse_error) => Err(make_error(ReplayErrorKind::Parse(parse_error))),
+            },
+        }
+    }
+}
+impl crate::sync::EndpointRequestor for Replayer {
+    type Error = ReplayError;
+    fn request(&mut self, endpoint: Endpoint) -> Result<Response, Self::Error> {
+        self.next_response(endpoint)
+    }
+}
+impl crate::asynchronous::EndpointRequestor for Replayer {
+    type Error = ReplayError;
+    fn request(
+        &mut self,
+        endpoint: Endpoint,
+    ) -> impl std::future::Future<Output = Result<Response, Self::Error>> + Send {
+        std::future::ready(self.next_response(endpoint))
+    }
+}
+
+/// Error recording an exchange with [`Recorder`]
+#[derive(Debug)]
+pub struct RecordError<E> {
+    kind: RecordErrorKind<E>,
+}
+#[derive(Debug)]
+enum RecordErrorKind<E> {
+    Request(E),
+    Write(std::io::Error),
+    Parse(ParseError),
+}
+impl<E> RecordError<E> {
+    /// Returns the inner requestor error (if the request failed)
+    pub fn into_request_error(self) -> Option<E> {
+        match self.kind {
+            RecordErrorKind::Request(error) => Some(error),
+            RecordErrorKind::Write(_) | RecordErrorKind::Parse(_) => None,
+        }
+    }
+}
+impl<E> std::fmt::Display for RecordError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match &self.kind {
+            RecordErrorKind::Request(_) => "request failed",
+            RecordErrorKind::Write(_) => "recording write failed",
+            RecordErrorKind::Parse(_) => "invalid response",
+        };
+        write!(f, "{description}")
+    }
+}
+impl<E> std::error::Error for RecordError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            RecordErrorKind::Request(error) => Some(error),
+            RecordErrorKind::Write(error) => Some(error),
+            RecordErrorKind::Parse(error) => Some(error),
+        }
+    }
+}
+
+/// Error serving a recorded response from [`Replayer`]
+#[derive(Debug)]
+pub struct ReplayError {
+    kind: ReplayErrorKind,
+}
+#[derive(Debug)]
+enum ReplayErrorKind {
+    Exhausted { endpoint: Endpoint },
+    Mismatch { expected: Endpoint, found: Endpoint },
+    Failed(Failure),
+    Base64(base64::DecodeError),
+    Parse(ParseError),
+}
+impl ReplayError {
+    /// Returns the HTTP status code of the recorded failure (if VLC responded)
+    #[must_use]
+    pub fn recorded_status(&self) -> Option<u16> {
+        match &self.kind {
+            ReplayErrorKind::Failed(failure) => failure.status,
+            _ => None,
+        }
+    }
+    /// Returns the category of the recorded failure, for deciding how to recover (or `None` if
+    /// the replay itself failed)
+    #[must_use]
+    pub fn category(&self) -> Option<ErrorCategory> {
+        match &self.kind {
+            ReplayErrorKind::Failed(Failure { status, .. }) => Some(match status {
+                Some(401) => ErrorCategory::Unauthorized,
+                Some(_) => ErrorCategory::BadResponse,
+                None => ErrorCategory::Unreachable,
+            }),
+            ReplayErrorKind::Exhausted { .. }
+            | ReplayErrorKind::Mismatch { .. }
+            | ReplayErrorKind::Base64(_)
+            | ReplayErrorKind::Parse(_) => None,
+        }
+    }
+}
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ReplayErrorKind::Exhausted { endpoint } => write!(
+                f,
+                "recording exhausted, requested {:?}",
+                endpoint.get_path_and_query()
+            ),
+            ReplayErrorKind::Mismatch { expected, found } => write!(
+                f,
+                "recording expected {:?}, requested {:?}",
+                expected.get_path_and_query(),
+                found.get_path_and_query()
+            ),
+            ReplayErrorKind::Failed(Failure { status, message }) => {
+                write!(f, "recorded request failed")?;
+                if let Some(status) = status {
+                    write!(f, " with status {status}")?;
+                }
+                write!(f, ": {message}")
+            }
+            ReplayErrorKind::Base64(_) => write!(f, "invalid base64 body in recording"),
+            ReplayErrorKind::Parse(_) => write!(f, "invalid response in recording"),
+        }
+    }
+}
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ReplayErrorKind::Exhausted { .. }
+            | ReplayErrorKind::Mismatch { .. }
+            | ReplayErrorKind::Failed(_) => None,
+            ReplayErrorKind::Base64(error) => Some(error),
+            ReplayErrorKind::Parse(error) => Some(error),
+        }
+    }
+}
+
+/// Error loading a recording for [`Replayer`]
+#[derive(Debug)]
+pub struct LoadError {
+    line_number: usize,
+    kind: LoadErrorKind,
+}
+#[derive(Debug)]
+enum LoadErrorKind {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { line_number, kind } = self;
+        let description = match kind {
+            LoadErrorKind::Io(_) => "read failed",
+            LoadErrorKind::Json(_) => "invalid exchange",
+        };
+        write!(f, "{description} on line {line_number}")
+    }
+}
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Io(error) => Some(error),
+            LoadErrorKind::Json(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::panic)]
+mod tests {
+    use super::*;
+
+    fn round_trip(body: &[u8]) -> (serde_json::Value, Vec<u8>) {
+        let exchange = Exchange {
+            endpoint: Endpoint::query_status(),
+            outcome: Outcome::Response {
+                content_type: "test".to_owned(),
+                body: Body::new(body.to_vec()),
+            },
+        };
+        let line = serde_json::to_string(&exchange).expect("serializable");
+        let value = serde_json::from_str(&line).expect("valid json");
+        let exchange: Exchange = serde_json::from_str(&line).expect("valid exchange");
+        let Outcome::Response { body, .. } = exchange.outcome else {
+            panic!("expected response, found {:?}", exchange.outcome);
+        };
+        (value, body.into_bytes().expect("valid body"))
+    }
+
+    #[test]
+    fn text_body() {
+        let (value, body) = round_trip(b"{\"key\": 1}");
+        assert_eq!(value["text"], "{\"key\": 1}");
+        assert_eq!(body, b"{\"key\": 1}");
+    }
+
+    #[test]
+    fn binary_body() {
+        let bytes = [0xFF, 0xD8, 0xFF, 0x00];
+        let (value, body) = round_trip(&bytes);
+        assert_eq!(value["base64"], "/9j/AA==");
+        assert_eq!(body, bytes);
+    }
+
+    #[test]
+    fn failure() {
+        let line =
+            r#"{"endpoint":{"path_and_query":"/"},"error":{"status":401,"message":"denied"}}"#;
+        let exchange: Exchange = serde_json::from_str(line).expect("valid exchange");
+        let Outcome::Failed { error } = exchange.outcome else {
+            panic!("expected failure, found {:?}", exchange.outcome);
+        };
+        assert_eq!(
+            error,
+            Failure {
+                status: Some(401),
+                message: "denied".to_owned(),
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&Exchange {
+                endpoint: exchange.endpoint,
+                outcome: Outcome::Failed { error }
+            })
+            .expect("serializable"),
+            line
+        );
+    }
+}
@@ -1,12 +1,20 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! HTTP-level request primitives (interchange for test purposes)
 
 use base64::{prelude::BASE64_STANDARD, Engine as _};
 pub use endpoint::Endpoint;
-use http::uri::Authority;
+pub(crate) use endpoint::{ArtItem, CommandEffect};
+use http::{
+    header::{HeaderName, HeaderValue},
+    uri::{Authority, PathAndQuery},
+    HeaderMap,
+};
 use std::str::FromStr as _;
 mod endpoint;
 
+#[cfg(test)]
+mod tests;
+
 /// Input authentication parameters to the VLC instance
 #[derive(Clone)]
 pub struct AuthInput {
@@ -16,59 +24,203 @@ pub struct AuthInput {
     pub host: String,
     /// Port number
     pub port: u16,
+    /// URI scheme
+    pub scheme: Scheme,
+    /// Path prefix for all endpoints (e.g. `/vlc` when behind a reverse proxy)
+    pub path_prefix: Option<String>,
+    /// Additional HTTP headers for all requests, as name-value pairs
+    pub extra_headers: Vec<(String, String)>,
+}
+impl AuthInput {
+    /// Creates an input for plain HTTP, with no path prefix or extra headers
+    #[must_use]
+    pub fn new(password: String, host: String, port: u16) -> Self {
+        Self {
+            password,
+            host,
+            port,
+            scheme: Scheme::default(),
+            path_prefix: None,
+            extra_headers: vec![],
+        }
+    }
+}
+/// URI scheme for reaching the VLC instance
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheme {
+    /// Plain HTTP (VLC default)
+    #[default]
+    Http,
+    /// HTTPS, e.g. for VLC behind a TLS reverse proxy
+    ///
+    /// Requires the `tls` feature and the [`ureq`](`crate::http_runner::ureq`) runner
+    Https,
 }
 /// Authentication information to reach a VLC instance
 #[derive(Clone)]
 pub struct Auth {
     /// Bearer string (base64 encoded password with prefix)
     bearer_credential_plaintext: String,
+    /// Scheme for the URI
+    scheme: http::uri::Scheme,
     /// Host and Port
     authority: Authority,
+    /// Path prefix, either empty or starting with a slash (without a trailing slash)
+    path_prefix: String,
+    /// Additional headers
+    extra_headers: HeaderMap,
 }
 impl Auth {
+    /// Returns `true` if requests use HTTPS
+    #[cfg(any(feature = "hyper", feature = "ureq"))]
+    pub(crate) fn is_https(&self) -> bool {
+        self.scheme == http::uri::Scheme::HTTPS
+    }
     /// Converts the authentication input into an optimal format for building HTTP requests
     ///
     /// # Errors
-    /// Returns an error if the host URI is invalid
-    pub fn new(input: AuthInput) -> Result<Self, InvalidHostUri> {
+    /// Returns an error if the host URI, path prefix, or extra headers are invalid
+    pub fn new(input: AuthInput) -> Result<Self, InvalidAuthInput> {
         let AuthInput {
             password,
             host,
             port,
+            scheme,
+            path_prefix,
+            extra_headers,
         } = input;
+        let make_error = |kind| InvalidAuthInput { kind };
 
         // username is blank
         let user_pass = format!(":{password}");
         let bearer_credential_plaintext = format!("Basic {}", BASE64_STANDARD.encode(user_pass));
 
+        let scheme = match scheme {
+            Scheme::Http => http::uri::Scheme::HTTP,
+            Scheme::Https => http::uri::Scheme::HTTPS,
+        };
+
         let host_port: String = format!("{host}:{port}");
-        let authority =
-            Authority::from_str(&host_port).map_err(|error| InvalidHostUri { host_port, error })?;
+        let authority = Authority::from_str(&host_port)
+            .map_err(|error| make_error(InvalidAuthKind::HostUri { host_port, error }))?;
+
+        let path_prefix = {
+            let trimmed = path_prefix.as_deref().unwrap_or_default().trim_matches('/');
+            if trimmed.is_empty() {
+                String::new()
+            } else {
+                format!("/{trimmed}")
+            }
+        };
+        if path_prefix.contains(['?', '#']) {
+            return Err(make_error(InvalidAuthKind::PathPrefixNotPath {
+                path_prefix,
+            }));
+        }
+        if let Err(error) = PathAndQuery::from_str(&path_prefix) {
+            return Err(make_error(InvalidAuthKind::PathPrefix {
+                path_prefix,
+                error,
+            }));
+        }
+
+        let extra_headers = extra_headers
+            .into_iter()
+            .map(|(name, value)| {
+                let header_name = HeaderName::from_str(&name).map_err(|error| {
+                    make_error(InvalidAuthKind::HeaderName {
+                        name: name.clone(),
+                        error,
+                    })
+                })?;
+                let header_value = HeaderValue::from_str(&value)
+                    .map_err(|error| make_error(InvalidAuthKind::HeaderValue { name, error }))?;
+                Ok((header_name, header_value))
+            })
+            .collect::<Result<HeaderMap, _>>()?;
 
         Ok(Self {
             bearer_credential_plaintext,
+            scheme,
             authority,
+            path_prefix,
+            extra_headers,
         })
     }
 }
-/// Error from an invalid `host` in the [`AuthInput`]
+/// Error from an invalid field in the [`AuthInput`]
 #[derive(Debug)]
-pub struct InvalidHostUri {
-    host_port: String,
-    error: http::uri::InvalidUri,
+pub struct InvalidAuthInput {
+    kind: InvalidAuthKind,
 }
-impl std::fmt::Display for InvalidHostUri {
+#[derive(Debug)]
+enum InvalidAuthKind {
+    HostUri {
+        host_port: String,
+        error: http::uri::InvalidUri,
+    },
+    PathPrefix {
+        path_prefix: String,
+        error: http::uri::InvalidUri,
+    },
+    PathPrefixNotPath {
+        path_prefix: String,
+    },
+    HeaderName {
+        name: String,
+        error: http::header::InvalidHeaderName,
+    },
+    HeaderValue {
+        name: String,
+        error: http::header::InvalidHeaderValue,
+    },
+}
+impl std::fmt::Display for InvalidAuthInput {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Self { host_port, error } = self;
-        write!(f, "error for host:port {host_port:?}: {error}")
+        match &self.kind {
+            InvalidAuthKind::HostUri { host_port, error } => {
+                write!(f, "error for host:port {host_port:?}: {error}")
+            }
+            InvalidAuthKind::PathPrefix { path_prefix, error } => {
+                write!(f, "error for path prefix {path_prefix:?}: {error}")
+            }
+            InvalidAuthKind::PathPrefixNotPath { path_prefix } => write!(
+                f,
+                "error for path prefix {path_prefix:?}: query and fragment are not allowed"
+            ),
+            // NOTE: header values may be secret, only print the name
+            InvalidAuthKind::HeaderName { name, error } => {
+                write!(f, "error for header name {name:?}: {error}")
+            }
+            InvalidAuthKind::HeaderValue { name, error } => {
+                write!(f, "error for header value of {name:?}: {error}")
+            }
+        }
+    }
+}
+impl std::error::Error for InvalidAuthInput {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            InvalidAuthKind::HostUri { error, .. } | InvalidAuthKind::PathPrefix { error, .. } => {
+                Some(error)
+            }
+            InvalidAuthKind::PathPrefixNotPath { .. } => None,
+            InvalidAuthKind::HeaderName { error, .. } => Some(error),
+            InvalidAuthKind::HeaderValue { error, .. } => Some(error),
+        }
     }
 }
-impl std::error::Error for InvalidHostUri {}
 
 impl std::fmt::Display for Auth {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // describe the "host:port" only (skip base64-encoded password)
-        write!(f, "{}", self.authority.as_str())
+        // describe the "scheme://host:port/prefix" only (skip password and headers)
+        let Self {
+            scheme,
+            authority,
+            path_prefix,
+            ..
+        } = self;
+        write!(f, "{scheme}://{authority}{path_prefix}")
     }
 }
 impl Endpoint {
@@ -77,7 +229,10 @@ impl Endpoint {
         RequestInfo {
             endpoint: self,
             bearer_credential_plaintext: &auth.bearer_credential_plaintext,
+            scheme: &auth.scheme,
             authority: &auth.authority,
+            path_prefix: &auth.path_prefix,
+            extra_headers: &auth.extra_headers,
         }
     }
 }
@@ -90,8 +245,14 @@ pub struct RequestInfo<'a> {
     endpoint: &'a Endpoint,
     /// Bearer string (base64 encoded password with prefix)
     bearer_credential_plaintext: &'a str,
+    /// Scheme for the URI
+    scheme: &'a http::uri::Scheme,
     /// Host and Port
     authority: &'a Authority,
+    /// Path prefix, either empty or starting with a slash
+    path_prefix: &'a str,
+    /// Additional headers
+    extra_headers: &'a HeaderMap,
 }
 impl RequestInfo<'_> {
     /// Creates an HTTP request
@@ -105,17 +266,25 @@ impl RequestInfo<'_> {
         let Self {
             endpoint,
             bearer_credential_plaintext,
+            scheme,
             authority,
+            path_prefix,
+            extra_headers,
         } = self;
 
+        let path_and_query = format!("{path_prefix}{}", endpoint.get_path_and_query());
         let uri = http::Uri::builder()
-            .scheme("http")
+            .scheme(scheme.clone())
             .authority(authority.clone())
-            .path_and_query(endpoint.get_path_and_query())
+            .path_and_query(path_and_query)
             .build()
             .expect("internally-generated URI is valid");
 
-        http::Request::builder()
+        let mut builder = http::Request::builder();
+        for (name, value) in extra_headers {
+            builder = builder.header(name, value);
+        }
+        builder
             .header(HEADER_AUTHORIZATION, bearer_credential_plaintext)
             .uri(uri)
             .method(endpoint.get_method())
@@ -1,4 +1,4 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! HTTP-level primitives (interchange for test purposes)
 
 use crate::{
@@ -37,6 +37,10 @@ mod endpoint_args {
 
     const PATH_STATUS_JSON: &str = "/requests/status.json";
     const PATH_PLAYLIST_JSON: &str = "/requests/playlist.json";
+    const PATH_ART: &str = "/art";
+    const QUERY_ART_ITEM: &str = "?item=";
+    const PATH_BROWSE_JSON: &str = "/requests/browse.json";
+    const QUERY_BROWSE_URI: &str = "?uri=";
 
     impl Endpoint {
         pub(crate) fn query_status() -> Endpoint {
@@ -45,6 +49,86 @@ mod endpoint_args {
         pub(crate) fn query_playlist() -> Endpoint {
             EndpointArgs::new(PATH_PLAYLIST_JSON, None).finish()
         }
+        pub(crate) fn query_art(item_id: u64) -> Endpoint {
+            EndpointArgs::new(PATH_ART, None)
+                .append_uint("item", item_id)
+                .finish()
+        }
+        pub(crate) fn query_browse(uri: &url::Url) -> Endpoint {
+            EndpointArgs::new(PATH_BROWSE_JSON, None)
+                .append_url("uri", uri)
+                .finish()
+        }
+        /// Returns the (decoded) URI argument, if this is a browse endpoint
+        pub(crate) fn get_browse_uri(&self) -> Option<String> {
+            let encoded = self
+                .path_and_query
+                .strip_prefix(PATH_BROWSE_JSON)?
+                .strip_prefix(QUERY_BROWSE_URI)?;
+            let decoded = urlencoding::decode(encoded)
+                .map_or_else(|_| encoded.to_owned(), std::borrow::Cow::into_owned);
+            Some(decoded)
+        }
+        /// Returns the effect on the playback status, if this is a command endpoint
+        ///
+        /// NOTE: Only for endpoints requested directly, see [`Command::effect`] for commands
+        pub(crate) fn get_command_effect(&self) -> Option<CommandEffect> {
+            const QUERY_COMMAND: &str = "?command=";
+            let query = self
+                .path_and_query
+                .strip_prefix(PATH_STATUS_JSON)
+                .or_else(|| self.path_and_query.strip_prefix(PATH_PLAYLIST_JSON))?;
+            let command = query.strip_prefix(QUERY_COMMAND)?;
+            let name = command.split_once('&').map_or(command, |(name, _)| name);
+            let effect = match name {
+                "pl_next" | "pl_previous" => CommandEffect::ChangeItem,
+                "pl_play" | "pl_delete" | "pl_forceresume" | "pl_forcepause" | "pl_stop"
+                | "seek" | "rate" => CommandEffect::Timing,
+                "volume" => CommandEffect::Volume,
+                _ => CommandEffect::Other,
+            };
+            Some(effect)
+        }
+        /// Returns the item argument, if this is an art endpoint
+        pub(crate) fn get_art_item(&self) -> Option<ArtItem<'_>> {
+            let query = self.path_and_query.strip_prefix(PATH_ART)?;
+            if query.is_empty() {
+                Some(ArtItem::Current)
+            } else {
+                query.strip_prefix(QUERY_ART_ITEM).map(ArtItem::Encoded)
+            }
+        }
+    }
+
+    /// Item argument of an art endpoint
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(crate) enum ArtItem<'a> {
+        /// No argument, VLC responds with the art for the current item
+        Current,
+        /// Encoded item argument
+        Encoded(&'a str),
+    }
+
+    /// Effect of a command endpoint on the playback status
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(crate) enum CommandEffect {
+        /// Moves to another playlist item (e.g. next, previous)
+        ChangeItem,
+        /// Changes the playback position or rate (e.g. play, pause, seek)
+        Timing,
+        /// Changes the volume
+        Volume,
+        /// No effect on the playback timing or volume (e.g. toggle random, add item)
+        Other,
+    }
+    impl CommandEffect {
+        /// Returns `true` if the playback position may change
+        pub(crate) fn affects_timing(self) -> bool {
+            match self {
+                Self::ChangeItem | Self::Timing => true,
+                Self::Volume | Self::Other => false,
+            }
+        }
     }
 
     /// Builder for [`Endpoint`]
@@ -71,7 +155,6 @@ mod endpoint_args {
             Self::new(PATH_PLAYLIST_JSON, Some(command))
         }
         pub fn new_art(id: &str) -> Self {
-            const PATH_ART: &str = "/art";
             Self::new(PATH_ART, None).append("item", id)
         }
         pub fn append_uint<T>(self, key: &str, value: T) -> Self
@@ -126,6 +209,7 @@ mod endpoint_args {
         }
     }
 }
+pub(crate) use endpoint_args::{ArtItem, CommandEffect};
 
 impl Command {
     /// Creates a request endpoint for the current art
@@ -136,6 +220,33 @@ impl Command {
     pub fn into_endpoint(self) -> Endpoint {
         self.into()
     }
+    /// Returns the effect of the command on the playback status
+    pub(crate) fn effect(&self) -> CommandEffect {
+        match self {
+            Self::SeekNext | Self::SeekPrevious => CommandEffect::ChangeItem,
+            Self::PlaylistPlay { .. }
+            | Self::PlaylistDelete { .. }
+            | Self::PlaybackResume
+            | Self::PlaybackPause
+            | Self::PlaybackStop
+            | Self::SeekTo { .. }
+            | Self::SeekRelative { .. }
+            | Self::PlaybackSpeed { .. } => CommandEffect::Timing,
+            Self::Volume { .. } | Self::VolumeRelative { .. } => CommandEffect::Volume,
+            Self::PlaylistAdd { .. }
+            | Self::ToggleRandom
+            | Self::ToggleRepeatOne
+            | Self::ToggleLoopAll
+            | Self::EqualizerEnable { .. }
+            | Self::EqualizerPreamp { .. }
+            | Self::EqualizerBand { .. }
+            | Self::EqualizerPreset { .. }
+            | Self::AudioDelay { .. }
+            | Self::SubtitleDelay { .. }
+            | Self::AudioTrack { .. }
+            | Self::SubtitleTrack { .. } => CommandEffect::Other,
+        }
+    }
 }
 impl From<Command> for Endpoint {
     /// Creates a request for the specified command
@@ -178,6 +289,30 @@ impl From<Command> for Endpoint {
             Command::PlaybackSpeed { speed } => {
                 Args::new_status("rate").append("val", &speed.to_string())
             }
+            Command::EqualizerEnable { enabled } => {
+                Args::new_status("enableeq").append_uint("val", u8::from(enabled))
+            }
+            Command::EqualizerPreamp { gain } => {
+                Args::new_status("preamp").append("val", &gain.value().to_string())
+            }
+            Command::EqualizerBand { band, gain } => Args::new_status("equalizer")
+                .append_uint("band", band.value())
+                .append("val", &gain.value().to_string()),
+            Command::EqualizerPreset { preset_id } => {
+                Args::new_status("setpreset").append_uint("val", preset_id)
+            }
+            Command::AudioDelay { seconds } => {
+                Args::new_status("audiodelay").append("val", &seconds.value().to_string())
+            }
+            Command::SubtitleDelay { seconds } => {
+                Args::new_status("subdelay").append("val", &seconds.value().to_string())
+            }
+            Command::AudioTrack { track_id } => {
+                Args::new_status("audio_track").append_uint("val", track_id)
+            }
+            Command::SubtitleTrack { track_id } => {
+                Args::new_status("subtitle_track").append_uint("val", track_id)
+            }
         }
         .finish()
     }
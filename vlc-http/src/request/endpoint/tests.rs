@@ -1,6 +1,7 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 use super::*;
+use crate::command::DelaySeconds;
 use test_log::test;
 
 #[test]
@@ -390,3 +391,130 @@ fn playback_speed() {
     )
     "###);
 }
+
+#[test]
+fn equalizer() {
+    insta::assert_ron_snapshot!(Endpoint::from(Command::EqualizerEnable {
+        enabled: true,
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=enableeq&val=1",
+    )
+    "###);
+    insta::assert_ron_snapshot!(Endpoint::from(Command::EqualizerEnable {
+        enabled: false,
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=enableeq&val=0",
+    )
+    "###);
+    insta::assert_ron_snapshot!(Endpoint::from(Command::EqualizerPreamp {
+        gain: (-3.5).try_into().expect("gain"),
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=preamp&val=-3.5",
+    )
+    "###);
+    insta::assert_ron_snapshot!(Endpoint::from(Command::EqualizerBand {
+        band: 9u8.try_into().expect("band"),
+        gain: 12.0.try_into().expect("gain"),
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=equalizer&band=9&val=12",
+    )
+    "###);
+    insta::assert_ron_snapshot!(Endpoint::from(Command::EqualizerPreset {
+        preset_id: 4,
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=setpreset&val=4",
+    )
+    "###);
+}
+
+#[test]
+fn delay() {
+    insta::assert_ron_snapshot!(Endpoint::from(Command::AudioDelay {
+        seconds: DelaySeconds::new(-0.25).expect("delay"),
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=audiodelay&val=-0.25",
+    )
+    "###);
+    insta::assert_ron_snapshot!(Endpoint::from(Command::SubtitleDelay {
+        seconds: DelaySeconds::new(1.5).expect("delay"),
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=subdelay&val=1.5",
+    )
+    "###);
+}
+
+#[test]
+fn track() {
+    insta::assert_ron_snapshot!(Endpoint::from(Command::AudioTrack {
+        track_id: 1,
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=audio_track&val=1",
+    )
+    "###);
+    insta::assert_ron_snapshot!(Endpoint::from(Command::SubtitleTrack {
+        track_id: 3,
+    }), @r###"
+    Endpoint(
+      path_and_query: "/requests/status.json?command=subtitle_track&val=3",
+    )
+    "###);
+}
+
+#[test]
+fn command_effect() {
+    assert_eq!(Endpoint::query_status().get_command_effect(), None);
+    assert_eq!(Endpoint::query_playlist().get_command_effect(), None);
+    assert_eq!(Endpoint::query_art(5).get_command_effect(), None);
+    assert_eq!(
+        Command::PlaylistPlay { item_id: Some(3) }.effect(),
+        CommandEffect::Timing
+    );
+    assert_eq!(
+        Command::PlaylistDelete { item_id: 3 }.effect(),
+        CommandEffect::Timing
+    );
+    assert_eq!(Command::SeekNext.effect(), CommandEffect::ChangeItem);
+    assert_eq!(
+        Command::SeekTo { seconds: 5 }.effect(),
+        CommandEffect::Timing
+    );
+    assert_eq!(
+        Command::VolumeRelative {
+            percent_delta: (-5i16).try_into().expect("delta"),
+        }
+        .effect(),
+        CommandEffect::Volume
+    );
+    assert_eq!(Command::ToggleRandom.effect(), CommandEffect::Other);
+    assert_eq!(
+        Command::PlaylistAdd {
+            url: url::Url::parse("file:///a").expect("valid url"),
+        }
+        .effect(),
+        CommandEffect::Other
+    );
+    // endpoints requested directly have the same effect
+    for command in [
+        Command::PlaylistPlay { item_id: None },
+        Command::PlaybackPause,
+        Command::SeekPrevious,
+        Command::SeekRelative {
+            seconds_delta: (-5).into(),
+        },
+        Command::Volume {
+            percent: 50u16.try_into().expect("volume"),
+        },
+        Command::ToggleLoopAll,
+    ] {
+        let effect = command.effect();
+        assert_eq!(command.into_endpoint().get_command_effect(), Some(effect));
+    }
+}
@@ -1,10 +1,17 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 //! HTTP-level response primitives
 
+use crate::{request::ArtItem, Endpoint};
 use std::io::Read;
 
+pub use art::Art;
+mod art;
+
+pub use browse::Listing as BrowseListing;
+pub mod browse;
+
 pub use playback::Status as PlaybackStatus;
-mod playback;
+pub mod playback;
 
 pub use playlist::Info as PlaylistInfo;
 pub mod playlist;
@@ -22,14 +29,16 @@ pub struct Response {
 #[cfg_attr(test, derive(serde::Serialize))]
 pub(crate) enum ResponseInner {
     PlaylistInfo(PlaylistInfo),
-    PlaybackStatus(PlaybackStatus),
+    PlaybackStatus(Box<PlaybackStatus>),
+    Art(Art),
+    BrowseListing(BrowseListing),
 }
 
 #[derive(serde::Deserialize)]
 #[serde(untagged)]
 enum ResponseJSON {
     PlaylistInfo(playlist::InfoJSON),
-    PlaybackStatus(playback::StatusJSON),
+    PlaybackStatus(Box<playback::StatusJSON>),
 }
 
 impl std::str::FromStr for Response {
@@ -59,6 +68,70 @@ impl Response {
         let response_json: ResponseJSON = serde_json::from_reader(reader)?;
         Ok(response_json.into())
     }
+    /// Parse the VLC response body for the specified [`Endpoint`]
+    ///
+    /// Art endpoints return the image bytes as-is (with the specified `content_type`), browse
+    /// endpoints are labeled with the requested URI, and all other endpoints are parsed as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the response is invalid, the art endpoint has no numeric item id (a
+    /// bare `/art` for the current item is not supported), or the browse endpoint has no valid URI
+    pub fn from_endpoint_body(
+        endpoint: &Endpoint,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<Self, ParseError> {
+        if let Some(uri) = endpoint.get_browse_uri() {
+            let uri = url::Url::parse(&uri).map_err(|_| ParseError {
+                kind: ParseErrorKind::BrowseUri { uri },
+            })?;
+            let listing_json = serde_json::from_slice(body)?;
+            return Ok(Self {
+                inner: ResponseInner::BrowseListing(BrowseListing::new(uri, listing_json)),
+            });
+        }
+        let Some(item) = endpoint.get_art_item() else {
+            return Self::from_slice(body);
+        };
+        let playlist_item_id = Self::parse_art_item(item)?;
+        Ok(Self {
+            inner: ResponseInner::Art(Art::new(
+                playlist_item_id,
+                content_type.to_owned(),
+                body.to_owned(),
+            )),
+        })
+    }
+    /// Returns the response for a `404 Not Found` status from the specified [`Endpoint`], or
+    /// `None` if the status is an error for the endpoint
+    ///
+    /// VLC responds `404 Not Found` for art endpoints when the item has no art, so this is
+    /// reported as [`Art`] that is absent (to cache per item, avoiding repeated requests).
+    ///
+    /// # Errors
+    /// Returns an error if the art endpoint has no numeric item id
+    pub fn from_endpoint_not_found(endpoint: &Endpoint) -> Result<Option<Self>, ParseError> {
+        let Some(item) = endpoint.get_art_item() else {
+            return Ok(None);
+        };
+        let playlist_item_id = Self::parse_art_item(item)?;
+        Ok(Some(Self {
+            inner: ResponseInner::Art(Art::absent(playlist_item_id)),
+        }))
+    }
+    fn parse_art_item(item: ArtItem<'_>) -> Result<u64, ParseError> {
+        match item {
+            // NOTE: the current item may change before the response, so the art is not attributable
+            ArtItem::Current => Err(ParseError {
+                kind: ParseErrorKind::ArtItemMissing,
+            }),
+            ArtItem::Encoded(item) => item.parse().map_err(|_| ParseError {
+                kind: ParseErrorKind::ArtItemId {
+                    item: item.to_owned(),
+                },
+            }),
+        }
+    }
 }
 
 impl From<ResponseJSON> for Response {
@@ -68,7 +141,7 @@ impl From<ResponseJSON> for Response {
                 inner: ResponseInner::PlaylistInfo(PlaylistInfo::new(info)),
             },
             ResponseJSON::PlaybackStatus(status) => Self {
-                inner: ResponseInner::PlaybackStatus(status.into()),
+                inner: ResponseInner::PlaybackStatus(Box::new((*status).into())),
             },
         }
     }
@@ -77,19 +150,34 @@ impl From<ResponseJSON> for Response {
 /// Error in parsing a VLC response
 #[derive(Debug)]
 pub struct ParseError {
-    serde_json_err: serde_json::Error,
+    kind: ParseErrorKind,
+}
+#[derive(Debug)]
+enum ParseErrorKind {
+    Json(serde_json::Error),
+    ArtItemId { item: String },
+    ArtItemMissing,
+    BrowseUri { uri: String },
 }
 impl From<serde_json::Error> for ParseError {
     fn from(value: serde_json::Error) -> Self {
         Self {
-            serde_json_err: value,
+            kind: ParseErrorKind::Json(value),
         }
     }
 }
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Self { serde_json_err } = self;
-        write!(f, "invalid json: {serde_json_err}")
+        match &self.kind {
+            ParseErrorKind::Json(serde_json_err) => write!(f, "invalid json: {serde_json_err}"),
+            ParseErrorKind::ArtItemId { item } => {
+                write!(f, "invalid item id {item:?} for art endpoint")
+            }
+            ParseErrorKind::ArtItemMissing => write!(f, "missing item id for art endpoint"),
+            ParseErrorKind::BrowseUri { uri } => {
+                write!(f, "invalid uri {uri:?} for browse endpoint")
+            }
+        }
     }
 }
 impl std::error::Error for ParseError {}
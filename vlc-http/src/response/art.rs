@@ -0,0 +1,68 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Album art response types
+
+/// Album art for a playlist item, or the absence of art (VLC responds `404 Not Found`)
+#[must_use]
+#[derive(Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Art {
+    playlist_item_id: u64,
+    image: Option<Image>,
+}
+#[derive(Clone, PartialEq, Eq, serde::Serialize)]
+struct Image {
+    content_type: String,
+    data: Vec<u8>,
+}
+impl Art {
+    pub(crate) fn new(playlist_item_id: u64, content_type: String, data: Vec<u8>) -> Self {
+        Self {
+            playlist_item_id,
+            image: Some(Image { content_type, data }),
+        }
+    }
+    pub(crate) fn absent(playlist_item_id: u64) -> Self {
+        Self {
+            playlist_item_id,
+            image: None,
+        }
+    }
+    /// Returns the playlist item for the art
+    #[must_use]
+    pub fn playlist_item_id(&self) -> u64 {
+        self.playlist_item_id
+    }
+    /// Returns `true` if the item has no art
+    #[must_use]
+    pub fn is_absent(&self) -> bool {
+        self.image.is_none()
+    }
+    /// Returns the HTTP content type reported by VLC (e.g. `image/jpeg`), or `None` if the item
+    /// has no art
+    #[must_use]
+    pub fn content_type(&self) -> Option<&str> {
+        self.image.as_ref().map(|image| image.content_type.as_str())
+    }
+    /// Returns the raw image bytes, or `None` if the item has no art
+    #[must_use]
+    pub fn data(&self) -> Option<&[u8]> {
+        self.image.as_ref().map(|image| image.data.as_slice())
+    }
+}
+impl std::fmt::Debug for Art {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // NOTE: omit the image bytes, to keep logs readable
+        let Self {
+            playlist_item_id,
+            image,
+        } = self;
+        let mut debug = f.debug_struct("Art");
+        debug.field("playlist_item_id", playlist_item_id);
+        match image {
+            Some(Image { content_type, data }) => debug
+                .field("content_type", content_type)
+                .field("data_len", &data.len()),
+            None => debug.field("absent", &true),
+        };
+        debug.finish()
+    }
+}
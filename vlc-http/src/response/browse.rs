@@ -0,0 +1,131 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! File browsing response types
+
+use crate::fmt::DebugUrl;
+use serde::Deserialize;
+
+/// Directory listing on the VLC host
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Listing {
+    uri: DebugUrl,
+    items: Vec<Item>,
+}
+impl Listing {
+    pub(super) fn new(uri: url::Url, json: ListingJSON) -> Self {
+        let ListingJSON { element } = json;
+        Self {
+            uri: DebugUrl(uri),
+            items: element.into_iter().map(Item::from).collect(),
+        }
+    }
+    /// Returns the URI of the listed directory (as requested)
+    #[must_use]
+    pub fn get_uri(&self) -> &url::Url {
+        self.uri.as_ref()
+    }
+    /// Returns the entries in the directory
+    ///
+    /// NOTE: VLC includes the parent directory as `..`
+    #[must_use]
+    pub fn get_items(&self) -> &[Item] {
+        &self.items
+    }
+}
+/// Entry in a directory [`Listing`]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Item {
+    name: String,
+    file_type: FileType,
+    size_bytes: Option<u64>,
+    uri: DebugUrl,
+    path: String,
+    modification_time_secs: Option<u64>,
+}
+impl Item {
+    /// Returns the file name
+    #[must_use]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Returns the type of file
+    pub fn get_file_type(&self) -> FileType {
+        self.file_type
+    }
+    /// Returns the size in bytes (if known)
+    #[must_use]
+    pub fn get_size_bytes(&self) -> Option<u64> {
+        self.size_bytes
+    }
+    /// Returns the URI, for use in [`Command::PlaylistAdd`](`crate::Command::PlaylistAdd`) or
+    /// browsing a subdirectory
+    #[must_use]
+    pub fn get_uri(&self) -> &url::Url {
+        self.uri.as_ref()
+    }
+    /// Returns the path on the VLC host
+    #[must_use]
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    /// Returns the modification time in seconds since the Unix epoch (if known)
+    #[must_use]
+    pub fn get_modification_time_secs(&self) -> Option<u64> {
+        self.modification_time_secs
+    }
+}
+/// Type of an [`Item`] in a directory [`Listing`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[must_use]
+pub enum FileType {
+    /// Directory, may be browsed
+    Directory,
+    /// Regular file
+    File,
+    /// Unrecognized type
+    Other,
+}
+impl From<ItemJSON> for Item {
+    fn from(other: ItemJSON) -> Self {
+        let ItemJSON {
+            name,
+            file_type,
+            size_bytes,
+            uri,
+            path,
+            modification_time_secs,
+        } = other;
+        let file_type = match file_type.as_str() {
+            "dir" => FileType::Directory,
+            "file" => FileType::File,
+            _ => FileType::Other,
+        };
+        Self {
+            name,
+            file_type,
+            size_bytes,
+            uri: DebugUrl(uri),
+            path,
+            modification_time_secs,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(super) struct ListingJSON {
+    #[serde(default)]
+    element: Vec<ItemJSON>,
+}
+#[derive(Deserialize, Debug)]
+struct ItemJSON {
+    name: String,
+    #[serde(rename = "type")]
+    file_type: String,
+    #[serde(rename = "size", default)]
+    size_bytes: Option<u64>,
+    uri: url::Url,
+    #[serde(default)]
+    path: String,
+    #[serde(rename = "modification_time", default)]
+    modification_time_secs: Option<u64>,
+}
@@ -1,4 +1,5 @@
 // Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Playback status response types
 
 use crate::command::VolumePercent256;
 use serde::Deserialize;
@@ -36,29 +37,98 @@ pub struct Status {
     pub position_fraction: f64,
     /// Rate (as a fraction) of playback speed
     pub rate_ratio: f64,
+    // --------------------------------------------------
+    // Audio Effects
+    // --------------------------------------------------
+    /// Equalizer settings, or `None` if the equalizer is disabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equalizer: Option<Equalizer>,
 }
 /// Mode of the playback
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize)]
 pub enum Mode {
+    /// Current item is paused
     Paused,
+    /// Current item is playing
     #[default]
     Playing,
+    /// No item is selected for playback
     Stopped,
 }
+/// Equalizer settings (present only when the equalizer is enabled)
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
+#[non_exhaustive]
+pub struct Equalizer {
+    /// Gain (in dB) of the preamp
+    pub preamp_db: f64,
+    /// Gain (in dB) of each band, keyed by band index
+    pub bands_db: BTreeMap<u8, f64>,
+    /// Names of the available presets, keyed by preset id
+    pub presets: BTreeMap<u32, String>,
+}
 /// Information about the current (playing/paused) item
 #[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
 #[non_exhaustive]
 pub struct Info {
+    /// Title of the item
     pub title: String,
+    /// Artist of the item
     pub artist: String,
+    /// Album containing the item
     pub album: String,
+    /// Release date of the item
     pub date: String,
+    /// Track number within the album
     pub track_number: String,
+    /// Total track count of the album
     pub track_total: String,
+    /// Genre of the item
+    pub genre: Option<String>,
+    /// Artist of the album (if different from the item artist)
+    pub album_artist: Option<String>,
+    /// Disc number within the album
+    pub disc_number: Option<String>,
+    /// Location of the album art (may be an `attachment://` URL)
+    pub artwork_url: Option<String>,
+    /// Encoder of the item
+    pub encoded_by: Option<String>,
+    /// Remaining metadata fields, keyed by the VLC field name
     pub extra: BTreeMap<String, String>,
+    /// Details of the elementary streams (audio, video, subtitles), sorted by index
+    pub streams: Vec<Stream>,
+    /// Remaining information categories, keyed by the VLC category name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_categories: BTreeMap<String, BTreeMap<String, String>>,
     /// Playlist ID of the item
     pub playlist_item_id: Option<u64>,
 }
+/// Details of an elementary stream (from the `Stream N` categories)
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+#[non_exhaustive]
+pub struct Stream {
+    /// Index of the stream
+    pub index: u32,
+    /// Type of the stream (e.g. `Audio`, `Video`, `Subtitle`)
+    pub stream_type: Option<String>,
+    /// Codec description
+    pub codec: Option<String>,
+    /// Language of the stream
+    pub language: Option<String>,
+    /// Audio channel layout (e.g. `Stereo`)
+    pub channels: Option<String>,
+    /// Audio sample rate (e.g. `44100 Hz`)
+    pub sample_rate: Option<String>,
+    /// Audio bits per sample
+    pub bits_per_sample: Option<String>,
+    /// Bitrate (e.g. `320 kb/s`)
+    pub bitrate: Option<String>,
+    /// Video resolution (e.g. `1920x1080`)
+    pub video_resolution: Option<String>,
+    /// Video frame rate
+    pub frame_rate: Option<String>,
+    /// Remaining fields, keyed by the VLC field name
+    pub extra: BTreeMap<String, String>,
+}
 impl From<StatusJSON> for Status {
     fn from(other: StatusJSON) -> Self {
         let StatusJSON {
@@ -75,6 +145,7 @@ impl From<StatusJSON> for Status {
             position_secs,
             version,
             volume_256,
+            equalizer,
         } = other;
         // convert signed time to unsigned
         let position_secs = u64::try_from(position_secs).unwrap_or(0);
@@ -96,6 +167,7 @@ impl From<StatusJSON> for Status {
             position_fraction,
             rate_ratio,
             mode: mode.into(),
+            equalizer: equalizer.and_then(EqualizerJSON::into_equalizer),
         }
     }
 }
@@ -108,8 +180,49 @@ impl From<ModeJSON> for Mode {
         }
     }
 }
+impl EqualizerJSON {
+    fn into_equalizer(self) -> Option<Equalizer> {
+        /// Parses the id from VLC's XML-style keys, e.g. `band id="3"`
+        fn parse_key<T: std::str::FromStr>(key: &str, prefix: &str) -> Option<T> {
+            key.strip_prefix(prefix)?
+                .strip_prefix(" id=\"")?
+                .strip_suffix('"')?
+                .parse()
+                .ok()
+        }
+        match self {
+            Self::Disabled(_) => None,
+            Self::Enabled {
+                preamp,
+                bands,
+                presets,
+            } => Some(Equalizer {
+                preamp_db: preamp.value(),
+                bands_db: bands
+                    .into_iter()
+                    .filter_map(|(key, gain)| Some((parse_key(&key, "band")?, gain.value())))
+                    .collect(),
+                presets: presets
+                    .into_iter()
+                    .filter_map(|(key, name)| Some((parse_key(&key, "preset")?, name)))
+                    .collect(),
+            }),
+        }
+    }
+}
+impl FloatJSON {
+    fn value(&self) -> f64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::String(value) => value.trim().parse().unwrap_or(0.0),
+        }
+    }
+}
 impl From<InfoJSON> for Info {
     fn from(other: InfoJSON) -> Self {
+        const STREAM_PREFIX: &str = "Stream ";
+
+        let CategoryJSON { meta, others } = other.category;
         let MetaJSON {
             title,
             artist,
@@ -117,9 +230,30 @@ impl From<InfoJSON> for Info {
             date,
             track_number,
             track_total,
+            genre,
+            album_artist,
+            disc_number,
+            artwork_url,
+            encoded_by,
             extra,
             playlist_item_id,
-        } = other.category.meta;
+        } = meta;
+
+        let mut streams = vec![];
+        let mut extra_categories = BTreeMap::new();
+        for (name, fields) in others {
+            let index = name
+                .strip_prefix(STREAM_PREFIX)
+                .and_then(|index| index.parse().ok());
+            match index {
+                Some(index) => streams.push(Stream::new(index, fields)),
+                None => {
+                    extra_categories.insert(name, fields);
+                }
+            }
+        }
+        streams.sort_by_key(|stream| stream.index);
+
         Self {
             title,
             artist,
@@ -127,11 +261,36 @@ impl From<InfoJSON> for Info {
             date,
             track_number,
             track_total,
+            genre,
+            album_artist,
+            disc_number,
+            artwork_url,
+            encoded_by,
             extra,
+            streams,
+            extra_categories,
             playlist_item_id,
         }
     }
 }
+impl Stream {
+    fn new(index: u32, mut fields: BTreeMap<String, String>) -> Self {
+        let mut take = |key: &str| fields.remove(key);
+        Self {
+            index,
+            stream_type: take("Type"),
+            codec: take("Codec"),
+            language: take("Language"),
+            channels: take("Channels"),
+            sample_rate: take("Sample_rate"),
+            bits_per_sample: take("Bits_per_sample"),
+            bitrate: take("Bitrate"),
+            video_resolution: take("Video_resolution"),
+            frame_rate: take("Frame_rate"),
+            extra: fields,
+        }
+    }
+}
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct StatusJSON {
@@ -161,6 +320,36 @@ pub(crate) struct StatusJSON {
     /// 256-scale
     #[serde(rename = "volume")]
     volume_256: u16,
+    #[serde(default)]
+    equalizer: Option<EqualizerJSON>,
+}
+/// Equalizer state, reported as an empty array when disabled
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum EqualizerJSON {
+    Disabled(#[expect(unused)] Vec<serde_json::Value>),
+    Enabled {
+        #[serde(default)]
+        preamp: FloatJSON,
+        /// keyed by `band id="N"`
+        #[serde(default)]
+        bands: BTreeMap<String, FloatJSON>,
+        /// keyed by `preset id="N"`
+        #[serde(default)]
+        presets: BTreeMap<String, String>,
+    },
+}
+/// VLC formats some numbers as strings
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum FloatJSON {
+    Number(f64),
+    String(String),
+}
+impl Default for FloatJSON {
+    fn default() -> Self {
+        Self::Number(0.0)
+    }
 }
 /// Mode of the playback
 #[derive(Deserialize, Debug)]
@@ -176,10 +365,14 @@ struct InfoJSON {
 }
 #[derive(Deserialize, Debug)]
 struct CategoryJSON {
+    #[serde(default)]
     meta: MetaJSON,
+    /// keyed by category name, e.g. `Stream 0`
+    #[serde(flatten)]
+    others: BTreeMap<String, BTreeMap<String, String>>,
 }
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
-pub struct MetaJSON {
+struct MetaJSON {
     #[serde(default)]
     title: String,
     #[serde(default)]
@@ -192,6 +385,16 @@ pub struct MetaJSON {
     track_number: String,
     #[serde(default)]
     track_total: String,
+    #[serde(default)]
+    genre: Option<String>,
+    #[serde(default, alias = "ALBUMARTIST", alias = "ALBUM ARTIST")]
+    album_artist: Option<String>,
+    #[serde(default, alias = "DISCNUMBER")]
+    disc_number: Option<String>,
+    #[serde(default)]
+    artwork_url: Option<String>,
+    #[serde(default)]
+    encoded_by: Option<String>,
     #[serde(flatten)]
     extra: BTreeMap<String, String>,
     #[serde(default)]
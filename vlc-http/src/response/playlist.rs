@@ -2,6 +2,7 @@
 //! Playlist response types
 
 use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
 
 /// Playlist information
 #[must_use]
@@ -33,19 +34,29 @@ impl From<ItemJSON> for Item {
             id,
             name,
             url,
+            extra,
         } = other;
+        let extra = extra
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(value) => (key, value),
+                value => (key, value.to_string()),
+            })
+            .collect();
         Self::new(ItemBuilder {
             duration_secs: duration_secs.try_into().ok(),
             id,
             name,
             url,
         })
+        .with_extra(extra)
     }
 }
 pub use item::Item;
 pub(crate) use item::ItemBuilder;
 mod item {
     use crate::fmt::DebugUrl;
+    use std::collections::BTreeMap;
 
     /// Item in the playlist (track, playlist, folder, etc.)
     #[derive(Clone, PartialEq, Eq, serde::Serialize)]
@@ -56,6 +67,9 @@ mod item {
         id: u64,
         name: String,
         url: DebugUrl,
+        /// Remaining fields, keyed by the VLC field name
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        extra: BTreeMap<String, String>,
     }
     impl Item {
         /// Returns the duration in seconds (if known)
@@ -78,6 +92,13 @@ mod item {
         pub fn get_url(&self) -> &url::Url {
             self.as_ref()
         }
+        /// Returns the remaining fields, keyed by the VLC field name
+        ///
+        /// NOTE: VLC 3.x reports only `ro` (read-only flag) and `type` (`leaf` or `node`)
+        #[must_use]
+        pub fn get_extra(&self) -> &BTreeMap<String, String> {
+            &self.extra
+        }
     }
     impl AsRef<url::Url> for Item {
         fn as_ref(&self) -> &url::Url {
@@ -109,8 +130,13 @@ mod item {
                 id,
                 name,
                 url: DebugUrl(url),
+                extra: BTreeMap::new(),
             }
         }
+        pub(super) fn with_extra(mut self, extra: BTreeMap<String, String>) -> Self {
+            self.extra = extra;
+            self
+        }
     }
 
     impl std::fmt::Debug for Item {
@@ -120,6 +146,7 @@ mod item {
                 id,
                 name,
                 url: DebugUrl(url_raw),
+                extra: _,
             } = self;
 
             write!(f, r#"[{id}] "{name}""#)?;
@@ -158,6 +185,8 @@ struct ItemJSON {
     name: String,
     #[serde(rename = "uri")]
     url: url::Url,
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
 }
 impl std::fmt::Debug for Info {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -2,6 +2,7 @@
 //! Convenience functions for [`Plan`]s in a synchronous (blocking) context
 
 use crate::{goal::Step, ClientState, Endpoint, Plan, Response};
+use std::time::Instant;
 
 /// IO portion that resolves [`Endpoint`]s into the [`Response`]
 pub trait EndpointRequestor {
@@ -31,10 +32,8 @@ where
 /// Returns an error if the endpoint generation fails, `call_endpoint_fn` fails, or the
 /// `max_iter_count` is exceeded.
 ///
-///
-/// NOTE: While an equivalent helper function could be created for `async` (accepting an `async`
-/// closure) this is left for the user to implement, as they may need to select between other
-/// competing futures.
+/// See [`asynchronous::complete_plan`](`crate::asynchronous::complete_plan`) for the `async`
+/// equivalent.
 pub fn complete_plan<'a, T, E, F>(
     mut source: T,
     client_state: &'a mut ClientState,
@@ -52,6 +51,7 @@ where
             let Step::Need(endpoint) = source.next(client_state).map_err(ErrorKind::Poll)? else {
                 break; // final output borrow occurs below
             };
+            client_state.notify_endpoint(&endpoint, Instant::now());
             let response = endpoint_caller
                 .request(endpoint)
                 .map_err(ErrorKind::EndpointFn)?;
@@ -66,7 +66,7 @@ where
             }),
         }
     };
-    inner(&mut source, client_state, endpoint_caller).map_err(|kind| Error { source, kind })
+    inner(&mut source, client_state, endpoint_caller).map_err(|kind| Error::new(source, kind))
 }
 
 /// Failure to exhaust a [`Plan`] to the final output
@@ -77,8 +77,20 @@ pub struct Error<T, E> {
     source: T,
     kind: ErrorKind<E>,
 }
+impl<T, E> Error<T, E> {
+    pub(crate) fn new(source: T, kind: ErrorKind<E>) -> Self {
+        Self { source, kind }
+    }
+    /// Returns the error from the [`EndpointRequestor`], if that was the cause of failure
+    pub fn endpoint_error(&self) -> Option<&E> {
+        match &self.kind {
+            ErrorKind::EndpointFn(error) => Some(error),
+            ErrorKind::Poll(_) | ErrorKind::IterationCountExceeded { .. } => None,
+        }
+    }
+}
 #[derive(Debug)]
-enum ErrorKind<E> {
+pub(crate) enum ErrorKind<E> {
     Poll(crate::goal::Error),
     EndpointFn(E),
     IterationCountExceeded {
@@ -0,0 +1,40 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+//! Shared fixtures for unit tests driven by a fake clock
+
+use crate::Response;
+use std::{
+    str::FromStr as _,
+    time::{Duration, Instant},
+};
+
+/// Fake clock, starting at an arbitrary instant
+pub(crate) struct Clock(Instant);
+impl Clock {
+    pub(crate) fn new() -> Self {
+        Self(Instant::now())
+    }
+    pub(crate) fn at_millis(&self, millis: u64) -> Instant {
+        self.0 + Duration::from_millis(millis)
+    }
+}
+
+/// Playback status response for the current item (ID 5)
+pub(crate) fn status(state: &str, length: u64, time: u64, rate: f64, volume_256: u16) -> Response {
+    let json = format!(
+        r#"{{
+          "rate":{rate},
+          "time":{time},
+          "repeat":false,
+          "loop":false,
+          "length":{length},
+          "random":false,
+          "apiversion":3,
+          "version":"3.0.20 Vetinari",
+          "currentplid":5,
+          "position":0.0,
+          "volume":{volume_256},
+          "state":"{state}"
+        }}"#
+    );
+    Response::from_str(&json).expect("valid status JSON")
+}
@@ -1,12 +1,25 @@
 // Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
-use super::Model;
 use clap::Parser as _;
-use std::{collections::VecDeque, num::NonZeroU32};
+use std::{collections::VecDeque, num::NonZeroU32, time::Duration};
 use tracing::error;
+use vlc_http::fake::{Model, ModelResponse};
 use vlc_http::{client_state::PlanBuilder, goal::Step, ClientState, Endpoint, Plan};
 
 pub fn run_input(input: &str) -> Vec<LogEntry> {
+    run(input).into_log()
+}
+
+/// Runs the input, returning only the [`ChangeEvent`](`vlc_http::client_state::ChangeEvent`)s
+pub fn run_input_events(input: &str) -> Vec<EventLogEntry> {
+    let mut runner = run(input);
+    let events = runner.model_logger.take_events();
+    // verify no pending actions
+    let _log = runner.into_log();
+    events
+}
+
+fn run(input: &str) -> Runner {
     let mut runner = Runner::default();
 
     for line in input.lines() {
@@ -30,7 +43,7 @@ pub fn run_input(input: &str) -> Vec<LogEntry> {
         runner.run_test_action(test_action, line);
     }
 
-    runner.into_log()
+    runner
 }
 
 #[derive(Default)]
@@ -47,6 +60,9 @@ struct Runner {
 enum ActionPending {
     NoOutput(vlc_http::goal::ActionPlan),
     ItemsOutput(vlc_http::goal::ActionQuerySetItems),
+    QueryArt(vlc_http::goal::QueryArt),
+    PlaylistSetCost(vlc_http::goal::QueryPlaylistSetCost),
+    DesiredState(vlc_http::goal::ActionDesiredState),
 }
 
 impl Runner {
@@ -67,6 +83,15 @@ impl Runner {
                 let endpoint = vlc_http::Command::art_endpoint(&item_id);
                 self.run_endpoint(endpoint);
             }
+            TestAction::Query {
+                query: Query::CurrentArt,
+            } => {
+                self.set_action_pending_or_bail(
+                    line,
+                    ActionPending::QueryArt(self.client_state.build_plan().query_art()),
+                );
+                self.run_pending_action(line);
+            }
             TestAction::Query {
                 query: Query::PlaylistSetQueryMatched(action_query),
             } => {
@@ -80,6 +105,34 @@ impl Runner {
                 );
                 self.run_pending_action(line);
             }
+            TestAction::Query {
+                query: Query::PlaylistSetCost(target),
+            } => {
+                self.set_action_pending_or_bail(
+                    line,
+                    ActionPending::PlaylistSetCost(
+                        self.client_state
+                            .build_plan()
+                            .query_playlist_set_cost(target.into()),
+                    ),
+                );
+                self.run_pending_action(line);
+            }
+            TestAction::Query {
+                query: Query::DesiredState(target),
+            } => {
+                let target = match vlc_http::goal::DesiredState::try_from(target) {
+                    Ok(target) => target,
+                    Err(e) => panic!("invalid desired state {line:?}: {e}"),
+                };
+                self.set_action_pending_or_bail(
+                    line,
+                    ActionPending::DesiredState(
+                        self.client_state.build_plan().apply_desired_state(target),
+                    ),
+                );
+                self.run_pending_action(line);
+            }
             TestAction::Action {
                 action,
                 extend_cache,
@@ -97,39 +150,61 @@ impl Runner {
                 );
                 self.run_pending_action(line);
             }
-            TestAction::Harness { override_command } => match override_command {
-                OverrideCommand::InitItems { items } => {
-                    self.model_logger
-                        .edit_model(|model| model.initialize_items(items));
-                }
-                OverrideCommand::ActionStepLimit { step_count } => {
-                    self.action_step_limit = Some(step_count);
-                }
-                OverrideCommand::ActionClearLimit => {
-                    self.action_step_limit = None;
-                }
-                OverrideCommand::ActionIgnorePush { push_count } => {
-                    let push_count = push_count.map_or(1, NonZeroU32::get);
-                    self.with_action_pending(line, |this, pollable| match pollable {
-                        ActionPending::NoOutput(inner) => this
-                            .ignore_endpoints(push_count, inner, line)
-                            .map(ActionPending::NoOutput),
-                        ActionPending::ItemsOutput(inner) => this
-                            .ignore_endpoints(push_count, inner, line)
-                            .map(ActionPending::ItemsOutput),
-                    });
-                }
-                OverrideCommand::ActionIgnorePop { pop_count } => {
-                    let pop_count = pop_count.map_or(1, NonZeroU32::get);
-                    for iter in 0..pop_count {
-                        let Some(endpoint) = self.action_ignored_endpoints.pop_front() else {
-                            panic!("invalid state for {line:?}: no ignored endpoint found for ActionApplyIgnored (iter {iter})")
-                        };
-                        self.run_endpoint(endpoint);
-                    }
+            TestAction::Harness { override_command } => {
+                self.run_override(override_command, line);
+            }
+        }
+    }
+    fn run_override(&mut self, override_command: OverrideCommand, line: &str) {
+        match override_command {
+            OverrideCommand::InitItems { items } => {
+                self.model_logger
+                    .edit_model(|model| model.initialize_items(items));
+            }
+            OverrideCommand::Restart => {
+                self.model_logger
+                    .edit_model(|model| *model = Model::default());
+            }
+            OverrideCommand::Advance { seconds } => {
+                self.model_logger
+                    .edit_model(|model| model.advance(Duration::from_secs(seconds)));
+            }
+            OverrideCommand::ActionStepLimit { step_count } => {
+                self.action_step_limit = Some(step_count);
+            }
+            OverrideCommand::ActionClearLimit => {
+                self.action_step_limit = None;
+            }
+            OverrideCommand::ActionIgnorePush { push_count } => {
+                let push_count = push_count.map_or(1, NonZeroU32::get);
+                self.with_action_pending(line, |this, pollable| match pollable {
+                    ActionPending::NoOutput(inner) => this
+                        .ignore_endpoints(push_count, inner, line)
+                        .map(ActionPending::NoOutput),
+                    ActionPending::ItemsOutput(inner) => this
+                        .ignore_endpoints(push_count, inner, line)
+                        .map(ActionPending::ItemsOutput),
+                    ActionPending::QueryArt(inner) => this
+                        .ignore_endpoints(push_count, inner, line)
+                        .map(ActionPending::QueryArt),
+                    ActionPending::PlaylistSetCost(inner) => this
+                        .ignore_endpoints(push_count, inner, line)
+                        .map(ActionPending::PlaylistSetCost),
+                    ActionPending::DesiredState(inner) => this
+                        .ignore_endpoints(push_count, inner, line)
+                        .map(ActionPending::DesiredState),
+                });
+            }
+            OverrideCommand::ActionIgnorePop { pop_count } => {
+                let pop_count = pop_count.map_or(1, NonZeroU32::get);
+                for iter in 0..pop_count {
+                    let Some(endpoint) = self.action_ignored_endpoints.pop_front() else {
+                        panic!("invalid state for {line:?}: no ignored endpoint found for ActionApplyIgnored (iter {iter})")
+                    };
+                    self.run_endpoint(endpoint);
                 }
-                OverrideCommand::ActionResume => self.run_pending_action(line),
-            },
+            }
+            OverrideCommand::ActionResume => self.run_pending_action(line),
         }
     }
     fn run_endpoint(&mut self, endpoint: Endpoint) {
@@ -166,6 +241,15 @@ impl Runner {
             ActionPending::ItemsOutput(inner) => this
                 .run_action_generic(inner, line)
                 .map(ActionPending::ItemsOutput),
+            ActionPending::QueryArt(inner) => this
+                .run_action_generic(inner, line)
+                .map(ActionPending::QueryArt),
+            ActionPending::PlaylistSetCost(inner) => this
+                .run_action_generic(inner, line)
+                .map(ActionPending::PlaylistSetCost),
+            ActionPending::DesiredState(inner) => this
+                .run_action_generic(inner, line)
+                .map(ActionPending::DesiredState),
         });
     }
     fn run_action_generic<T>(&mut self, mut pollable: T, line: &str) -> Option<T>
@@ -260,12 +344,13 @@ impl Runner {
     }
 }
 
+pub use model_logger::EventLogEntry;
 use model_logger::{LogEntry, ModelLogger};
 mod model_logger {
-    use super::Model;
+    use super::{Model, ModelResponse};
     use std::str::FromStr;
     use tracing::info;
-    use vlc_http::{ClientState, Endpoint, Response};
+    use vlc_http::{client_state::ChangeEvent, ClientState, Endpoint, Response};
 
     #[derive(Debug, PartialEq, Eq, serde::Serialize)]
     pub enum LogEntry {
@@ -278,10 +363,15 @@ mod model_logger {
         Output(serde_json::Value),
     }
 
+    #[derive(Debug, PartialEq, Eq, serde::Serialize)]
+    #[serde(rename = "Events")]
+    pub struct EventLogEntry(Endpoint, Vec<ChangeEvent>);
+
     #[derive(Default)]
     pub(super) struct ModelLogger {
         model: Model,
         log: Vec<LogEntry>,
+        events: Vec<EventLogEntry>,
     }
     impl ModelLogger {
         pub fn update_for(&mut self, endpoint: Endpoint, target: &mut ClientState) {
@@ -292,13 +382,33 @@ mod model_logger {
 
             let endpoint_str = endpoint.get_path_and_query();
 
-            let response_str = self.model.request(endpoint_str);
-            let response = match Response::from_str(&response_str) {
-                Ok(response) => response,
-                Err(e) => panic!("invalid response from model {response_str:?}: {e}"),
+            let response = match self.model.request(endpoint_str) {
+                ModelResponse::Json(response_str) => match Response::from_str(&response_str) {
+                    Ok(response) => Some(response),
+                    Err(e) => panic!("invalid response from model {response_str:?}: {e}"),
+                },
+                ModelResponse::Art(art) => {
+                    match Response::from_endpoint_body(
+                        &endpoint,
+                        ModelResponse::ART_CONTENT_TYPE,
+                        art.as_bytes(),
+                    ) {
+                        Ok(response) => Some(response),
+                        Err(e) => panic!("invalid art response from model {art:?}: {e}"),
+                    }
+                }
+                // NOTE: unknown endpoints are recorded in the model, leaving the state unchanged
+                ModelResponse::NotFound => {
+                    Response::from_endpoint_not_found(&endpoint).ok().flatten()
+                }
             };
 
-            target.update(response.clone());
+            if let Some(response) = response {
+                let events = target.update(response);
+                if !events.is_empty() {
+                    self.events.push(EventLogEntry(endpoint.clone(), events));
+                }
+            }
 
             let log_entry = LogEntry::Endpoint(endpoint, self.model.clone());
 
@@ -341,6 +451,9 @@ mod model_logger {
         pub fn into_log(self) -> Vec<LogEntry> {
             self.log
         }
+        pub fn take_events(&mut self) -> Vec<EventLogEntry> {
+            std::mem::take(&mut self.events)
+        }
     }
 }
 
@@ -375,7 +488,10 @@ enum TestAction {
 #[derive(clap::Subcommand, Debug)]
 enum Query {
     Art { item_id: String },
+    CurrentArt,
     PlaylistSetQueryMatched(vlc_http::clap::PlaylistSetQueryMatched),
+    PlaylistSetCost(vlc_http::clap::PlaylistSetQueryMatched),
+    DesiredState(vlc_http::clap::DesiredState),
 }
 /// Overrides to simulate anomalies in VLC server behavior
 #[derive(clap::Subcommand, Debug)]
@@ -383,6 +499,10 @@ enum OverrideCommand {
     /// One-time initialization of the items (to avoid tedious setup with "playlist-add" commands)
     #[clap(alias = "items")]
     InitItems { items: Vec<String> },
+    /// Advances the playback clock (to simulate time passing)
+    Advance { seconds: u64 },
+    /// Replaces the model with a fresh instance (to simulate VLC restarting)
+    Restart,
     /// Pauses future actions after the specified number of steps (for use in `ActionResume`)
     ///
     /// Errors if the test ends while an action is paused
@@ -1,7 +1,4 @@
 // Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
-pub use harness::run_input;
+pub use harness::{run_input, run_input_events};
 mod harness;
-
-pub use model::Model;
-pub mod model;
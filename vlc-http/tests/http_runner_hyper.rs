@@ -0,0 +1,197 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Runs plans using the `hyper` runner against the fake VLC server
+#![cfg(feature = "fake")]
+// yes panic, it's tests!
+#![allow(clippy::panic)]
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use vlc_http::{
+    asynchronous::{complete_plan, EndpointRequestor as _},
+    fake::{Model, ModelResponse, Server},
+    http_runner::hyper::{Error as HyperError, ErrorCategory, HttpRunner},
+    request::{AuthInput, Scheme},
+    Auth, ClientState, Command,
+};
+
+const PASSWORD: &str = "sentinel-password";
+const MAX_ITER_COUNT: usize = 100;
+
+/// Serves the [`Model`] over HTTP, requiring the [`PASSWORD`]
+fn spawn_fake(model: Model) -> SocketAddr {
+    let listener =
+        std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).expect("bind");
+    let address = listener.local_addr().expect("local address");
+    tokio::spawn(Server::new(model, PASSWORD).serve(listener));
+    address
+}
+
+fn runner_for(address: SocketAddr, password: &str) -> HttpRunner {
+    let auth = Auth::new(AuthInput::new(
+        password.to_owned(),
+        address.ip().to_string(),
+        address.port(),
+    ))
+    .expect("valid auth");
+    HttpRunner::new(auth)
+}
+
+#[tokio::test]
+async fn query_playback() {
+    let address = spawn_fake(Model::default());
+    let mut runner = runner_for(address, PASSWORD);
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    runner.set_observe_responses_str({
+        let observed = Arc::clone(&observed);
+        Box::new(move |response| observed.lock().expect("lock").push(response.to_owned()))
+    });
+
+    let mut client_state = ClientState::new();
+    let plan = client_state.build_plan().query_playback();
+    let status = complete_plan(plan, &mut client_state, &mut runner, MAX_ITER_COUNT)
+        .await
+        .expect("query succeeds");
+    let current_item = status
+        .information
+        .as_ref()
+        .and_then(|info| info.playlist_item_id);
+    assert_eq!(current_item, None);
+
+    let observed = observed.lock().expect("lock");
+    assert_eq!(observed.len(), 1);
+    assert!(observed[0].contains("\"apiversion\""), "{observed:?}");
+}
+
+#[tokio::test]
+async fn play_then_art() {
+    let mut model = Model::default();
+    model.initialize_items(vec!["file:///a", "file:///b"]);
+    let address = spawn_fake(model);
+    let mut runner = runner_for(address, PASSWORD);
+
+    let mut client_state = ClientState::new();
+    let play_endpoint = Command::PlaylistPlay { item_id: Some(1) }.into_endpoint();
+    let response = runner.request(play_endpoint).await.expect("play succeeds");
+    client_state.update(response);
+
+    let plan = client_state.build_plan().query_art();
+    let art = complete_plan(plan, &mut client_state, &mut runner, MAX_ITER_COUNT)
+        .await
+        .expect("art query succeeds")
+        .expect("current item has art");
+    assert_eq!(art.playlist_item_id(), 1);
+    assert_eq!(art.content_type(), Some(ModelResponse::ART_CONTENT_TYPE));
+    assert_eq!(art.data(), Some(&b"art1"[..]));
+}
+
+#[tokio::test]
+async fn art_not_found() {
+    let mut model = Model::default();
+    model.initialize_items(vec!["file:///a", "file:///b"]);
+    let address = spawn_fake(model);
+    let mut runner = runner_for(address, PASSWORD);
+
+    let mut client_state = ClientState::new();
+    // current item is deleted while playing, so it has no art
+    for command in [
+        Command::PlaylistPlay { item_id: Some(1) },
+        Command::PlaylistDelete { item_id: 1 },
+    ] {
+        let response = runner
+            .request(command.into_endpoint())
+            .await
+            .expect("command succeeds");
+        client_state.update(response);
+    }
+
+    let plan = client_state.build_plan().query_art();
+    let art = complete_plan(plan, &mut client_state, &mut runner, MAX_ITER_COUNT)
+        .await
+        .expect("missing art is not an error")
+        .expect("current item exists");
+    assert_eq!(art.playlist_item_id(), 1);
+    assert!(art.is_absent());
+    assert_eq!(art.data(), None);
+
+    let unknown: vlc_http::Endpoint =
+        serde_json::from_str(r#"{"path_and_query":"/unknown"}"#).expect("valid endpoint");
+    let error = runner
+        .request(unknown)
+        .await
+        .expect_err("unknown endpoint not found");
+    assert_eq!(error.category(), ErrorCategory::BadResponse);
+    assert_eq!(error.to_string(), "unexpected status 404 Not Found");
+}
+
+#[tokio::test]
+async fn iteration_limit() {
+    let address = spawn_fake(Model::default());
+    let mut runner = runner_for(address, PASSWORD);
+
+    let mut client_state = ClientState::new();
+    let plan = client_state.build_plan().query_playback();
+    let error = complete_plan(plan, &mut client_state, &mut runner, 0)
+        .await
+        .expect_err("zero iterations allowed");
+    assert!(error.endpoint_error().is_none());
+    assert!(
+        error.to_string().contains("exceeded iteration count"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn wrong_password() {
+    let address = spawn_fake(Model::default());
+    let mut runner = runner_for(address, "wrong-password");
+
+    let mut client_state = ClientState::new();
+    let plan = client_state.build_plan().query_playback();
+    let error = complete_plan(plan, &mut client_state, &mut runner, MAX_ITER_COUNT)
+        .await
+        .expect_err("password rejected");
+    let category = error.endpoint_error().map(HyperError::category);
+    assert_eq!(category, Some(ErrorCategory::Unauthorized));
+}
+
+#[tokio::test]
+async fn unreachable() {
+    let address = {
+        let listener =
+            std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).expect("bind");
+        listener.local_addr().expect("local address")
+        // dropped, nothing is listening
+    };
+    let mut runner = runner_for(address, PASSWORD);
+
+    let mut client_state = ClientState::new();
+    let plan = client_state.build_plan().query_playback();
+    let error = complete_plan(plan, &mut client_state, &mut runner, MAX_ITER_COUNT)
+        .await
+        .expect_err("nothing listening");
+    let category = error.endpoint_error().map(HyperError::category);
+    assert_eq!(category, Some(ErrorCategory::Unreachable));
+}
+
+#[tokio::test]
+async fn https_unsupported() {
+    let address = spawn_fake(Model::default());
+    let mut input = AuthInput::new(
+        PASSWORD.to_owned(),
+        address.ip().to_string(),
+        address.port(),
+    );
+    input.scheme = Scheme::Https;
+    let mut runner = HttpRunner::new(Auth::new(input).expect("valid auth"));
+
+    let error = runner
+        .request(Command::PlaybackPause.into_endpoint())
+        .await
+        .expect_err("no TLS support");
+    assert_eq!(error.category(), ErrorCategory::Unsupported);
+    insta::assert_snapshot!(error, @"HTTPS is not supported by the hyper runner, use the ureq runner");
+}
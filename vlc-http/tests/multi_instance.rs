@@ -0,0 +1,373 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Runs the multi-instance [`Coordinator`] against several fake VLC [`Model`]s
+#![cfg(feature = "fake")]
+// yes panic, it's tests!
+#![allow(clippy::panic)]
+
+use std::{
+    str::FromStr as _,
+    time::{Duration, Instant},
+};
+use vlc_http::{
+    client_state::ChangeEvent,
+    fake::{Model, ModelResponse},
+    goal::TargetPlaylistItems,
+    multi::Coordinator,
+    Command, Response,
+};
+
+const ROOMS: [&str; 3] = ["kitchen", "office", "patio"];
+const MAX_ITER_COUNT: usize = 100;
+
+struct Rooms {
+    coordinator: Coordinator<&'static str>,
+    models: Vec<Model>,
+    /// Fake clock for receiving responses (models advance separately)
+    now: Instant,
+}
+impl Rooms {
+    fn new() -> Self {
+        Self::new_with(Coordinator::new())
+    }
+    /// Adds the rooms to the coordinator (e.g. after setting a playlist)
+    fn new_with(mut coordinator: Coordinator<&'static str>) -> Self {
+        let mut models = vec![];
+        for (index, room) in ROOMS.into_iter().enumerate() {
+            coordinator.add_instance(room).expect("unique room");
+            // different existing items, so playlist IDs differ between rooms
+            let mut model = Model::default();
+            model.initialize_items((0..=index).map(|n| format!("file:///old{n}")).collect());
+            models.push(model);
+        }
+        Self {
+            coordinator,
+            models,
+            now: Instant::now(),
+        }
+    }
+    fn run(&mut self) {
+        for index in 0..ROOMS.len() {
+            self.run_room(index);
+        }
+        assert!(!self.coordinator.is_pending(), "iteration limit exceeded");
+    }
+    fn run_room(&mut self, index: usize) {
+        let room = &ROOMS[index];
+        let model = &mut self.models[index];
+        for _ in 0..MAX_ITER_COUNT {
+            let Some(endpoint) = self
+                .coordinator
+                .next_endpoint_at(room, self.now)
+                .expect("valid plan")
+            else {
+                break;
+            };
+            let ModelResponse::Json(body) = model.request(endpoint.get_path_and_query()) else {
+                panic!("unexpected non-JSON response for {endpoint:?}");
+            };
+            let response = Response::from_str(&body).expect("valid response");
+            self.coordinator
+                .update_at(room, response, self.now)
+                .expect("known room");
+        }
+    }
+    /// Advances the clock and all models
+    fn advance(&mut self, elapsed: Duration) {
+        self.now += elapsed;
+        for model in &mut self.models {
+            model.advance(elapsed);
+        }
+    }
+    fn drift_deltas(&self) -> Vec<Option<i64>> {
+        self.coordinator
+            .drift_at(self.now)
+            .into_iter()
+            .map(|(_, drift)| drift.position_delta_secs)
+            .collect()
+    }
+}
+
+fn url(s: &str) -> url::Url {
+    url::Url::parse(s).expect("valid url")
+}
+
+#[test]
+fn converges_playlists() {
+    let mut rooms = Rooms::new();
+    let target = TargetPlaylistItems::new().set_urls(vec![
+        url("file:///a"),
+        url("file:///b"),
+        url("file:///c"),
+    ]);
+    rooms.coordinator.set_playlist(&target);
+    rooms
+        .coordinator
+        .push_command(&Command::PlaylistPlay { item_id: None })
+        .expect("no item ID");
+    rooms.run();
+
+    insta::assert_ron_snapshot!(rooms.models, @r###"
+    [
+      Model(
+        items: {
+          1: "file:///a",
+          2: "file:///b",
+          3: "file:///c",
+        },
+        current_item_id: Some((1, Playing)),
+      ),
+      Model(
+        items: {
+          2: "file:///a",
+          3: "file:///b",
+          4: "file:///c",
+        },
+        current_item_id: Some((2, Playing)),
+      ),
+      Model(
+        items: {
+          3: "file:///a",
+          4: "file:///b",
+          5: "file:///c",
+        },
+        current_item_id: Some((3, Playing)),
+      ),
+    ]
+    "###);
+    insta::assert_ron_snapshot!(rooms.coordinator.drift_at(rooms.now), @r###"
+    [
+      ("kitchen", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(0),
+      )),
+      ("office", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(0),
+      )),
+      ("patio", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(0),
+      )),
+    ]
+    "###);
+}
+
+#[test]
+fn reapplies_playlist() {
+    let target = TargetPlaylistItems::new().set_urls(vec![url("file:///a"), url("file:///b")]);
+    // rooms added after the playlist target
+    let mut coordinator = Coordinator::new();
+    coordinator.set_playlist(&target);
+    let mut rooms = Rooms::new_with(coordinator);
+    assert!(rooms.coordinator.is_pending());
+    rooms.run();
+
+    // office restarts, reusing playlist IDs for other items
+    let mut model = Model::default();
+    model.initialize_items(vec!["file:///other0", "file:///other1"]);
+    rooms.models[1] = model;
+    let ModelResponse::Json(body) = rooms.models[1].request("/requests/playlist.json") else {
+        panic!("unexpected non-JSON playlist response");
+    };
+    let events = rooms
+        .coordinator
+        .update_at(
+            &"office",
+            Response::from_str(&body).expect("valid response"),
+            rooms.now,
+        )
+        .expect("known room");
+    assert!(events.contains(&ChangeEvent::VlcRestarted), "{events:?}");
+    assert!(rooms.coordinator.is_pending());
+    rooms.run();
+
+    insta::assert_ron_snapshot!(rooms.models, @r###"
+    [
+      Model(
+        items: {
+          1: "file:///a",
+          2: "file:///b",
+        },
+      ),
+      Model(
+        items: {
+          2: "file:///a",
+          3: "file:///b",
+        },
+      ),
+      Model(
+        items: {
+          3: "file:///a",
+          4: "file:///b",
+        },
+      ),
+    ]
+    "###);
+}
+
+#[test]
+fn reports_drift() {
+    let mut rooms = Rooms::new();
+    let target = TargetPlaylistItems::new().set_urls(vec![
+        url("file:///a"),
+        url("file:///b"),
+        url("file:///c"),
+    ]);
+    rooms.coordinator.set_playlist(&target);
+    rooms
+        .coordinator
+        .push_command(&Command::PlaylistPlay { item_id: None })
+        .expect("no item ID");
+    rooms.run();
+
+    // office lags behind the leader (kitchen)
+    rooms.models[0].advance(Duration::from_secs(5));
+    rooms.models[1].advance(Duration::from_secs(2));
+    rooms.models[2].advance(Duration::from_secs(5));
+    rooms.coordinator.push_status_query();
+    rooms.run();
+    insta::assert_ron_snapshot!(rooms.coordinator.drift_at(rooms.now), @r###"
+    [
+      ("kitchen", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(0),
+      )),
+      ("office", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(-3),
+      )),
+      ("patio", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(0),
+      )),
+    ]
+    "###);
+
+    // patio moves to the next item
+    rooms.models[2].advance(Duration::from_secs(400));
+    rooms
+        .coordinator
+        .push_command(&Command::PlaybackPause)
+        .expect("no item ID");
+    rooms.run();
+    insta::assert_ron_snapshot!(rooms.coordinator.drift_at(rooms.now), @r###"
+    [
+      ("kitchen", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(0),
+      )),
+      ("office", Drift(
+        current_url: Some("file:///a"),
+        same_track: true,
+        position_delta_secs: Some(-3),
+      )),
+      ("patio", Drift(
+        current_url: Some("file:///b"),
+        same_track: false,
+        position_delta_secs: None,
+      )),
+    ]
+    "###);
+}
+
+#[test]
+fn rejects_item_id_commands() {
+    let mut rooms = Rooms::new();
+    for command in [
+        Command::PlaylistPlay { item_id: Some(1) },
+        Command::PlaylistDelete { item_id: 1 },
+    ] {
+        let error = rooms
+            .coordinator
+            .push_command(&command)
+            .expect_err("item IDs differ between rooms");
+        assert!(
+            error.to_string().starts_with("playlist item IDs differ"),
+            "{error}"
+        );
+    }
+    assert!(!rooms.coordinator.is_pending());
+}
+
+#[test]
+fn drift_extrapolates_status_age() {
+    let mut rooms = Rooms::new();
+    rooms
+        .coordinator
+        .set_playlist(&TargetPlaylistItems::new().set_urls(vec![url("file:///a")]));
+    rooms
+        .coordinator
+        .push_command(&Command::PlaylistPlay { item_id: None })
+        .expect("no item ID");
+    rooms.run();
+
+    rooms.advance(Duration::from_secs(5));
+    rooms.coordinator.push_status_query();
+    rooms.run();
+    assert_eq!(rooms.drift_deltas(), [Some(0), Some(0), Some(0)]);
+
+    // leader (kitchen) status is older, but still in sync
+    rooms.advance(Duration::from_secs(10));
+    rooms.coordinator.push_status_query();
+    rooms.run_room(1);
+    rooms.run_room(2);
+    assert_eq!(rooms.drift_deltas(), [Some(0), Some(0), Some(0)]);
+
+    // later, with all statuses received
+    rooms.advance(Duration::from_secs(3));
+    rooms.run_room(0);
+    assert_eq!(rooms.drift_deltas(), [Some(0), Some(0), Some(0)]);
+}
+
+#[test]
+fn commands_mark_estimates_stale() {
+    let mut rooms = Rooms::new();
+    rooms
+        .coordinator
+        .set_playlist(&TargetPlaylistItems::new().set_urls(vec![url("file:///a")]));
+    rooms
+        .coordinator
+        .push_command(&Command::PlaylistPlay { item_id: None })
+        .expect("no item ID");
+    rooms.run();
+    rooms.advance(Duration::from_secs(1));
+    rooms.coordinator.push_status_query();
+    rooms.run();
+    let is_stale = |rooms: &Rooms| {
+        ROOMS
+            .iter()
+            .map(|room| {
+                rooms
+                    .coordinator
+                    .client_state(room)
+                    .and_then(|state| state.estimate_playback(rooms.now))
+                    .expect("status received")
+                    .is_stale()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(is_stale(&rooms), [false, false, false]);
+
+    rooms.advance(Duration::from_secs(1));
+    rooms
+        .coordinator
+        .push_command(&Command::PlaybackPause)
+        .expect("no item ID");
+    rooms.run_room(0);
+    assert_eq!(is_stale(&rooms), [true, false, false]);
+
+    rooms.run_room(1);
+    rooms.run_room(2);
+    rooms.advance(Duration::from_secs(1));
+    rooms.coordinator.push_status_query();
+    rooms.run();
+    assert_eq!(is_stale(&rooms), [false, false, false]);
+}
@@ -0,0 +1,159 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Runs [`Change::PlaylistSet`] on generated playlists, checking the reported cost matches the
+//! commands sent
+#![cfg(feature = "fake")]
+// yes panic, it's tests!
+#![allow(clippy::panic)]
+
+use std::str::FromStr as _;
+use vlc_http::{
+    fake::{Model, ModelResponse},
+    goal::{PlaylistSetCost, TargetPlaylistItems},
+    sync::complete_plan,
+    Change, ClientState, Command, Endpoint, Response,
+};
+
+const CASE_COUNT: usize = 500;
+const MAX_ITER_COUNT: usize = 100;
+const URLS: [&str; 5] = [
+    "file:///a",
+    "file:///b",
+    "file:///c",
+    "file:///d",
+    "file:///e",
+];
+
+/// Deterministic pseudo-random numbers (linear congruential generator)
+struct Lcg(u64);
+impl Lcg {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let upper = usize::try_from(self.0 >> 33).expect("32 bits fit in usize");
+        upper % bound
+    }
+    fn urls(&mut self, max_len: usize) -> Vec<&'static str> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| URLS[self.below(URLS.len())]).collect()
+    }
+}
+
+#[derive(Debug)]
+struct Case {
+    items: Vec<&'static str>,
+    playing_index: Option<usize>,
+    target: Vec<&'static str>,
+    keep_history: u16,
+}
+impl Case {
+    fn generate(rng: &mut Lcg) -> Self {
+        let items = rng.urls(8);
+        let playing_index = match items.len() {
+            0 => None,
+            len => Some(rng.below(len)).filter(|_| rng.below(2) == 0),
+        };
+        let target = rng.urls(5);
+        let keep_history = u16::try_from(rng.below(4)).expect("small");
+        Self {
+            items,
+            playing_index,
+            target,
+            keep_history,
+        }
+    }
+    fn target(&self, minimize_edits: bool) -> TargetPlaylistItems {
+        let urls = self.target.iter().map(|&url| url_parse(url)).collect();
+        TargetPlaylistItems::new()
+            .set_urls(urls)
+            .set_keep_history(self.keep_history)
+            .set_minimize_edits(minimize_edits)
+    }
+    /// Returns the reported cost, and the number of playlist commands sent
+    fn run(&self, minimize_edits: bool) -> (PlaylistSetCost, usize) {
+        let mut model = Model::default();
+        model.initialize_items(self.items.clone());
+        if let Some(index) = self.playing_index {
+            let item_id = Some(u64::try_from(index).expect("small"));
+            let endpoint = Endpoint::from(Command::PlaylistPlay { item_id });
+            model.request(endpoint.get_path_and_query());
+        }
+
+        let mut command_count = 0;
+        let mut requestor = |endpoint: Endpoint| {
+            let path_and_query = endpoint.get_path_and_query();
+            if path_and_query.contains("command=in_enqueue")
+                || path_and_query.contains("command=pl_delete")
+            {
+                command_count += 1;
+            }
+            let ModelResponse::Json(body) = model.request(path_and_query) else {
+                panic!("unexpected non-JSON response for {endpoint:?}");
+            };
+            Response::from_str(&body)
+        };
+
+        let mut client_state = ClientState::new();
+        let cost_plan = |client_state: &ClientState| {
+            client_state
+                .build_plan()
+                .query_playlist_set_cost(self.target(minimize_edits))
+        };
+        let cost = complete_plan(
+            cost_plan(&client_state),
+            &mut client_state,
+            &mut requestor,
+            MAX_ITER_COUNT,
+        )
+        .expect("cost query completes");
+
+        let set_plan = client_state
+            .build_plan()
+            .apply(Change::PlaylistSet(self.target(minimize_edits)));
+        complete_plan(set_plan, &mut client_state, &mut requestor, MAX_ITER_COUNT)
+            .expect("playlist set completes");
+
+        let remaining = complete_plan(
+            cost_plan(&client_state),
+            &mut client_state,
+            &mut requestor,
+            MAX_ITER_COUNT,
+        )
+        .expect("cost query completes");
+        assert_eq!(remaining.total(), 0, "target reached for {self:?}");
+
+        (cost, command_count)
+    }
+}
+
+fn url_parse(s: &str) -> url::Url {
+    url::Url::parse(s).expect("valid url")
+}
+
+#[test]
+fn reported_cost_matches_commands() {
+    let mut rng = Lcg(0x5EED);
+    let mut saved_total = 0;
+    for _ in 0..CASE_COUNT {
+        let case = Case::generate(&mut rng);
+
+        let (default_cost, default_count) = case.run(false);
+        let (minimized_cost, minimized_count) = case.run(true);
+
+        assert_eq!(default_cost.total(), default_count, "default {case:?}");
+        assert_eq!(
+            minimized_cost.total(),
+            minimized_count,
+            "minimized {case:?}"
+        );
+        assert!(
+            minimized_count <= default_count,
+            "minimized {minimized_count} > default {default_count} for {case:?}"
+        );
+        saved_total += default_count - minimized_count;
+    }
+    // generated cases include some where a later match is cheaper
+    assert!(saved_total > 0, "no commands saved");
+}
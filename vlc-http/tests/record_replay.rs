@@ -0,0 +1,184 @@
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+
+//! Records exchanges with a fake VLC [`Model`], then replays them without the model
+#![cfg(feature = "fake")]
+// yes panic, it's tests!
+#![allow(clippy::panic)]
+
+use vlc_http::{
+    fake::Model,
+    goal::TargetPlaylistItems,
+    http_runner::ErrorCategory,
+    record::{RawEndpointRequestor, RawResponse, Recorder, Replayer},
+    sync::{complete_plan, EndpointRequestor},
+    Change, ClientState, Endpoint,
+};
+
+const MAX_ITER_COUNT: usize = 100;
+
+fn url(s: &str) -> url::Url {
+    url::Url::parse(s).expect("valid url")
+}
+fn target(urls: &[&str]) -> Change {
+    let urls = urls.iter().copied().map(url).collect();
+    Change::PlaylistSet(TargetPlaylistItems::new().set_urls(urls))
+}
+/// Applies the change, then queries the resulting playlist URLs
+fn run<R>(requestor: &mut R, change: Change) -> Result<Vec<String>, String>
+where
+    R: EndpointRequestor,
+    R::Error: std::error::Error + 'static,
+{
+    let source_str = |error: &dyn std::error::Error| {
+        error
+            .source()
+            .map_or_else(|| error.to_string(), ToString::to_string)
+    };
+    let mut client_state = ClientState::new();
+    let plan = client_state.build_plan().apply(change);
+    complete_plan(plan, &mut client_state, requestor, MAX_ITER_COUNT)
+        .map_err(|error| source_str(&error))?;
+    let plan = client_state.build_plan().query_playlist();
+    let items = complete_plan(plan, &mut client_state, requestor, MAX_ITER_COUNT)
+        .map_err(|error| source_str(&error))?;
+    Ok(items
+        .iter()
+        .map(|item| item.get_url().to_string())
+        .collect())
+}
+
+fn record(change: Change) -> (Vec<u8>, Vec<String>) {
+    let mut model = Model::default();
+    model.initialize_items(vec!["file:///old"]);
+    let model_requestor = |endpoint: &Endpoint| {
+        let response = model.request(endpoint.get_path_and_query());
+        Ok::<_, std::convert::Infallible>(RawResponse {
+            content_type: response.content_type().to_owned(),
+            body: response.into_body().into_bytes(),
+        })
+    };
+    let mut recorder = Recorder::new(model_requestor, vec![]);
+    let urls = run(&mut recorder, change).expect("recording completes");
+
+    let (_, recording) = recorder.into_inner();
+    (recording, urls)
+}
+
+#[test]
+fn replays_recording() {
+    let change = || target(&["file:///a", "file:///b"]);
+    let (recording, recorded_urls) = record(change());
+
+    let endpoints: Vec<String> = std::str::from_utf8(&recording)
+        .expect("utf8 recording")
+        .lines()
+        .map(|line| {
+            let exchange: serde_json::Value = serde_json::from_str(line).expect("valid json");
+            exchange["endpoint"]["path_and_query"].to_string()
+        })
+        .collect();
+    insta::assert_snapshot!(endpoints.join("\n"), @r###"
+    "/requests/status.json"
+    "/requests/playlist.json"
+    "/requests/playlist.json?command=pl_delete&id=0"
+    "/requests/playlist.json?command=in_enqueue&input=file%3A%2F%2F%2Fa"
+    "/requests/playlist.json?command=in_enqueue&input=file%3A%2F%2F%2Fb"
+    "/requests/playlist.json"
+    "###);
+
+    let mut replayer = Replayer::from_reader(&recording[..]).expect("valid recording");
+    let urls = run(&mut replayer, change()).expect("replay completes");
+
+    assert_eq!(replayer.remaining(), 0);
+    assert_eq!(urls, recorded_urls);
+}
+
+#[test]
+fn replay_mismatch() {
+    let (recording, _) = record(target(&["file:///a", "file:///b"]));
+
+    let mut replayer = Replayer::from_reader(&recording[..]).expect("valid recording");
+    let error = run(&mut replayer, target(&["file:///a", "file:///c"]))
+        .expect_err("differs from recording");
+    insta::assert_snapshot!(error, @r#"recording expected "/requests/playlist.json?command=in_enqueue&input=file%3A%2F%2F%2Fb", requested "/requests/playlist.json?command=in_enqueue&input=file%3A%2F%2F%2Fc""#);
+}
+
+#[test]
+fn replay_exhausted() {
+    let (recording, _) = record(target(&["file:///a"]));
+    let first_line = recording
+        .split(|&byte| byte == b'\n')
+        .next()
+        .expect("nonempty recording");
+
+    let mut replayer = Replayer::from_reader(first_line).expect("valid recording");
+    let error = run(&mut replayer, target(&["file:///a"])).expect_err("recording too short");
+    insta::assert_snapshot!(error, @r#"recording exhausted, requested "/requests/playlist.json""#);
+}
+
+#[test]
+fn load_error_line_number() {
+    let recording = br#"
+{"endpoint":{"path_and_query":"/"},"content_type":"","text":""}
+not json
+"#;
+    let error = Replayer::from_reader(&recording[..]).expect_err("invalid line");
+    insta::assert_snapshot!(error, @"invalid exchange on line 3");
+}
+
+/// Responds to every request with the HTTP status
+struct FailingRequestor {
+    status: u16,
+}
+#[derive(Debug)]
+struct StatusError(u16);
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "status {}", self.0)
+    }
+}
+impl std::error::Error for StatusError {}
+impl RawEndpointRequestor for FailingRequestor {
+    type Error = StatusError;
+    fn request_raw(&mut self, _endpoint: &Endpoint) -> Result<RawResponse, Self::Error> {
+        Err(StatusError(self.status))
+    }
+    fn error_status(error: &Self::Error) -> Option<u16> {
+        Some(error.0)
+    }
+}
+
+#[test]
+fn replays_failure() {
+    let mut recorder = Recorder::new(FailingRequestor { status: 401 }, vec![]);
+    let error = recorder
+        .request(vlc_http::Command::PlaybackPause.into_endpoint())
+        .expect_err("request fails");
+    let error = error.into_request_error().expect("request error");
+    assert_eq!(error.to_string(), "status 401");
+
+    let (_, recording) = recorder.into_inner();
+    insta::assert_snapshot!(std::str::from_utf8(&recording).expect("utf8 recording"), @r#"{"endpoint":{"path_and_query":"/requests/status.json?command=pl_forcepause"},"error":{"status":401,"message":"status 401"}}"#);
+
+    let mut replayer = Replayer::from_reader(&recording[..]).expect("valid recording");
+    let error = replayer
+        .request(vlc_http::Command::PlaybackPause.into_endpoint())
+        .expect_err("recorded failure");
+    assert_eq!(error.recorded_status(), Some(401));
+    assert_eq!(error.category(), Some(ErrorCategory::Unauthorized));
+    insta::assert_snapshot!(error, @"recorded request failed with status 401: status 401");
+}
+
+#[test]
+fn replays_art_not_found() {
+    let art = vlc_http::Command::art_endpoint("5");
+    let mut recorder = Recorder::new(FailingRequestor { status: 404 }, vec![]);
+    let response = recorder.request(art.clone()).expect("absent art");
+
+    let (_, recording) = recorder.into_inner();
+    let mut replayer = Replayer::from_reader(&recording[..]).expect("valid recording");
+    let replay_response = replayer.request(art).expect("absent art");
+    let response = format!("{response:?}");
+    assert!(response.contains("absent: true"), "{response}");
+    assert_eq!(format!("{replay_response:?}"), response);
+}
@@ -1,7 +1,8 @@
-// Copyright (C) 2021-2024  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
+// Copyright (C) 2021-2025  Daniel Lambert. Licensed under GPL-3.0-or-later, see /COPYING file for details
 
 //! Test case runner for `vlc_http`
 
+#![cfg(feature = "fake")]
 // yes panic, it's tests!
 #![allow(clippy::panic)]
 
@@ -15,6 +16,11 @@ fn test_cases() {
     insta::glob!("inputs/*.txt", test_case);
 }
 
+#[test]
+fn change_events() {
+    insta::glob!("inputs/*.txt", change_events_case);
+}
+
 fn test_case(input: &std::path::Path) {
     let name = input
         .file_name()
@@ -31,3 +37,9 @@ fn test_case(input: &std::path::Path) {
     let output = common::run_input(&input);
     insta::assert_ron_snapshot!(output);
 }
+
+fn change_events_case(input: &std::path::Path) {
+    let input = std::fs::read_to_string(input).expect("test input file exists");
+    let events = common::run_input_events(&input);
+    insta::assert_ron_snapshot!(events);
+}